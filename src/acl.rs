@@ -0,0 +1,444 @@
+//! POSIX ACLs from `--acl` and `--default-acl`, written in setfacl's syntax.
+//!
+//! Entries are merged into the ACL a directory already has, as `setfacl -m`
+//! does, and written straight to the `system.posix_acl_access` and
+//! `system.posix_acl_default` extended attributes.
+
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use crate::owner::{Id, Owner};
+
+const ACCESS: &str = "system.posix_acl_access";
+const DEFAULT: &str = "system.posix_acl_default";
+
+/// The only version of the xattr encoding Linux knows.
+const VERSION: u32 = 2;
+const UNDEFINED_ID: u32 = u32::MAX;
+
+/// Entries to add to, or replace in, a directory's ACL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    pub entries: Vec<Entry>,
+}
+
+/// One entry such as `g:devs:rwx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub tag: Tag,
+    /// `rwx` as the bits 4, 2 and 1.
+    pub perms: u16,
+}
+
+/// Who an entry applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tag {
+    /// The owning user, `u::`.
+    UserObj,
+    User(Id),
+    /// The owning group, `g::`.
+    GroupObj,
+    Group(Id),
+    /// The most any named entry or the owning group can be granted.
+    Mask,
+    Other,
+}
+
+impl Acl {
+    /// Parses comma-separated entries such as `u:alice:rwx,g:devs:r-x,o::-`.
+    ///
+    /// Entries starting with `d:` or `default:` are returned separately, for
+    /// the default ACL, so `--acl d:g:devs:rwx` works like setfacl.
+    pub fn parse(s: &str) -> Result<(Acl, Acl), String> {
+        let mut access = Acl::default();
+        let mut default = Acl::default();
+        for text in s.split(',').map(str::trim) {
+            let (acl, entry) = match text
+                .strip_prefix("default:")
+                .or_else(|| text.strip_prefix("d:"))
+            {
+                Some(rest) => (&mut default, rest),
+                None => (&mut access, text),
+            };
+            let entry =
+                parse_entry(entry).map_err(|e| format!("invalid entry `{}`: {}", text, e))?;
+            acl.entries.push(entry);
+        }
+        Ok((access, default))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds entries given later on the command line.
+    pub fn extend(&mut self, other: Acl) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Reads the access or default ACL of `path`, if it has one.
+    pub fn read(path: &Path, default: bool) -> io::Result<Option<Acl>> {
+        let name = if default { DEFAULT } else { ACCESS };
+        match xattr::get(path, name)? {
+            Some(value) => decode(&value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Merges these entries into the access or default ACL of `path`.
+    ///
+    /// A missing ACL starts from the permission bits, and the mask is
+    /// recalculated unless one was given, as `setfacl -m` does.
+    pub fn apply(&self, path: &Path, default: bool) -> io::Result<()> {
+        let mode = fs::metadata(path)?.permissions().mode();
+        let mut acl = match Acl::read(path, default)? {
+            Some(acl) => acl,
+            // A new default ACL starts from the access ACL, like setfacl's
+            None if default => Acl::read(path, false)?
+                .map(|acl| acl.base(mode))
+                .unwrap_or_else(|| Acl::from_mode(mode)),
+            None => Acl::from_mode(mode),
+        };
+
+        for entry in &self.entries {
+            match acl
+                .entries
+                .iter_mut()
+                .find(|e| e.tag.key() == entry.tag.key())
+            {
+                Some(existing) => existing.perms = entry.perms,
+                None => acl.entries.push(entry.clone()),
+            }
+        }
+
+        let named = acl
+            .entries
+            .iter()
+            .any(|e| matches!(e.tag, Tag::User(_) | Tag::Group(_)));
+        let has_mask = acl.entries.iter().any(|e| e.tag == Tag::Mask);
+        if !self.entries.iter().any(|e| e.tag == Tag::Mask) && (named || has_mask) {
+            let perms = acl
+                .entries
+                .iter()
+                .filter(|e| matches!(e.tag, Tag::User(_) | Tag::Group(_) | Tag::GroupObj))
+                .fold(0, |perms, e| perms | e.perms);
+            acl.entries.retain(|e| e.tag != Tag::Mask);
+            acl.entries.push(Entry {
+                tag: Tag::Mask,
+                perms,
+            });
+        }
+
+        let name = if default { DEFAULT } else { ACCESS };
+        xattr::set(path, name, &acl.encode())
+    }
+
+    /// The minimal ACL equivalent to permission bits.
+    fn from_mode(mode: u32) -> Acl {
+        let perms = |shift: u32| ((mode >> shift) & 0o7) as u16;
+        Acl {
+            entries: vec![
+                Entry {
+                    tag: Tag::UserObj,
+                    perms: perms(6),
+                },
+                Entry {
+                    tag: Tag::GroupObj,
+                    perms: perms(3),
+                },
+                Entry {
+                    tag: Tag::Other,
+                    perms: perms(0),
+                },
+            ],
+        }
+    }
+
+    /// The owner, owning group and other entries of an access ACL.
+    fn base(self, mode: u32) -> Acl {
+        let mut base = Acl::from_mode(mode);
+        for entry in self.entries {
+            if let Some(existing) = base.entries.iter_mut().find(|e| e.tag == entry.tag) {
+                existing.perms = entry.perms;
+            }
+        }
+        base
+    }
+
+    fn encode(mut self) -> Vec<u8> {
+        // The kernel wants entries sorted by tag, then id
+        self.entries.sort_by_key(|e| e.tag.key());
+        let mut value = VERSION.to_le_bytes().to_vec();
+        for entry in &self.entries {
+            let (tag, id) = entry.tag.key();
+            value.extend_from_slice(&tag.to_le_bytes());
+            value.extend_from_slice(&entry.perms.to_le_bytes());
+            value.extend_from_slice(&id.to_le_bytes());
+        }
+        value
+    }
+}
+
+impl Tag {
+    /// The tag and id stored in the xattr, which identify an entry.
+    fn key(&self) -> (u16, u32) {
+        match self {
+            Tag::UserObj => (0x01, UNDEFINED_ID),
+            Tag::User(user) => (0x02, user.id),
+            Tag::GroupObj => (0x04, UNDEFINED_ID),
+            Tag::Group(group) => (0x08, group.id),
+            Tag::Mask => (0x10, UNDEFINED_ID),
+            Tag::Other => (0x20, UNDEFINED_ID),
+        }
+    }
+}
+
+fn decode(value: &[u8]) -> io::Result<Acl> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "unrecognized ACL format");
+    let version = value.get(..4).ok_or_else(invalid)?;
+    let raw_entries = value[4..].chunks_exact(8);
+    if u32::from_le_bytes(version.try_into().unwrap()) != VERSION
+        || !raw_entries.remainder().is_empty()
+    {
+        return Err(invalid());
+    }
+    let mut entries = Vec::new();
+    for raw in raw_entries {
+        let tag = u16::from_le_bytes([raw[0], raw[1]]);
+        let perms = u16::from_le_bytes([raw[2], raw[3]]);
+        let id = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]);
+        let named = || Id {
+            name: id.to_string(),
+            id,
+        };
+        let tag = match tag {
+            0x01 => Tag::UserObj,
+            0x02 => Tag::User(named()),
+            0x04 => Tag::GroupObj,
+            0x08 => Tag::Group(named()),
+            0x10 => Tag::Mask,
+            0x20 => Tag::Other,
+            _ => return Err(invalid()),
+        };
+        entries.push(Entry { tag, perms });
+    }
+    Ok(Acl { entries })
+}
+
+fn parse_entry(s: &str) -> Result<Entry, String> {
+    let mut parts = s.split(':');
+    let (kind, qualifier, perms) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(kind), Some(qualifier), Some(perms), None) => (kind, qualifier, perms),
+        // setfacl allows `m:rwx` and `o:r-x`
+        (Some(kind @ ("m" | "mask" | "o" | "other")), Some(perms), None, None) => (kind, "", perms),
+        _ => return Err("expected type:qualifier:permissions".to_string()),
+    };
+    let tag = match (kind, qualifier) {
+        ("u" | "user", "") => Tag::UserObj,
+        ("u" | "user", name) => Tag::User(Owner::user(name)?),
+        ("g" | "group", "") => Tag::GroupObj,
+        ("g" | "group", name) => Tag::Group(Owner::group(name)?),
+        ("m" | "mask", "") => Tag::Mask,
+        ("o" | "other", "") => Tag::Other,
+        ("m" | "mask" | "o" | "other", _) => {
+            return Err(format!("`{}` entries take no user or group", kind))
+        }
+        _ => return Err(format!("unknown type `{}`, expected u, g, m or o", kind)),
+    };
+    Ok(Entry {
+        tag,
+        perms: parse_perms(perms)?,
+    })
+}
+
+/// Parses `rwx`, `r-x`, `rw`, `-` or a single octal digit.
+fn parse_perms(s: &str) -> Result<u16, String> {
+    if let Some(digit) = s.chars().next().filter(|_| s.len() == 1) {
+        if let Some(perms) = digit.to_digit(8) {
+            return Ok(perms as u16);
+        }
+    }
+    if s.is_empty() {
+        return Err("missing permissions".to_string());
+    }
+    s.chars().try_fold(0, |perms, c| match c {
+        'r' => Ok(perms | 4),
+        'w' => Ok(perms | 2),
+        'x' => Ok(perms | 1),
+        '-' => Ok(perms),
+        _ => Err(format!("unknown permission `{}`", c)),
+    })
+}
+
+impl fmt::Display for Acl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tag {
+            Tag::UserObj => f.write_str("u::")?,
+            Tag::User(user) => write!(f, "u:{}:", user.name)?,
+            Tag::GroupObj => f.write_str("g::")?,
+            Tag::Group(group) => write!(f, "g:{}:", group.name)?,
+            Tag::Mask => f.write_str("m::")?,
+            Tag::Other => f.write_str("o::")?,
+        }
+        for (bit, c) in [(4, 'r'), (2, 'w'), (1, 'x')] {
+            write!(f, "{}", if self.perms & bit != 0 { c } else { '-' })?;
+        }
+        Ok(())
+    }
+}
+
+/// The raw extended attribute calls; ACLs in this format are Linux-only.
+mod xattr {
+    use super::*;
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn get(path: &Path, name: &str) -> io::Result<Option<Vec<u8>>> {
+        use std::os::raw::{c_char, c_void};
+        extern "C" {
+            fn getxattr(
+                path: *const c_char,
+                name: *const c_char,
+                value: *mut c_void,
+                size: usize,
+            ) -> isize;
+        }
+        const ERANGE: i32 = 34;
+        const ENODATA: i32 = 61;
+
+        let path = c_path(path)?;
+        let name = CString::new(name).unwrap();
+        let mut value: Vec<u8> = Vec::new();
+        loop {
+            // SAFETY: both strings are NUL-terminated and `value` has `value.len()`
+            // bytes; a size of 0 only asks for the length.
+            let len = unsafe {
+                getxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    value.as_mut_ptr().cast(),
+                    value.len(),
+                )
+            };
+            if len < 0 {
+                let e = io::Error::last_os_error();
+                return match e.raw_os_error() {
+                    Some(ENODATA) => Ok(None),
+                    // Grew since the length was read
+                    Some(ERANGE) => {
+                        value.clear();
+                        continue;
+                    }
+                    _ => Err(e),
+                };
+            }
+            if value.is_empty() && len > 0 {
+                value.resize(len as usize, 0);
+                continue;
+            }
+            value.truncate(len as usize);
+            return Ok(Some(value));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        use std::os::raw::{c_char, c_int, c_void};
+        extern "C" {
+            fn setxattr(
+                path: *const c_char,
+                name: *const c_char,
+                value: *const c_void,
+                size: usize,
+                flags: c_int,
+            ) -> c_int;
+        }
+
+        let path = c_path(path)?;
+        let name = CString::new(name).unwrap();
+        // SAFETY: both strings are NUL-terminated and `value` is a valid slice.
+        let result = unsafe {
+            setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn remove(path: &Path, name: &str) -> io::Result<()> {
+        use std::os::raw::{c_char, c_int};
+        extern "C" {
+            fn removexattr(path: *const c_char, name: *const c_char) -> c_int;
+        }
+
+        let path = c_path(path)?;
+        let name = CString::new(name).unwrap();
+        // SAFETY: both strings are NUL-terminated.
+        if unsafe { removexattr(path.as_ptr(), name.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn get(path: &Path, _name: &str) -> io::Result<Option<Vec<u8>>> {
+        c_path(path)?;
+        Ok(None)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn set(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "POSIX ACLs are only supported on Linux",
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn remove(path: &Path, name: &str) -> io::Result<()> {
+        set(path, name, &[])
+    }
+}
+
+/// The raw ACL attribute of `path`, for putting it back on rollback.
+pub(crate) fn save(path: &Path, default: bool) -> io::Result<Option<Vec<u8>>> {
+    xattr::get(path, if default { DEFAULT } else { ACCESS })
+}
+
+/// Puts back an ACL attribute returned by [`save`], or removes the ACL.
+pub(crate) fn restore(path: &Path, default: bool, value: Option<&[u8]>) -> io::Result<()> {
+    let name = if default { DEFAULT } else { ACCESS };
+    match value {
+        Some(value) => xattr::set(path, name, value),
+        None => xattr::remove(path, name),
+    }
+}
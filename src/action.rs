@@ -0,0 +1,78 @@
+/// A project initialization step requested by one of the CLI flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Git,
+    Readme,
+    License,
+    Docker,
+    Go,
+    Cargo,
+    Npm,
+    Bun,
+    Yarn,
+    Pnpm,
+    Deno,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Git,
+        Action::Readme,
+        Action::License,
+        Action::Docker,
+        Action::Go,
+        Action::Cargo,
+        Action::Npm,
+        Action::Bun,
+        Action::Yarn,
+        Action::Pnpm,
+        Action::Deno,
+    ];
+
+    /// Looks up the action for a long (`--git`) or short (`-g`) flag.
+    pub fn from_flag(flag: &str) -> Option<Action> {
+        match flag {
+            "--git" | "-g" => Some(Action::Git),
+            "--readme" | "-r" => Some(Action::Readme),
+            "--license" | "-l" => Some(Action::License),
+            "--docker" | "-do" => Some(Action::Docker),
+            "--go" | "-go" => Some(Action::Go),
+            "--cargo" | "-c" => Some(Action::Cargo),
+            "--npm" | "-n" => Some(Action::Npm),
+            "--bun" | "-b" => Some(Action::Bun),
+            "--yarn" | "-y" => Some(Action::Yarn),
+            "--pnpm" | "-p" => Some(Action::Pnpm),
+            "--deno" | "-d" => Some(Action::Deno),
+            _ => None,
+        }
+    }
+
+    /// Looks up the action for a flag name as written in config files.
+    ///
+    /// Both the bare name (`git`) and either flag form (`--git`, `-g`) are accepted.
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::from_flag(name).or_else(|| {
+            Action::ALL
+                .iter()
+                .copied()
+                .find(|action| action.name() == name)
+        })
+    }
+
+    /// The flag name without leading dashes, e.g. `git`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Git => "git",
+            Action::Readme => "readme",
+            Action::License => "license",
+            Action::Docker => "docker",
+            Action::Go => "go",
+            Action::Cargo => "cargo",
+            Action::Npm => "npm",
+            Action::Bun => "bun",
+            Action::Yarn => "yarn",
+            Action::Pnpm => "pnpm",
+            Action::Deno => "deno",
+        }
+    }
+}
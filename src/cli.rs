@@ -0,0 +1,455 @@
+use std::path::{Path, PathBuf};
+
+use crate::acl::Acl;
+use crate::action::Action;
+use crate::config::Config;
+use crate::expand;
+use crate::license::Expression;
+use crate::mode::{self, Mode, ParentsMode};
+use crate::mtree::Manifest;
+use crate::outline;
+use crate::output::Format;
+use crate::owner::Owner;
+use crate::plan::CreateRequest;
+use crate::reporter::{ColorChoice, Painter, Role, Verbosity};
+use crate::runner;
+use crate::spec::Spec;
+use crate::toolchain;
+use crate::user_template::UserTemplate;
+
+/// The help text; print it with [`usage`] for colored headings and options.
+pub const USAGE: &str = "\
+Usage: makedir [directories] [options]
+
+Help:  Creates one or more directories with optional project initialization.
+       Multiple directories can be specified, and options apply to all of them.
+
+Options:
+    --git,     -g         Initialize a Git repository.
+    --readme,  -r         Generate a template README.md file.
+    --license, -l         Generate a LICENSE file, MIT unless --license=<spdx> is given.
+    --docker,  -do        Generate a template Docker file.
+    --go,      -go        Initialize a Go project.
+    --cargo,   -c         Initialize a Rust Cargo project.
+    --npm,     -n         Initialize an npm project (package.json).
+    --bun,     -b         Initialize a Bun project.
+    --yarn,    -y         Initialize a Yarn project.
+    --pnpm,    -p         Initialize a pnpm project.
+    --deno,    -d         Initialize a Deno project (deno.json).
+    --verbose, -v         Show every step; -vv also streams initializer output, -vvv adds
+                          timings and the argv of each command.
+    --quiet,   -q         Print only errors.
+    --dry-run             Print every step without touching the filesystem.
+    --output <format>     Print a final report as text (default) or json.
+    --events jsonl        Stream one JSON object per step to stdout.
+    --color <when>        Color messages: auto (default, terminals only), always or never.
+    --timeout <duration>  Kill initializers that run longer than this (90s, 2m; 0 for none).
+    --atomic              Undo a directory's changes if any of its steps fail.
+    --keep-going          Process every directory, then summarize failures (default).
+    --fail-fast           Stop at the first directory that fails.
+    --jobs,    -j <n>     Process up to n directories at once, printing each one's output
+                          in one piece.
+    --preset, @<name>     Apply a preset defined in the config file.
+    --template <name>     Copy a template directory into each new directory.
+    --spec <file>         Build the directory tree described in a YAML, TOML or JSON file.
+    --from-tree <file>    Build the tree in an indented outline or `tree` output (- for stdin).
+    --mtree <file>        Build the directories and files listed in a BSD mtree spec.
+    --no-expand           Take directory names literally, without brace expansion.
+    --                    Treat every following argument as a directory, even one named
+                          like a command (`makedir -- doctor`).
+    --mode <mode>         Set directory permissions, in octal (2775) or chmod syntax (u=rwx,g+s).
+    --parents-mode <policy>
+                          Which new parent directories get permissions: leaf (default), all,
+                          or a separate mode for them. Existing directories are left alone.
+    --owner <user[:group]>
+                          Give new directories this owner (and group; `user:` for the login group).
+    --group <group>       Give new directories this group.
+    --shared              Make new directories group-writable and setgid, for team folders.
+    --acl <entries>       Add setfacl-style ACL entries to new directories (g:devs:rwx,u:ann:r-x).
+    --default-acl <entries>
+                          Add default ACL entries, inherited by everything created inside later.
+               -###       Set directory permissions (octal format, e.g., -700, -755).
+
+Commands:
+    makedir config show   Print the settings merged from the config file and environment.
+    makedir presets list  Show the flags each preset expands to.
+    makedir doctor [flags]
+                          Check that the tools behind each flag (or just those given) are on
+                          PATH, and show their versions.
+    makedir verify --mtree <file> [root]
+                          Report entries of an mtree spec missing from root, or with the wrong
+                          type or permissions.
+
+Exit codes:
+    0  Success.
+    1  Usage error, nothing was created.
+    2  Filesystem error (creating a directory, setting permissions, writing a file).
+    3  Initializer error (git, npm, cargo, ... failed or is not installed).
+    4  `makedir verify` found differences.
+";
+
+/// [`USAGE`] with headings and options colored by `painter`.
+pub fn usage(painter: &Painter) -> String {
+    let mut out = String::new();
+    let mut options = false;
+    for line in USAGE.lines() {
+        if let Some((heading, rest)) = line.split_once(':').filter(|_| !line.starts_with(' ')) {
+            options = matches!(heading, "Options" | "Commands");
+            out.push_str(&painter.paint(Role::Notice, format_args!("{}:", heading)));
+            out.push_str(rest);
+        } else if options && line.starts_with("    ") && !line.starts_with(&" ".repeat(26)) {
+            // The option column ends at the description, or at its placeholders
+            let column = line.get(..26).unwrap_or(line).trim_end();
+            let option_end = column.find(['<', '[']).unwrap_or(column.len());
+            let option = column[..option_end].trim_end();
+            let indent = option.len() - option.trim_start().len();
+            out.push_str(&option[..indent]);
+            out.push_str(&painter.paint(Role::Highlight, option.trim_start()));
+            out.push_str(&line[option.len()..]);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The result of parsing the command line.
+///
+/// Problems with individual arguments are collected in `errors` so they can
+/// all be reported at once; any error makes the whole command line invalid.
+#[derive(Debug, Default)]
+pub struct ParsedArgs {
+    pub request: CreateRequest,
+    pub errors: Vec<String>,
+}
+
+/// Parses the arguments that follow the program name.
+pub fn parse_args(args: &[String]) -> ParsedArgs {
+    parse_args_with_config(args, &Config::default())
+}
+
+/// Parses the arguments on top of the defaults from `config`.
+pub fn parse_args_with_config(args: &[String], config: &Config) -> ParsedArgs {
+    let mut parsed = ParsedArgs::default();
+    config.apply_to(&mut parsed.request);
+    let mut options_ended = false;
+    let mut expand = true;
+    let mut verbose = 0;
+    let mut quiet = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if !options_ended && (arg == "--preset" || arg.starts_with('@')) {
+            let name = match arg.strip_prefix('@') {
+                Some(name) => Some(name),
+                None => args.next().map(String::as_str),
+            };
+            match name {
+                Some(name) if !name.is_empty() => apply_preset(&mut parsed, config, name),
+                _ => parsed
+                    .errors
+                    .push("--preset requires a preset name".to_string()),
+            }
+        } else if !options_ended && arg == "--template" {
+            match args.next() {
+                Some(name) => match UserTemplate::find(name) {
+                    Ok(template) => parsed.request.templates.push(template),
+                    Err(e) => parsed.errors.push(format!("Invalid template: {}", e)),
+                },
+                None => parsed
+                    .errors
+                    .push("--template requires a template name".to_string()),
+            }
+        } else if !options_ended && arg == "--spec" {
+            match args.next() {
+                Some(path) => match Spec::load(Path::new(path)) {
+                    Ok(spec) => set_spec(&mut parsed, spec),
+                    Err(e) => parsed.errors.push(format!("Invalid spec: {}", e)),
+                },
+                None => parsed.errors.push("--spec requires a file".to_string()),
+            }
+        } else if !options_ended && arg == "--from-tree" {
+            match args.next() {
+                Some(source) => match outline::load(source) {
+                    Ok(spec) => set_spec(&mut parsed, spec),
+                    Err(e) => parsed.errors.push(format!("Invalid tree: {}", e)),
+                },
+                None => parsed
+                    .errors
+                    .push("--from-tree requires a file, or - for standard input".to_string()),
+            }
+        } else if !options_ended && arg == "--mtree" {
+            match args.next() {
+                Some(path) => match Manifest::load(Path::new(path)).and_then(|m| m.to_spec()) {
+                    Ok(spec) => set_spec(&mut parsed, spec),
+                    Err(e) => parsed.errors.push(format!("Invalid mtree spec: {}", e)),
+                },
+                None => parsed.errors.push("--mtree requires a file".to_string()),
+            }
+        } else if !options_ended && arg == "--mode" {
+            match args.next() {
+                Some(mode) => set_mode(&mut parsed, mode),
+                None => parsed.errors.push("--mode requires a mode".to_string()),
+            }
+        } else if let Some(mode) = arg.strip_prefix("--mode=").filter(|_| !options_ended) {
+            set_mode(&mut parsed, mode);
+        } else if !options_ended && arg == "--parents-mode" {
+            match args.next().map(|policy| ParentsMode::parse(policy)) {
+                Some(Ok(policy)) => parsed.request.parents_mode = policy,
+                Some(Err(e)) => parsed.errors.push(format!("--parents-mode: {}", e)),
+                None => parsed
+                    .errors
+                    .push("--parents-mode requires leaf, all or a mode".to_string()),
+            }
+        } else if !options_ended && arg == "--owner" {
+            match args.next().map(|spec| Owner::parse(spec)) {
+                Some(Ok(owner)) => {
+                    // `--owner user` keeps a group given with --group
+                    let group = owner.group.or(parsed.request.owner.group.take());
+                    parsed.request.owner = Owner { group, ..owner };
+                }
+                Some(Err(e)) => parsed.errors.push(format!("--owner: {}", e)),
+                None => parsed
+                    .errors
+                    .push("--owner requires user[:group]".to_string()),
+            }
+        } else if !options_ended && arg == "--group" {
+            match args.next().map(|name| Owner::group(name)) {
+                Some(Ok(group)) => parsed.request.owner.group = Some(group),
+                Some(Err(e)) => parsed.errors.push(format!("--group: {}", e)),
+                None => parsed.errors.push("--group requires a group".to_string()),
+            }
+        } else if !options_ended && arg == "--shared" {
+            parsed.request.shared = true;
+        } else if !options_ended && (arg == "--acl" || arg == "--default-acl") {
+            match args.next().map(|entries| Acl::parse(entries)) {
+                Some(Ok((access, default))) if arg == "--acl" => {
+                    parsed.request.acl.extend(access);
+                    parsed.request.default_acl.extend(default);
+                }
+                // Every entry of --default-acl is a default one, prefixed or not
+                Some(Ok((access, default))) => {
+                    parsed.request.default_acl.extend(access);
+                    parsed.request.default_acl.extend(default);
+                }
+                Some(Err(e)) => parsed.errors.push(format!("{}: {}", arg, e)),
+                None => parsed
+                    .errors
+                    .push(format!("{} requires ACL entries like g:devs:rwx", arg)),
+            }
+        } else if !options_ended && arg == "--output" {
+            match args.next() {
+                Some(format) => set_output(&mut parsed, format),
+                None => parsed
+                    .errors
+                    .push("--output requires text or json".to_string()),
+            }
+        } else if let Some(format) = arg.strip_prefix("--output=").filter(|_| !options_ended) {
+            set_output(&mut parsed, format);
+        } else if !options_ended && arg == "--color" {
+            match args.next() {
+                Some(choice) => set_color(&mut parsed, choice),
+                None => parsed
+                    .errors
+                    .push("--color requires auto, always or never".to_string()),
+            }
+        } else if let Some(choice) = arg.strip_prefix("--color=").filter(|_| !options_ended) {
+            set_color(&mut parsed, choice);
+        } else if !options_ended && (arg == "--jobs" || arg == "-j") {
+            match args.next() {
+                Some(jobs) => set_jobs(&mut parsed, jobs),
+                None => parsed
+                    .errors
+                    .push(format!("{} requires a number of jobs", arg)),
+            }
+        } else if let Some(jobs) = arg
+            .strip_prefix("--jobs=")
+            .or_else(|| arg.strip_prefix("-j"))
+            .filter(|_| !options_ended)
+        {
+            set_jobs(&mut parsed, jobs);
+        } else if !options_ended && arg == "--timeout" {
+            match args.next() {
+                Some(timeout) => set_timeout(&mut parsed, timeout),
+                None => parsed
+                    .errors
+                    .push("--timeout requires a duration".to_string()),
+            }
+        } else if let Some(timeout) = arg.strip_prefix("--timeout=").filter(|_| !options_ended) {
+            set_timeout(&mut parsed, timeout);
+        } else if !options_ended && arg == "--events" {
+            match args.next() {
+                Some(format) => set_events(&mut parsed, format),
+                None => parsed.errors.push("--events requires jsonl".to_string()),
+            }
+        } else if let Some(format) = arg.strip_prefix("--events=").filter(|_| !options_ended) {
+            set_events(&mut parsed, format);
+        } else if !options_ended && (arg == "--license" || arg == "-l") {
+            // The expression needs `=`, so `--license mit` means MIT and a directory `mit`
+            add_action(&mut parsed.request, Action::License);
+        } else if let Some(expression) = arg.strip_prefix("--license=").filter(|_| !options_ended) {
+            add_action(&mut parsed.request, Action::License);
+            match Expression::parse(expression) {
+                Ok(expression) => parsed.request.license = Some(expression.to_string()),
+                Err(e) => parsed.errors.push(format!("Invalid license: {}", e)),
+            }
+        } else if options_ended || !arg.starts_with('-') {
+            // Not a flag, must be a directory
+            parsed.request.dirs.push(PathBuf::from(arg));
+        } else if arg.len() > 1 && arg.as_bytes()[1].is_ascii_digit() {
+            // Permission tag (e.g., -700), must be up to 3 octal digits
+            match mode::parse_octal(&arg[1..]) {
+                Some(perm) => parsed.request.mode = Some(Mode::Octal(perm)),
+                None => parsed.errors.push(format!(
+                    "Invalid permission format: {}. Must be 3 octal digits (000-777).",
+                    arg
+                )),
+            }
+        } else if arg == "--verbose" {
+            verbose += 1;
+        } else if arg.len() > 1 && arg[1..].bytes().all(|b| b == b'v') {
+            // -v, -vv and -vvv, which can also be given separately
+            verbose += arg.len() - 1;
+        } else if arg == "--quiet" || arg == "-q" {
+            quiet = true;
+        } else if arg == "--dry-run" {
+            parsed.request.dry_run = true;
+        } else if arg == "--atomic" {
+            parsed.request.atomic = true;
+        } else if arg == "--keep-going" {
+            parsed.request.fail_fast = false;
+        } else if arg == "--fail-fast" {
+            parsed.request.fail_fast = true;
+        } else if arg == "--no-expand" {
+            expand = false;
+        } else if arg == "--" {
+            options_ended = true;
+        } else if let Some(action) = Action::from_flag(arg) {
+            add_action(&mut parsed.request, action);
+        } else {
+            parsed.errors.push(format!("Unknown flag: {}", arg));
+        }
+    }
+
+    if quiet && verbose > 0 {
+        parsed
+            .errors
+            .push("--quiet and --verbose cannot be combined".to_string());
+    } else if quiet {
+        parsed.request.verbosity = Verbosity::Quiet;
+    } else if verbose > 0 {
+        parsed.request.verbosity = Verbosity::from_count(verbose);
+    }
+
+    // Expanded after the loop, so --no-expand applies to every directory
+    if expand {
+        let dirs = std::mem::take(&mut parsed.request.dirs);
+        for dir in dirs {
+            match expand::expand(&dir.to_string_lossy()) {
+                Ok(words) => parsed.request.dirs.extend(
+                    words
+                        .into_iter()
+                        .filter(|w| !w.is_empty())
+                        .map(PathBuf::from),
+                ),
+                Err(e) => parsed.errors.push(format!("Invalid directory: {}", e)),
+            }
+        }
+    }
+
+    // A spec can name the directory it describes
+    if parsed.request.dirs.is_empty() {
+        if let Some(name) = parsed
+            .request
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.name.clone())
+        {
+            parsed.request.dirs.push(PathBuf::from(name));
+        }
+    }
+
+    parsed
+}
+
+fn set_mode(parsed: &mut ParsedArgs, mode: &str) {
+    match Mode::parse(mode) {
+        Ok(mode) => parsed.request.mode = Some(mode),
+        Err(e) => parsed.errors.push(format!("--mode: {}", e)),
+    }
+}
+
+fn set_output(parsed: &mut ParsedArgs, format: &str) {
+    match Format::parse(format) {
+        Ok(format) => parsed.request.output = format,
+        Err(e) => parsed.errors.push(format!("--output: {}", e)),
+    }
+}
+
+fn set_jobs(parsed: &mut ParsedArgs, jobs: &str) {
+    match jobs.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => parsed.request.jobs = jobs,
+        _ => parsed.errors.push(format!(
+            "--jobs: expected a number of jobs greater than 0, got `{}`",
+            jobs
+        )),
+    }
+}
+
+fn set_timeout(parsed: &mut ParsedArgs, timeout: &str) {
+    match runner::parse_duration(timeout) {
+        Ok(timeout) => parsed.request.timeout = timeout,
+        Err(e) => parsed.errors.push(format!("--timeout: {}", e)),
+    }
+}
+
+fn set_color(parsed: &mut ParsedArgs, choice: &str) {
+    match ColorChoice::parse(choice) {
+        Ok(choice) => parsed.request.color = choice,
+        Err(e) => parsed.errors.push(format!("--color: {}", e)),
+    }
+}
+
+fn set_events(parsed: &mut ParsedArgs, format: &str) {
+    match format {
+        "jsonl" => parsed.request.events = true,
+        _ => parsed.errors.push(format!(
+            "--events: unknown format `{}`, expected jsonl",
+            format
+        )),
+    }
+}
+
+fn set_spec(parsed: &mut ParsedArgs, spec: Spec) {
+    if parsed.request.spec.is_some() {
+        parsed
+            .errors
+            .push("Only one of --spec, --from-tree and --mtree can be given".to_string());
+    }
+    parsed.request.spec = Some(spec);
+}
+
+fn apply_preset(parsed: &mut ParsedArgs, config: &Config, name: &str) {
+    match config.resolve_preset(name) {
+        Ok(preset) => {
+            for action in preset.flags {
+                add_action(&mut parsed.request, action);
+            }
+            if preset.mode.is_some() {
+                parsed.request.mode = preset.mode;
+            }
+            for requirement in preset.requires {
+                toolchain::add_requirement(&mut parsed.request.requires, requirement);
+            }
+        }
+        Err(e) => parsed.errors.push(format!("Invalid preset: {}", e)),
+    }
+}
+
+fn add_action(request: &mut CreateRequest, action: Action) {
+    // Flags may already be set by the config defaults or a preset
+    if !request.actions.contains(&action) {
+        request.actions.push(action);
+    }
+}
@@ -0,0 +1,483 @@
+//! User configuration from `~/.config/makedir/config.toml` and `MAKEDIR_*`
+//! environment variables.
+//!
+//! Settings are layered: the config file first, then the environment, then
+//! the command line, with later layers overriding earlier ones.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::action::Action;
+use crate::license::Expression;
+use crate::mode::Mode;
+use crate::plan::CreateRequest;
+pub use crate::preset::{Preset, ResolvedPreset};
+use crate::reporter::{ColorChoice, Palette, Verbosity};
+use crate::runner;
+use crate::toml::{self, Table, Value};
+use crate::toolchain::{self, Outdated, Requirement};
+
+/// Environment variables read by [`Config::apply_env`].
+pub const ENV_VARS: [&str; 12] = [
+    "MAKEDIR_CONFIG",
+    "MAKEDIR_COLOR",
+    "MAKEDIR_COLORS",
+    "MAKEDIR_FLAGS",
+    "MAKEDIR_MODE",
+    "MAKEDIR_VERBOSE",
+    "MAKEDIR_AUTHOR_NAME",
+    "MAKEDIR_AUTHOR_EMAIL",
+    "MAKEDIR_GO_MODULE_PREFIX",
+    "MAKEDIR_LICENSE",
+    "MAKEDIR_TIMEOUT",
+    "MAKEDIR_OUTDATED",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Flags applied to every directory in addition to those on the command line.
+    pub flags: Option<Vec<Action>>,
+    pub mode: Option<Mode>,
+    pub verbose: Option<bool>,
+    /// When to color messages; `--color` overrides it.
+    pub color: Option<ColorChoice>,
+    /// Escape codes for each kind of message, from `[colors]`.
+    pub palette: Palette,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// Prefix for `go mod init`, e.g. `github.com/you`.
+    pub go_module_prefix: Option<String>,
+    pub license: Option<String>,
+    /// How long each initializer may run; `--timeout` overrides it.
+    pub timeout: Option<Duration>,
+    /// Minimum toolchain versions from `[requires]`.
+    pub requires: Vec<Requirement>,
+    /// Whether an older toolchain stops the run or only warns.
+    pub outdated: Option<Outdated>,
+    /// Named flag sets from `[preset.<name>]` tables, in file order.
+    pub presets: Vec<Preset>,
+}
+
+impl Config {
+    /// Where the config file is looked for.
+    ///
+    /// `MAKEDIR_CONFIG` wins, then `$XDG_CONFIG_HOME/makedir/config.toml`,
+    /// then `~/.config/makedir/config.toml`.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("MAKEDIR_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        Some(Config::dir()?.join("config.toml"))
+    }
+
+    /// makedir's directory under `$XDG_CONFIG_HOME`, or `~/.config/makedir`.
+    pub fn dir() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("makedir"))
+    }
+
+    /// Loads the config file, if there is one, and applies the environment on top.
+    pub fn load() -> Result<Config, String> {
+        let mut config = match Config::path() {
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(text) => Config::from_toml(&text)
+                    .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Config::default(),
+                Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+            },
+            None => Config::default(),
+        };
+        config.apply_env(|name| env::var(name).ok())?;
+        Ok(config)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Config, String> {
+        let root = toml::parse(text).map_err(|e| e.to_string())?;
+        let mut config = Config::default();
+
+        for (section, value) in root.iter() {
+            let table = value
+                .as_table()
+                .ok_or_else(|| format!("`{}` must be a table", section))?;
+            match section {
+                "defaults" => config.read_defaults(table)?,
+                "author" => {
+                    for (key, value) in table.iter() {
+                        match key {
+                            "name" => config.author_name = Some(string(section, key, value)?),
+                            "email" => config.author_email = Some(string(section, key, value)?),
+                            _ => return Err(unknown_key(section, key)),
+                        }
+                    }
+                }
+                "go" => {
+                    for (key, value) in table.iter() {
+                        match key {
+                            "module_prefix" => {
+                                config.go_module_prefix = Some(string(section, key, value)?)
+                            }
+                            _ => return Err(unknown_key(section, key)),
+                        }
+                    }
+                }
+                "colors" => {
+                    for (key, value) in table.iter() {
+                        let codes = string(section, key, value)?;
+                        config
+                            .palette
+                            .set(key, &codes)
+                            .map_err(|e| format!("`colors.{}`: {}", key, e))?;
+                    }
+                }
+                "requires" => config.requires = requirements(section, value)?,
+                "preset" => {
+                    for (name, value) in table.iter() {
+                        let table = value
+                            .as_table()
+                            .ok_or_else(|| format!("`preset.{}` must be a table", name))?;
+                        config.presets.push(Preset::from_table(name, table)?);
+                    }
+                }
+                _ => return Err(format!("unknown section `[{}]`", section)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    fn read_defaults(&mut self, table: &Table) -> Result<(), String> {
+        for (key, value) in table.iter() {
+            match key {
+                "flags" => self.flags = Some(flag_list("defaults.flags", value)?),
+                "mode" => self.mode = Some(mode_value("defaults.mode", value)?),
+                "verbose" => {
+                    self.verbose = Some(
+                        value
+                            .as_bool()
+                            .ok_or_else(|| "`defaults.verbose` must be a boolean".to_string())?,
+                    )
+                }
+                "license" => self.license = Some(parse_license(&string("defaults", key, value)?)?),
+                "color" => {
+                    self.color = Some(
+                        ColorChoice::parse(&string("defaults", key, value)?)
+                            .map_err(|e| format!("`defaults.color`: {}", e))?,
+                    )
+                }
+                "timeout" => self.timeout = timeout_value("defaults.timeout", value)?,
+                "outdated" => {
+                    self.outdated = Some(
+                        Outdated::parse(&string("defaults", key, value)?)
+                            .map_err(|e| format!("`defaults.outdated`: {}", e))?,
+                    )
+                }
+                _ => return Err(unknown_key("defaults", key)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides settings from `MAKEDIR_*` variables, looked up through `var`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(flags) = var("MAKEDIR_FLAGS") {
+            self.flags = Some(
+                flags
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(parse_flag)
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("MAKEDIR_FLAGS: {}", e))?,
+            );
+        }
+        if let Some(mode) = var("MAKEDIR_MODE") {
+            self.mode = Some(Mode::parse(&mode).map_err(|e| format!("MAKEDIR_MODE: {}", e))?);
+        }
+        if let Some(verbose) = var("MAKEDIR_VERBOSE") {
+            self.verbose = Some(match verbose.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" | "" => false,
+                _ => {
+                    return Err(format!(
+                        "MAKEDIR_VERBOSE: expected true or false, got `{}`",
+                        verbose
+                    ))
+                }
+            });
+        }
+        if let Some(color) = var("MAKEDIR_COLOR") {
+            self.color =
+                Some(ColorChoice::parse(&color).map_err(|e| format!("MAKEDIR_COLOR: {}", e))?);
+        }
+        if let Some(colors) = var("MAKEDIR_COLORS") {
+            self.palette
+                .apply(&colors)
+                .map_err(|e| format!("MAKEDIR_COLORS: {}", e))?;
+        }
+        if let Some(name) = var("MAKEDIR_AUTHOR_NAME") {
+            self.author_name = Some(name);
+        }
+        if let Some(email) = var("MAKEDIR_AUTHOR_EMAIL") {
+            self.author_email = Some(email);
+        }
+        if let Some(prefix) = var("MAKEDIR_GO_MODULE_PREFIX") {
+            self.go_module_prefix = Some(prefix);
+        }
+        if let Some(license) = var("MAKEDIR_LICENSE") {
+            self.license =
+                Some(parse_license(&license).map_err(|e| format!("MAKEDIR_LICENSE: {}", e))?);
+        }
+        if let Some(timeout) = var("MAKEDIR_TIMEOUT") {
+            self.timeout =
+                runner::parse_duration(&timeout).map_err(|e| format!("MAKEDIR_TIMEOUT: {}", e))?;
+        }
+        if let Some(outdated) = var("MAKEDIR_OUTDATED") {
+            self.outdated =
+                Some(Outdated::parse(&outdated).map_err(|e| format!("MAKEDIR_OUTDATED: {}", e))?);
+        }
+        Ok(())
+    }
+
+    /// Fills in `request` from these settings, before the command line is applied.
+    pub fn apply_to(&self, request: &mut CreateRequest) {
+        if let Some(flags) = &self.flags {
+            request.actions = flags.clone();
+        }
+        request.mode = self.mode.clone();
+        if self.verbose == Some(true) {
+            request.verbosity = Verbosity::Steps;
+        }
+        request.color = self.color.unwrap_or_default();
+        request.palette = self.palette.clone();
+        request.author_name = self.author_name.clone();
+        request.author_email = self.author_email.clone();
+        request.go_module_prefix = self.go_module_prefix.clone();
+        request.license = self.license.clone();
+        request.timeout = self.timeout;
+        request.requires = self.requires.clone();
+        request.outdated = self.outdated.unwrap_or_default();
+    }
+
+    /// The settings in config file syntax, as printed by `makedir config show`.
+    pub fn to_toml(&self) -> String {
+        let mut root = Table::new();
+
+        let mut defaults = Table::new();
+        if let Some(flags) = &self.flags {
+            let names = flags
+                .iter()
+                .map(|action| Value::String(action.name().to_string()))
+                .collect();
+            defaults.insert("flags", Value::Array(names));
+        }
+        if let Some(mode) = &self.mode {
+            defaults.insert("mode", Value::String(mode.to_string()));
+        }
+        if let Some(verbose) = self.verbose {
+            defaults.insert("verbose", Value::Boolean(verbose));
+        }
+        if let Some(license) = &self.license {
+            defaults.insert("license", Value::String(license.clone()));
+        }
+        if let Some(color) = self.color {
+            defaults.insert("color", Value::String(color.name().to_string()));
+        }
+        if let Some(timeout) = self.timeout {
+            defaults.insert("timeout", Value::String(runner::format_duration(timeout)));
+        }
+        if let Some(outdated) = self.outdated {
+            defaults.insert("outdated", Value::String(outdated.name().to_string()));
+        }
+
+        // Only the colors changed from the defaults
+        let mut colors = Table::new();
+        let default_palette = Palette::default();
+        for role in Palette::ROLES {
+            let codes = self.palette.get(role);
+            if codes != default_palette.get(role) {
+                colors.insert(role, Value::String(codes.unwrap_or_default().to_string()));
+            }
+        }
+
+        let mut author = Table::new();
+        if let Some(name) = &self.author_name {
+            author.insert("name", Value::String(name.clone()));
+        }
+        if let Some(email) = &self.author_email {
+            author.insert("email", Value::String(email.clone()));
+        }
+
+        let mut go = Table::new();
+        if let Some(prefix) = &self.go_module_prefix {
+            go.insert("module_prefix", Value::String(prefix.clone()));
+        }
+
+        let requires = requirements_table(&self.requires);
+
+        let mut presets = Table::new();
+        for preset in &self.presets {
+            presets.insert(&preset.name, Value::Table(preset.to_table()));
+        }
+
+        for (name, table) in [
+            ("defaults", defaults),
+            ("author", author),
+            ("go", go),
+            ("colors", colors),
+            ("requires", requires),
+            ("preset", presets),
+        ] {
+            if !table.is_empty() {
+                root.insert(name, Value::Table(table));
+            }
+        }
+        toml::to_string(&root)
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Expands a preset and everything it extends into flags and a mode.
+    pub fn resolve_preset(&self, name: &str) -> Result<ResolvedPreset, String> {
+        let mut resolved = ResolvedPreset::default();
+        self.resolve_into(name, &mut Vec::new(), &mut resolved)?;
+        Ok(resolved)
+    }
+
+    fn resolve_into(
+        &self,
+        name: &str,
+        chain: &mut Vec<String>,
+        resolved: &mut ResolvedPreset,
+    ) -> Result<(), String> {
+        if chain.iter().any(|seen| seen == name) {
+            chain.push(name.to_string());
+            return Err(format!("preset cycle: {}", chain.join(" -> ")));
+        }
+        let preset = self
+            .preset(name)
+            .ok_or_else(|| format!("unknown preset `{}`", name))?;
+
+        chain.push(name.to_string());
+        // Parents first, so the preset's own settings win
+        for parent in &preset.extends {
+            self.resolve_into(parent, chain, resolved)?;
+        }
+        chain.pop();
+
+        for action in &preset.flags {
+            if !resolved.flags.contains(action) {
+                resolved.flags.push(*action);
+            }
+        }
+        if preset.mode.is_some() {
+            resolved.mode = preset.mode.clone();
+        }
+        for requirement in &preset.requires {
+            toolchain::add_requirement(&mut resolved.requires, requirement.clone());
+        }
+        Ok(())
+    }
+}
+
+fn string(section: &str, key: &str, value: &Value) -> Result<String, String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("`{}.{}` must be a string", section, key))
+}
+
+fn unknown_key(section: &str, key: &str) -> String {
+    format!("unknown key `{}` in `[{}]`", key, section)
+}
+
+pub(crate) fn flag_list(key: &str, value: &Value) -> Result<Vec<Action>, String> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| format!("`{}` must be an array", key))?;
+    items
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .ok_or_else(|| format!("`{}` must contain strings", key))
+                .and_then(parse_flag)
+        })
+        .collect()
+}
+
+pub(crate) fn mode_value(key: &str, value: &Value) -> Result<Mode, String> {
+    match value {
+        Value::String(s) => Mode::parse(s),
+        // TOML's 0o755 and a decimal 755 look the same once parsed
+        Value::Integer(i) => Err(format!(
+            "`{}` must be a string like \"{}\"; a bare number is read as decimal",
+            key, i
+        )),
+        _ => Err(format!(
+            "`{}` must be a string like \"755\" or \"g+w\"",
+            key
+        )),
+    }
+}
+
+fn timeout_value(key: &str, value: &Value) -> Result<Option<Duration>, String> {
+    match value {
+        Value::String(s) => runner::parse_duration(s).map_err(|e| format!("`{}`: {}", key, e)),
+        Value::Integer(seconds) if *seconds >= 0 => {
+            Ok(Some(Duration::from_secs(*seconds as u64)).filter(|d| !d.is_zero()))
+        }
+        _ => Err(format!(
+            "`{}` must be a number of seconds or a string like \"2m\"",
+            key
+        )),
+    }
+}
+
+/// A table of toolchain names to constraints, such as `[requires]`.
+pub(crate) fn requirements(key: &str, value: &Value) -> Result<Vec<Requirement>, String> {
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("`{}` must be a table", key))?;
+    table
+        .iter()
+        .map(|(name, constraint)| {
+            let constraint = match constraint {
+                Value::String(s) => s.clone(),
+                Value::Integer(major) => major.to_string(),
+                _ => {
+                    return Err(format!(
+                        "`{}.{}` must be a string like \">=1.22\"",
+                        key, name
+                    ))
+                }
+            };
+            Requirement::parse(name, &constraint).map_err(|e| format!("`{}.{}`: {}", key, name, e))
+        })
+        .collect()
+}
+
+pub(crate) fn requirements_table(requires: &[Requirement]) -> Table {
+    let mut table = Table::new();
+    for requirement in requires {
+        table.insert(
+            requirement.toolchain.name,
+            Value::String(requirement.constraint()),
+        );
+    }
+    table
+}
+
+fn parse_flag(name: &str) -> Result<Action, String> {
+    Action::from_name(name).ok_or_else(|| format!("unknown flag `{}`", name))
+}
+
+fn parse_license(s: &str) -> Result<String, String> {
+    Expression::parse(s).map(|expression| expression.to_string())
+}
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::fs_util::missing_dirs;
+use crate::mode::Mode;
+use crate::plan::{step_path, Plan, Step};
+
+/// Describes, in order, every change executing `plan` would make.
+///
+/// The filesystem is only inspected, never modified, so this is safe to call
+/// before deciding whether to run [`crate::execute`].
+pub fn describe(plan: &Plan) -> Vec<String> {
+    let mut lines = Vec::new();
+    // Directories an earlier target would already have created
+    let mut planned: HashSet<PathBuf> = HashSet::new();
+
+    for target in &plan.targets {
+        let dir = &target.dir;
+        let missing = missing_dirs(dir, &planned);
+        if missing.is_empty() {
+            lines.push(format!("directory already exists: {}", dir.display()));
+        }
+        for path in &missing {
+            lines.push(format!("create directory {}", path.display()));
+            planned.insert(path.clone());
+        }
+        let parents = missing.split_last().map_or(&[][..], |(_, parents)| parents);
+        let parents_mode = plan.parents_mode.for_parents(target.mode.as_ref());
+        for parent in parents.iter().rev() {
+            lines.extend(attributes(plan, parents_mode, parent));
+        }
+        lines.extend(attributes(plan, target.mode.as_ref(), dir));
+
+        for step in &target.steps {
+            match step {
+                Step::Run(cmd) => lines.push(format!("run `{}` in {}", cmd, dir.display())),
+                Step::WriteFile { name, contents } => lines.push(format!(
+                    "write {} ({} bytes)",
+                    dir.join(name).display(),
+                    contents.len()
+                )),
+                Step::CreateDir { name } => {
+                    lines.push(format!("create directory {}", dir.join(name).display()))
+                }
+                Step::CreateFile { name } => lines.push(format!(
+                    "create {} unless it exists",
+                    dir.join(name).display()
+                )),
+                Step::CopyFile { from, name } => lines.push(format!(
+                    "copy {} to {}",
+                    from.display(),
+                    dir.join(name).display()
+                )),
+                Step::SetMode { name, mode } => lines.push(format!(
+                    "set permissions {} on {}",
+                    mode,
+                    step_path(dir, name).display()
+                )),
+                Step::SetOwner { name, owner } => lines.push(format!(
+                    "set owner {} on {}",
+                    owner,
+                    step_path(dir, name).display()
+                )),
+                Step::SetLicenseField { name, license } => lines.push(format!(
+                    "set license {} in {}",
+                    license,
+                    dir.join(name).display()
+                )),
+            }
+        }
+    }
+
+    lines
+}
+
+/// The owner and permission changes made to a directory, in the order
+/// they are applied.
+fn attributes(plan: &Plan, mode: Option<&Mode>, dir: &Path) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !plan.owner.is_empty() {
+        lines.push(format!("set owner {} on {}", plan.owner, dir.display()));
+    }
+    if let Some(mode) = mode {
+        lines.push(format!("set permissions {} on {}", mode, dir.display()));
+    }
+    if plan.shared {
+        lines.push(format!(
+            "set permissions {} on {}",
+            Mode::shared(),
+            dir.display()
+        ));
+    }
+    if !plan.acl.is_empty() {
+        lines.push(format!("set ACL {} on {}", plan.acl, dir.display()));
+    }
+    if !plan.default_acl.is_empty() {
+        lines.push(format!(
+            "set default ACL {} on {}",
+            plan.default_acl,
+            dir.display()
+        ));
+    }
+    lines
+}
@@ -0,0 +1,792 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{self as unix_fs, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::acl::Acl;
+use crate::exit_code;
+use crate::fs_util::missing_dirs;
+use crate::license;
+use crate::mode::Mode;
+use crate::output::Event;
+use crate::owner::Owner;
+use crate::plan::{step_path, Plan, Step, Target};
+use crate::reporter::{Reporter, Role, Verbosity};
+use crate::rollback::{self, Journal};
+use crate::runner::{self, CommandSpec, LineHandler, RunOptions, Runner, SystemRunner};
+use crate::toolchain::VersionCheck;
+
+/// The outcome of executing a [`Plan`].
+#[derive(Debug, Default)]
+pub struct Report {
+    pub targets: Vec<TargetReport>,
+    /// Directories never started because a fail-fast run stopped early, or
+    /// every directory of a refused run.
+    pub skipped: Vec<PathBuf>,
+    /// The toolchain versions checked before the run, see
+    /// [`toolchain::check_versions`](crate::toolchain::check_versions).
+    pub toolchains: Vec<VersionCheck>,
+    /// The tools the plan needs that are not installed, see
+    /// [`toolchain::check`](crate::toolchain::check).
+    pub missing_tools: Vec<String>,
+    /// Whether the run was refused before anything was created because a
+    /// tool was missing or a toolchain was outdated.
+    pub refused: bool,
+}
+
+#[derive(Debug)]
+pub struct TargetReport {
+    pub dir: PathBuf,
+    /// Whether makedir created the directory, as opposed to finding it already there.
+    pub created: bool,
+    /// Whether an atomic run undid everything it did to this target after a failure.
+    pub rolled_back: bool,
+    /// The permissions the directory was left with, if makedir changed them.
+    pub mode: Option<u32>,
+    /// Files written or copied inside the directory.
+    pub files: Vec<PathBuf>,
+    pub commands: Vec<CommandReport>,
+    pub errors: Vec<Failure>,
+}
+
+/// An external command makedir ran and what it printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandReport {
+    pub command: CommandSpec,
+    /// `None` if the command could not be started or was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the command could not be started.
+    pub error: Option<String>,
+}
+
+/// What part of the work a failure came from, which decides the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Creating a directory, setting permissions or writing a file failed.
+    Filesystem,
+    /// An external initializer such as `git init` or `cargo init` failed.
+    Initializer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn filesystem(message: String) -> Failure {
+        Failure {
+            kind: FailureKind::Filesystem,
+            message,
+        }
+    }
+
+    pub fn initializer(message: String) -> Failure {
+        Failure {
+            kind: FailureKind::Initializer,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Report {
+    /// The report for a run refused before it started, skipping every directory.
+    pub fn refused(plan: &Plan) -> Report {
+        Report {
+            skipped: plan
+                .targets
+                .iter()
+                .map(|target| target.dir.clone())
+                .collect(),
+            refused: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none() && self.skipped.is_empty()
+    }
+
+    /// The targets that had at least one failure, in plan order.
+    pub fn failed(&self) -> impl Iterator<Item = &TargetReport> {
+        self.targets
+            .iter()
+            .filter(|target| !target.errors.is_empty())
+    }
+
+    /// The process exit code for this report, taken from the first failure.
+    pub fn exit_code(&self) -> i32 {
+        if self.refused {
+            return exit_code::INITIALIZER;
+        }
+        match self.failed().flat_map(|target| &target.errors).next() {
+            None => exit_code::SUCCESS,
+            Some(failure) => match failure.kind {
+                FailureKind::Filesystem => exit_code::FILESYSTEM,
+                FailureKind::Initializer => exit_code::INITIALIZER,
+            },
+        }
+    }
+}
+
+impl TargetReport {
+    /// `created`, `exists`, `failed` or `rolled_back`.
+    pub fn status(&self) -> &'static str {
+        if self.rolled_back {
+            "rolled_back"
+        } else if !self.errors.is_empty() {
+            "failed"
+        } else if self.created {
+            "created"
+        } else {
+            "exists"
+        }
+    }
+}
+
+/// Creates every directory in the plan and runs its steps, `plan.jobs`
+/// directories at a time.
+pub fn execute(plan: &Plan) -> Report {
+    execute_parallel_with(plan, &SystemRunner)
+}
+
+/// Like [`execute`], but runs external commands through `runner`, one
+/// directory at a time.
+pub fn execute_with(plan: &Plan, runner: &dyn Runner) -> Report {
+    let mut report = Report::default();
+    let reporter = Reporter::for_plan(plan);
+    for (i, target) in plan.targets.iter().enumerate() {
+        let target_report = run_target(target, plan, runner, &reporter);
+        let failed = !target_report.errors.is_empty();
+        report.targets.push(target_report);
+
+        if failed && plan.fail_fast {
+            report.skipped = plan.targets[i + 1..]
+                .iter()
+                .map(|target| target.dir.clone())
+                .collect();
+            break;
+        }
+    }
+    report
+}
+
+/// Like [`execute_with`], but processes up to `plan.jobs` directories at
+/// once. Each directory's output is printed in one piece when it is done,
+/// and the report lists directories in plan order regardless of which
+/// finished first.
+pub fn execute_parallel_with(plan: &Plan, runner: &(dyn Runner + Sync)) -> Report {
+    let chains = chains(&plan.targets);
+    if plan.jobs <= 1 || chains.len() <= 1 {
+        return execute_with(plan, runner);
+    }
+
+    let reporter = Reporter::for_plan(plan);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let results: Mutex<Vec<Option<TargetReport>>> =
+        Mutex::new(plan.targets.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..plan.jobs.min(chains.len()) {
+            scope.spawn(|| {
+                while let Some(chain) = chains.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    for &i in chain {
+                        // A fail-fast run starts nothing new after a failure
+                        if stop.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        let buffered = reporter.buffered();
+                        let target_report = run_target(&plan.targets[i], plan, runner, &buffered);
+                        buffered.flush();
+                        if plan.fail_fast && !target_report.errors.is_empty() {
+                            stop.store(true, Ordering::SeqCst);
+                        }
+                        results.lock().unwrap()[i] = Some(target_report);
+                    }
+                }
+            });
+        }
+    });
+
+    let mut report = Report::default();
+    let results = results.into_inner().unwrap();
+    for (target, result) in plan.targets.iter().zip(results) {
+        match result {
+            Some(target_report) => report.targets.push(target_report),
+            None => report.skipped.push(target.dir.clone()),
+        }
+    }
+    report
+}
+
+/// Groups targets that must not run at the same time, because one is
+/// inside the other or they would create the same parents. Each group
+/// keeps plan order and the groups are ordered by their first target.
+fn chains(targets: &[Target]) -> Vec<Vec<usize>> {
+    // The outermost directory each target may create, or the target itself
+    let roots: Vec<PathBuf> = targets
+        .iter()
+        .map(|target| {
+            missing_dirs(&target.dir, &HashSet::new())
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| target.dir.clone())
+        })
+        .collect();
+    let mut chains: Vec<Vec<usize>> = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        let overlaps = |j: &usize| roots[*j].starts_with(root) || root.starts_with(&roots[*j]);
+        let (mut joined, rest): (Vec<_>, Vec<_>) = chains
+            .into_iter()
+            .partition(|chain| chain.iter().any(overlaps));
+        let mut chain: Vec<usize> = joined.drain(..).flatten().collect();
+        chain.push(i);
+        chain.sort_unstable();
+        chains = rest;
+        chains.push(chain);
+    }
+    chains.sort_by_key(|chain| chain[0]);
+    chains
+}
+
+/// Processes one target, with the events and timing around it.
+fn run_target(
+    target: &Target,
+    plan: &Plan,
+    runner: &dyn Runner,
+    reporter: &Reporter,
+) -> TargetReport {
+    reporter.event(Event::Start { dir: &target.dir });
+    let started = Instant::now();
+    let target_report = execute_target(target, plan, runner, reporter);
+    reporter.debug(format_args!(
+        "{} took {:.1?}",
+        target.dir.display(),
+        started.elapsed()
+    ));
+    reporter.event(Event::Finish {
+        target: &target_report,
+    });
+    target_report
+}
+
+fn execute_target(
+    target: &Target,
+    plan: &Plan,
+    runner: &dyn Runner,
+    reporter: &Reporter,
+) -> TargetReport {
+    let dir = &target.dir;
+    let mut report = TargetReport {
+        dir: dir.clone(),
+        created: false,
+        rolled_back: false,
+        mode: None,
+        files: Vec::new(),
+        commands: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut journal = Journal::default();
+
+    if dir.exists() {
+        reporter.say(Role::Notice, "Directory already exists:", dir.display());
+        reporter.event(Event::Exists { path: dir });
+    } else {
+        let missing = missing_dirs(dir, &HashSet::new());
+        let result = fs::create_dir_all(dir);
+        // create_dir_all may fail after creating some of the parents
+        for path in missing.iter().take_while(|p| p.is_dir()) {
+            journal.created_dir(path.clone());
+            reporter.event(Event::Created { path });
+        }
+
+        if let Err(e) = result {
+            reporter.error(
+                format_args!("Failed to create directory {}:", dir.display()),
+                &e,
+            );
+            report.errors.push(Failure::filesystem(format!(
+                "failed to create directory: {}",
+                e
+            )));
+            finish_target(&mut report, journal, plan, reporter);
+            return report;
+        }
+
+        report.created = true;
+        let full_path = fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+        reporter.detail(Role::Notice, "Creating directory:", full_path.display());
+
+        // Only parents created just now, innermost first so they stay reachable
+        let parents = missing.split_last().map_or(&[][..], |(_, parents)| parents);
+        let parents_mode = plan.parents_mode.for_parents(target.mode.as_ref());
+        for parent in parents.iter().rev() {
+            set_attributes(
+                plan,
+                parent,
+                parents_mode,
+                &mut journal,
+                &mut report.errors,
+                reporter,
+            );
+        }
+    }
+
+    report.mode = set_attributes(
+        plan,
+        dir,
+        target.mode.as_ref(),
+        &mut journal,
+        &mut report.errors,
+        reporter,
+    );
+
+    for step in &target.steps {
+        // Atomic and fail-fast runs give up on a target as soon as anything fails
+        if (plan.atomic || plan.fail_fast) && !report.errors.is_empty() {
+            break;
+        }
+
+        let result = match step {
+            Step::Run(command) => {
+                let before = rollback::snapshot(dir);
+                let result = run_command(
+                    runner,
+                    dir,
+                    command,
+                    plan.timeout,
+                    &mut report.commands,
+                    reporter,
+                );
+                journal.new_entries_since(dir, &before);
+                result
+            }
+            Step::WriteFile { name, contents } => create_parents(dir, name, &mut journal, reporter)
+                .and_then(|()| {
+                    journal.writing_file(&dir.join(name));
+                    write_file(dir, name, contents, reporter)
+                })
+                .map(|()| report.files.push(dir.join(name))),
+            Step::CreateDir { name } => create_dirs(&dir.join(name), &mut journal, reporter),
+            Step::CreateFile { name } => create_parents(dir, name, &mut journal, reporter)
+                .and_then(|()| {
+                    let path = dir.join(name);
+                    if path.exists() {
+                        reporter.detail(Role::Notice, "File already exists:", path.display());
+                        return Ok(());
+                    }
+                    journal.writing_file(&path);
+                    write_file(dir, name, "", reporter).map(|()| report.files.push(path))
+                }),
+            Step::CopyFile { from, name } => create_parents(dir, name, &mut journal, reporter)
+                .and_then(|()| {
+                    journal.writing_file(&dir.join(name));
+                    copy_file(from, dir, name, reporter)
+                })
+                .map(|()| report.files.push(dir.join(name))),
+            Step::SetMode { name, mode } => {
+                let path = step_path(dir, name);
+                journal.changing_mode(&path);
+                set_mode(&path, mode, plan.umask, reporter).map(|_| ())
+            }
+            Step::SetOwner { name, owner } => {
+                let path = step_path(dir, name);
+                journal.changing_owner(&path);
+                set_owner(&path, owner, reporter)
+            }
+            Step::SetLicenseField { name, license } => {
+                journal.writing_file(&dir.join(name));
+                set_license_field(dir, name, license, reporter)
+            }
+        };
+        if let Err(e) = result {
+            report.errors.push(e);
+        }
+    }
+
+    finish_target(&mut report, journal, plan, reporter);
+    report
+}
+
+/// Rolls back a failed target when the plan is atomic.
+fn finish_target(report: &mut TargetReport, journal: Journal, plan: &Plan, reporter: &Reporter) {
+    for failure in &report.errors {
+        reporter.event(Event::Error {
+            dir: &report.dir,
+            failure,
+        });
+    }
+    if !plan.atomic || report.errors.is_empty() {
+        return;
+    }
+
+    reporter.say(
+        Role::Notice,
+        "Rolling back changes to:",
+        report.dir.display(),
+    );
+    let errors = journal.rollback(reporter);
+    report.rolled_back = errors.is_empty();
+    report.created = report.created && !report.rolled_back;
+    if report.rolled_back {
+        report.mode = None;
+        report.files.clear();
+        reporter.event(Event::RolledBack { dir: &report.dir });
+    }
+    let errors: Vec<Failure> = errors.into_iter().map(Failure::filesystem).collect();
+    for failure in &errors {
+        reporter.event(Event::Error {
+            dir: &report.dir,
+            failure,
+        });
+    }
+    report.errors.extend(errors);
+}
+
+/// Applies `mode`, returning the permissions `dir` ends up with.
+fn set_mode(dir: &Path, mode: &Mode, umask: u32, reporter: &Reporter) -> Result<u32, Failure> {
+    let metadata = fs::metadata(dir).map_err(|e| {
+        reporter.error(
+            format_args!("Failed to get metadata for {}:", dir.display()),
+            &e,
+        );
+        Failure::filesystem(format!("failed to get metadata: {}", e))
+    })?;
+
+    let mut perms = metadata.permissions();
+    let mode = mode.apply(perms.mode(), metadata.is_dir(), umask);
+    perms.set_mode(mode);
+    if let Err(e) = fs::set_permissions(dir, perms) {
+        reporter.error(
+            format_args!("Failed to set permissions {:o} on {}:", mode, dir.display()),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to set permissions {:o}: {}",
+            mode, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Set permissions {:o} on {}", mode, dir.display()),
+        "",
+    );
+    reporter.event(Event::Mode { path: dir, mode });
+    Ok(mode)
+}
+
+/// Applies the owner, mode, `--shared` bits and ACLs of `plan` to a
+/// directory, in that order: chown can clear the setgid bit, and chmod
+/// rewrites the ACL mask. Returns the permissions set last, if any.
+fn set_attributes(
+    plan: &Plan,
+    dir: &Path,
+    mode: Option<&Mode>,
+    journal: &mut Journal,
+    errors: &mut Vec<Failure>,
+    reporter: &Reporter,
+) -> Option<u32> {
+    let mut applied = None;
+    if !plan.owner.is_empty() {
+        journal.changing_owner(dir);
+        if let Err(e) = set_owner(dir, &plan.owner, reporter) {
+            errors.push(e);
+        }
+    }
+
+    let shared = Mode::shared();
+    for mode in mode.into_iter().chain(plan.shared.then_some(&shared)) {
+        journal.changing_mode(dir);
+        match set_mode(dir, mode, plan.umask, reporter) {
+            Ok(mode) => applied = Some(mode),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for (acl, default) in [(&plan.acl, false), (&plan.default_acl, true)] {
+        if !acl.is_empty() {
+            journal.changing_acl(dir, default);
+            if let Err(e) = set_acl(dir, acl, default, reporter) {
+                errors.push(e);
+            }
+        }
+    }
+    applied
+}
+
+fn set_acl(dir: &Path, acl: &Acl, default: bool, reporter: &Reporter) -> Result<(), Failure> {
+    let kind = if default { "default ACL" } else { "ACL" };
+    if let Err(e) = acl.apply(dir, default) {
+        reporter.error(
+            format_args!("Failed to set {} {} on {}:", kind, acl, dir.display()),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to set {} {}: {}",
+            kind, acl, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Set {} {} on {}", kind, acl, dir.display()),
+        "",
+    );
+    reporter.event(Event::Acl {
+        path: dir,
+        acl,
+        default,
+    });
+    Ok(())
+}
+
+fn set_owner(dir: &Path, owner: &Owner, reporter: &Reporter) -> Result<(), Failure> {
+    if let Err(e) = unix_fs::chown(dir, owner.uid(), owner.gid()) {
+        reporter.error(
+            format_args!("Failed to set owner {} on {}:", owner, dir.display()),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to set owner {}: {}",
+            owner, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Set owner {} on {}", owner, dir.display()),
+        "",
+    );
+    reporter.event(Event::Owner { path: dir, owner });
+    Ok(())
+}
+
+fn run_command(
+    runner: &dyn Runner,
+    dir: &Path,
+    command: &CommandSpec,
+    timeout: Option<Duration>,
+    commands: &mut Vec<CommandReport>,
+    reporter: &Reporter,
+) -> Result<(), Failure> {
+    if reporter.shows(Verbosity::Debug) {
+        let mut argv = command.argv();
+        let resolved = runner::resolve(&command.program);
+        if let Some(program) = &resolved {
+            argv[0] = program.to_str().unwrap_or(&command.program);
+        }
+        reporter.debug(format_args!("argv {:?} in {}", argv, dir.display()));
+    }
+    let started = Instant::now();
+    let on_line = |stream, line: &str| reporter.command_line(dir, stream, line);
+    let options = RunOptions {
+        timeout,
+        on_line: reporter
+            .shows(Verbosity::Output)
+            .then_some(&on_line as &LineHandler),
+    };
+    let output = runner.run_with(command, dir, &options);
+    reporter.debug(format_args!("{} took {:.1?}", command, started.elapsed()));
+
+    let record = match &output {
+        Ok(output) => CommandReport {
+            command: command.clone(),
+            exit_code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            error: None,
+        },
+        Err(e) => CommandReport {
+            command: command.clone(),
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(e.to_string()),
+        },
+    };
+    reporter.event(Event::Command {
+        dir,
+        command: &record,
+    });
+    commands.push(record);
+
+    match output {
+        Ok(output) if output.status.success() => {
+            reporter.detail(
+                Role::Success,
+                "Successfully executed:",
+                format_args!("{} in {}", command, dir.display()),
+            );
+            Ok(())
+        }
+        Ok(output) => {
+            // Always show errors regardless of verbose flag
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            reporter.error(
+                "Failed to execute:",
+                format_args!("{} in {} {}", command, dir.display(), stderr),
+            );
+            Err(Failure::initializer(format!(
+                "`{}` failed: {}",
+                command,
+                stderr.trim()
+            )))
+        }
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+            reporter.error(
+                "Killed:",
+                format_args!("{} in {} {}", command, dir.display(), e),
+            );
+            Err(Failure::initializer(format!(
+                "`{}` {} and was killed",
+                command, e
+            )))
+        }
+        Err(e) => {
+            reporter.error(
+                "Error running:",
+                format_args!("{} in {}", command, dir.display()),
+            );
+            Err(Failure::initializer(format!(
+                "could not run `{}`: {}",
+                command, e
+            )))
+        }
+    }
+}
+
+fn write_file(dir: &Path, name: &str, contents: &str, reporter: &Reporter) -> Result<(), Failure> {
+    if let Err(e) = fs::write(dir.join(name), contents) {
+        reporter.error(
+            format_args!("Failed to create {} in {}:", name, dir.display()),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to create {}: {}",
+            name, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Successfully created {} in {}.", name, dir.display()),
+        "",
+    );
+    reporter.event(Event::File {
+        path: &dir.join(name),
+    });
+    Ok(())
+}
+
+fn copy_file(from: &Path, dir: &Path, name: &str, reporter: &Reporter) -> Result<(), Failure> {
+    if let Err(e) = fs::copy(from, dir.join(name)) {
+        reporter.error(
+            format_args!(
+                "Failed to copy {} to {}:",
+                from.display(),
+                dir.join(name).display()
+            ),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to copy {}: {}",
+            name, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Successfully copied {} to {}.", name, dir.display()),
+        "",
+    );
+    reporter.event(Event::File {
+        path: &dir.join(name),
+    });
+    Ok(())
+}
+
+/// Creates the directories leading up to a file written by a template.
+fn create_parents(
+    dir: &Path,
+    name: &str,
+    journal: &mut Journal,
+    reporter: &Reporter,
+) -> Result<(), Failure> {
+    match dir.join(name).parent() {
+        Some(parent) => create_dirs(parent, journal, reporter),
+        None => Ok(()),
+    }
+}
+
+/// Creates `path` and any missing parents, recording them in `journal`.
+fn create_dirs(path: &Path, journal: &mut Journal, reporter: &Reporter) -> Result<(), Failure> {
+    if path.exists() {
+        return Ok(());
+    }
+    let missing = missing_dirs(path, &HashSet::new());
+    let result = fs::create_dir_all(path);
+    for path in missing.into_iter().take_while(|p| p.is_dir()) {
+        journal.created_dir(path);
+    }
+    result.map_err(|e| {
+        reporter.error(
+            format_args!("Failed to create directory {}:", path.display()),
+            &e,
+        );
+        Failure::filesystem(format!("failed to create directory: {}", e))
+    })
+}
+
+fn set_license_field(
+    dir: &Path,
+    name: &str,
+    license: &str,
+    reporter: &Reporter,
+) -> Result<(), Failure> {
+    let path = dir.join(name);
+    let result = fs::read_to_string(&path).and_then(|manifest| {
+        let updated = if name == "Cargo.toml" {
+            license::set_cargo_license(&manifest, license)
+        } else {
+            license::set_package_json_license(&manifest, license)
+        };
+        fs::write(&path, updated)
+    });
+    if let Err(e) = result {
+        reporter.error(
+            format_args!("Failed to set the license in {}:", path.display()),
+            &e,
+        );
+        return Err(Failure::filesystem(format!(
+            "failed to set the license in {}: {}",
+            name, e
+        )));
+    }
+
+    reporter.detail(
+        Role::Success,
+        format_args!("Set license {} in {}", license, path.display()),
+        "",
+    );
+    reporter.event(Event::License {
+        path: &path,
+        license,
+    });
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! Exit codes returned by the `makedir` binary.
+//!
+//! When several things fail, the code of the first failure is used.
+
+/// Every directory was created and initialized.
+pub const SUCCESS: i32 = 0;
+/// The command line was invalid; nothing was created.
+pub const USAGE: i32 = 1;
+/// Creating a directory, setting its permissions or writing a file failed.
+pub const FILESYSTEM: i32 = 2;
+/// An external initializer such as `git init` or `npm init -y` failed, or
+/// the tool it needs is not installed.
+pub const INITIALIZER: i32 = 3;
+/// `makedir verify` found entries missing or with the wrong type or permissions.
+pub const MISMATCH: i32 = 4;
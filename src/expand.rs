@@ -0,0 +1,194 @@
+//! Brace expansion for directory arguments, as done by bash.
+//!
+//! `src/{api,web}` becomes `src/api` and `src/web`, `day{01..03}` becomes
+//! `day01` to `day03`, and `{a..e..2}` becomes `a`, `c` and `e`. Braces nest,
+//! and a backslash keeps a `{`, `}`, `,` or `\` after it literal. Other
+//! backslashes, and every backslash in a word without braces, stay in the
+//! name. Braces that do not form a list or a range, such as `{x}` or `{a..}`,
+//! are left as they are.
+
+/// More directories than this from one argument is almost certainly a typo.
+pub const MAX_EXPANSIONS: usize = 10_000;
+
+/// Expands every brace expression in `word`.
+pub fn expand(word: &str) -> Result<Vec<String>, String> {
+    if !word.contains(['{', '}']) {
+        return Ok(vec![word.to_string()]);
+    }
+    let chars: Vec<char> = word.chars().collect();
+    let words = expand_chars(&chars).map_err(|_| {
+        format!(
+            "`{}` expands to more than {} directories",
+            word, MAX_EXPANSIONS
+        )
+    })?;
+    Ok(words.iter().map(|w| unescape(w)).collect())
+}
+
+struct TooMany;
+
+fn expand_chars(chars: &[char]) -> Result<Vec<String>, TooMany> {
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '{' => {
+                if let Some(close) = matching_close(chars, i) {
+                    if let Some(items) = brace_items(&chars[i + 1..close])? {
+                        let prefix: String = chars[..i].iter().collect();
+                        let suffixes = expand_chars(&chars[close + 1..])?;
+                        let mut words = Vec::new();
+                        for item in items {
+                            for middle in expand_chars(&item)? {
+                                for suffix in &suffixes {
+                                    if words.len() == MAX_EXPANSIONS {
+                                        return Err(TooMany);
+                                    }
+                                    words.push(format!("{}{}{}", prefix, middle, suffix));
+                                }
+                            }
+                        }
+                        return Ok(words);
+                    }
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    Ok(vec![chars.iter().collect()])
+}
+
+/// The index of the `}` closing the `{` at `open`.
+fn matching_close(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Splits the inside of a brace expression into its alternatives, or `None`
+/// when it is neither a list nor a range.
+fn brace_items(body: &[char]) -> Result<Option<Vec<Vec<char>>>, TooMany> {
+    let mut items = vec![Vec::new()];
+    let mut depth = 0;
+    let mut i = 0;
+    while i < body.len() {
+        let c = body[i];
+        match c {
+            '\\' => {
+                items.last_mut().unwrap().push(c);
+                if let Some(&next) = body.get(i + 1) {
+                    items.last_mut().unwrap().push(next);
+                }
+                i += 2;
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(Vec::new());
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        items.last_mut().unwrap().push(c);
+        i += 1;
+    }
+
+    if items.len() > 1 {
+        return Ok(Some(items));
+    }
+    let body: String = body.iter().collect();
+    Ok(sequence(&body)?.map(|words| words.into_iter().map(|w| w.chars().collect()).collect()))
+}
+
+/// Expands `start..end` or `start..end..step`, with numbers or single letters.
+fn sequence(body: &str) -> Result<Option<Vec<String>>, TooMany> {
+    let parts: Vec<&str> = body.split("..").collect();
+    let (start, end, step) = match parts.as_slice() {
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => match step.parse::<i64>() {
+            Ok(step) => (*start, *end, step.unsigned_abs().max(1)),
+            Err(_) => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    if let (Ok(from), Ok(to)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let count = from.abs_diff(to) / step + 1;
+        if count > MAX_EXPANSIONS as u64 {
+            return Err(TooMany);
+        }
+        // `01..10` pads every number to the width of the wider end
+        let padded = |s: &str| {
+            s.trim_start_matches('-').len() > 1 && s.trim_start_matches('-').starts_with('0')
+        };
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        let words = steps(from, to, step)
+            .map(|n| format!("{:0width$}", n, width = width))
+            .collect();
+        return Ok(Some(words));
+    }
+
+    let letter = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(c as i64),
+            _ => None,
+        }
+    };
+    if let (Some(from), Some(to)) = (letter(start), letter(end)) {
+        let words = steps(from, to, step)
+            .map(|c| char::from(c as u8).to_string())
+            .collect();
+        return Ok(Some(words));
+    }
+    Ok(None)
+}
+
+fn steps(from: i64, to: i64, step: u64) -> impl Iterator<Item = i64> {
+    let count = from.abs_diff(to) / step + 1;
+    let step = if from <= to {
+        step as i64
+    } else {
+        -(step as i64)
+    };
+    (0..count as i64).map(move |i| from + i * step)
+}
+
+/// Removes the backslashes that kept braces, commas and backslashes out of
+/// expansion.
+fn unescape(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(&next) = chars.peek().filter(|&&next| "{},\\".contains(next)) {
+                out.push(next);
+                chars.next();
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,16 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Every directory `create_dir_all(dir)` would create, outermost first.
+///
+/// Paths in `planned` are treated as if they already existed.
+pub(crate) fn missing_dirs(dir: &Path, planned: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let mut missing: Vec<PathBuf> = dir
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .take_while(|p| !p.exists() && !planned.contains(*p))
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    missing
+}
@@ -0,0 +1,61 @@
+//! makedir creates directories and bootstraps projects inside them.
+//!
+//! The CLI is a thin wrapper over this library: arguments are turned into a
+//! [`CreateRequest`], expanded into a [`Plan`] of concrete steps, and the plan
+//! is carried out by [`execute`]. [`dry_run::describe`] lists the same steps
+//! without performing them.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), String> {
+//! use makedir::{execute, Action, CreateRequest, Plan};
+//!
+//! let request = CreateRequest {
+//!     dirs: vec!["api".into()],
+//!     actions: vec![Action::Git, Action::Readme],
+//!     mode: Some(0o755.into()),
+//!     ..Default::default()
+//! };
+//! let report = execute(&Plan::new(&request)?);
+//! assert!(report.is_success());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod acl;
+pub mod action;
+pub mod cli;
+pub mod config;
+pub mod dry_run;
+pub mod execute;
+pub mod exit_code;
+pub mod expand;
+mod fs_util;
+pub mod json;
+pub mod license;
+pub mod mode;
+pub mod mtree;
+pub mod outline;
+pub mod output;
+pub mod owner;
+pub mod plan;
+pub mod preset;
+pub mod reporter;
+mod rollback;
+pub mod runner;
+pub mod spec;
+pub mod template;
+pub mod templates;
+pub mod toml;
+pub mod toolchain;
+pub mod user_template;
+pub mod yaml;
+
+pub use action::Action;
+pub use config::Config;
+pub use execute::{
+    execute, execute_parallel_with, execute_with, CommandReport, Failure, FailureKind, Report,
+    TargetReport,
+};
+pub use plan::{CreateRequest, Plan, Step, Target};
+pub use runner::{CommandSpec, RunOptions, Runner, SystemRunner};
+pub use user_template::UserTemplate;
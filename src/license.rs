@@ -0,0 +1,262 @@
+//! The bundled license texts and the SPDX expressions `--license` accepts.
+//!
+//! An expression is one license identifier, or several joined with `OR` or
+//! `AND`, e.g. `MIT OR Apache-2.0`. A single license is written to `LICENSE`;
+//! each license of a compound expression gets its own `LICENSE-<suffix>` file.
+
+/// A license makedir can write.
+#[derive(Debug, PartialEq, Eq)]
+pub struct License {
+    /// SPDX identifier.
+    pub id: &'static str,
+    /// File name suffix when several licenses are written, as in `LICENSE-MIT`.
+    pub suffix: &'static str,
+    /// Template for the license text.
+    pub text: &'static str,
+}
+
+// Only MIT and BSD-3-Clause have a copyright line to fill in; the others are
+// written out verbatim.
+pub const CATALOG: [License; 6] = [
+    License {
+        id: "MIT",
+        suffix: "MIT",
+        text: include_str!("licenses/MIT.txt"),
+    },
+    License {
+        id: "Apache-2.0",
+        suffix: "APACHE",
+        text: include_str!("licenses/Apache-2.0.txt"),
+    },
+    License {
+        id: "MPL-2.0",
+        suffix: "MPL",
+        text: include_str!("licenses/MPL-2.0.txt"),
+    },
+    License {
+        id: "GPL-3.0-only",
+        suffix: "GPL",
+        text: include_str!("licenses/GPL-3.0.txt"),
+    },
+    License {
+        id: "GPL-3.0-or-later",
+        suffix: "GPL",
+        text: include_str!("licenses/GPL-3.0.txt"),
+    },
+    License {
+        id: "BSD-3-Clause",
+        suffix: "BSD",
+        text: include_str!("licenses/BSD-3-Clause.txt"),
+    },
+];
+
+/// Looks up a license by SPDX identifier, ignoring case as SPDX does.
+pub fn find(id: &str) -> Option<&'static License> {
+    CATALOG
+        .iter()
+        .find(|license| license.id.eq_ignore_ascii_case(id))
+}
+
+/// A parsed SPDX license expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    licenses: Vec<&'static License>,
+    operators: Vec<&'static str>,
+}
+
+impl Expression {
+    /// Parses `MIT`, `MIT OR Apache-2.0`, `(MIT OR Apache-2.0)` and the like.
+    ///
+    /// Identifiers are normalized to their SPDX spelling, so `mit or apache-2.0`
+    /// becomes `MIT OR Apache-2.0`.
+    pub fn parse(s: &str) -> Result<Expression, String> {
+        let trimmed = s.trim();
+        let inner = trimmed
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap_or(trimmed);
+
+        let mut expression = Expression {
+            licenses: Vec::new(),
+            operators: Vec::new(),
+        };
+        for (i, word) in inner.split_whitespace().enumerate() {
+            if i % 2 == 1 {
+                match word.to_ascii_uppercase().as_str() {
+                    "OR" => expression.operators.push("OR"),
+                    "AND" => expression.operators.push("AND"),
+                    "WITH" => {
+                        return Err("license exceptions (`WITH`) are not supported".to_string())
+                    }
+                    _ => return Err(format!("expected OR or AND, found `{}`", word)),
+                }
+                continue;
+            }
+            let license = find(word).ok_or_else(|| {
+                let ids: Vec<&str> = CATALOG.iter().map(|l| l.id).collect();
+                format!(
+                    "unsupported license `{}`, available: {}",
+                    word,
+                    ids.join(", ")
+                )
+            })?;
+            expression.licenses.push(license);
+        }
+
+        if expression.licenses.is_empty() || expression.operators.len() >= expression.licenses.len()
+        {
+            return Err(format!("invalid license expression `{}`", s));
+        }
+        let licenses = &expression.licenses;
+        for (i, license) in licenses.iter().enumerate() {
+            if let Some(other) = licenses[..i].iter().find(|o| o.suffix == license.suffix) {
+                return Err(format!(
+                    "`{}` and `{}` would both be written to LICENSE-{}",
+                    other.id, license.id, license.suffix
+                ));
+            }
+        }
+        Ok(expression)
+    }
+
+    pub fn licenses(&self) -> &[&'static License] {
+        &self.licenses
+    }
+
+    /// The file each license is written to.
+    pub fn files(&self) -> Vec<(String, &'static License)> {
+        match self.licenses.as_slice() {
+            [license] => vec![("LICENSE".to_string(), *license)],
+            licenses => licenses
+                .iter()
+                .map(|license| (format!("LICENSE-{}", license.suffix), *license))
+                .collect(),
+        }
+    }
+}
+
+/// The normalized expression, e.g. `MIT OR Apache-2.0`.
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, license) in self.licenses.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {} ", self.operators[i - 1])?;
+            }
+            f.write_str(license.id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets `license = "<expression>"` in the `[package]` table of a Cargo.toml.
+pub(crate) fn set_cargo_license(manifest: &str, expression: &str) -> String {
+    let field = format!("license = {}", crate::template::json_string(expression));
+    let mut lines: Vec<String> = manifest.lines().map(str::to_string).collect();
+
+    let start = match lines.iter().position(|line| line.trim() == "[package]") {
+        Some(start) => start,
+        None => {
+            lines.insert(0, "[package]".to_string());
+            lines.insert(1, field);
+            return lines.join("\n") + "\n";
+        }
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| start + 1 + i);
+
+    let existing = (start + 1..end).find(|&i| {
+        let line = lines[i].trim_start();
+        line.strip_prefix("license")
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    });
+    match existing {
+        Some(i) => lines[i] = field,
+        None => {
+            // Keep the blank lines that separate [package] from the next table
+            let mut at = end;
+            while at > start + 1 && lines[at - 1].trim().is_empty() {
+                at -= 1;
+            }
+            lines.insert(at, field);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Sets the top-level `"license"` field of a package.json.
+pub(crate) fn set_package_json_license(manifest: &str, expression: &str) -> String {
+    let value = crate::template::json_string(expression);
+    if let Some(colon) = top_level_key(manifest, "\"license\"") {
+        let rest = &manifest[colon + 1..];
+        let start = rest.len() - rest.trim_start().len();
+        if let Some(len) = string_literal_len(&rest[start..]) {
+            let value_start = colon + 1 + start;
+            return format!(
+                "{}{}{}",
+                &manifest[..value_start],
+                value,
+                &manifest[value_start + len..]
+            );
+        }
+    }
+    match manifest.find('{') {
+        Some(open) => {
+            let (head, tail) = manifest.split_at(open + 1);
+            let separator = if tail.trim_start().starts_with('}') {
+                ""
+            } else {
+                ","
+            };
+            format!("{}\n  \"license\": {}{}{}", head, value, separator, tail)
+        }
+        None => manifest.to_string(),
+    }
+}
+
+/// The offset of the colon after `key` (quotes included) in the outermost
+/// object, skipping nested objects, arrays and string values that happen to
+/// spell the same.
+fn top_level_key(manifest: &str, key: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut at = 0;
+    while at < manifest.len() {
+        let rest = &manifest[at..];
+        match rest.as_bytes()[0] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b'"' => {
+                let len = string_literal_len(rest)?;
+                let after = &rest[len..];
+                let colon = at + len + after.len() - after.trim_start().len();
+                if depth == 1 && &rest[..len] == key && manifest[colon..].starts_with(':') {
+                    return Some(colon);
+                }
+                at += len;
+                continue;
+            }
+            _ => {}
+        }
+        at += rest.chars().next()?.len_utf8();
+    }
+    None
+}
+
+/// The length of the JSON string literal at the start of `s`, quotes included.
+fn string_literal_len(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    let mut escaped = false;
+    for (i, c) in chars {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
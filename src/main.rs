@@ -1,307 +1,356 @@
 use std::env;
-use std::fs;
-use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::process::Command;
 
+use makedir::mtree::Manifest;
+use makedir::output::{self, Format};
+use makedir::reporter::{ColorChoice, Palette, Reporter, Role};
+use makedir::toolchain::{Outdated, Tool, VersionCheck, TOOLS};
+use makedir::{cli, config, dry_run, exit_code, runner, toolchain};
+use makedir::{execute, Action, Config, CreateRequest, Plan, Report};
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    if args.len() < 2 {
-        let usage_message = "\
-\x1b[1;33mUsage:\x1b[0m makedir [directories] [options]
+    if args.is_empty() {
+        let reporter = Reporter::new(ColorChoice::Auto, &Palette::default());
+        eprint!("{}", cli::usage(&reporter.stderr));
+        std::process::exit(exit_code::USAGE);
+    }
 
-\x1b[1;33mHelp:\x1b[0m  Creates one or more directories with optional project initialization.
-       Multiple directories can be specified, and options apply to all of them.
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            Reporter::new(ColorChoice::Auto, &Palette::default()).error(e, "");
+            std::process::exit(exit_code::USAGE);
+        }
+    };
 
-\x1b[1;33mOptions:\x1b[0m
-    \x1b[32m--git,     -g\x1b[0m         Initialize a Git repository.
-    \x1b[32m--readme,  -r\x1b[0m         Generate a template README.md file.
-    \x1b[32m--license, -l\x1b[0m         Generate a template MIT License file.
-    \x1b[32m--docker,  -do\x1b[0m        Generate a template Docker file.
-    \x1b[32m--go,      -go\x1b[0m        Initialize a Go project.
-    \x1b[32m--cargo,   -c\x1b[0m         Initialize a Rust Cargo project.
-    \x1b[32m--npm,     -n\x1b[0m         Initialize an npm project (package.json).
-    \x1b[32m--bun,     -b\x1b[0m         Initialize a Bun project.
-    \x1b[32m--yarn,    -y\x1b[0m         Initialize a Yarn project.
-    \x1b[32m--pnpm,    -p\x1b[0m         Initialize a pnpm project.
-    \x1b[32m--deno,    -d\x1b[0m         Initialize a Deno project (deno.json).
-    \x1b[32m--verbose, -v\x1b[0m         Show detailed output from commands.
-    \x1b[32m           -###\x1b[0m       Set directory permissions (octal format, e.g., -700, -755).
-";
-        eprintln!("{}", usage_message);
-        std::process::exit(1);
+    let reporter = Reporter::new(config.color.unwrap_or_default(), &config.palette);
+    match (args[0].as_str(), args.get(1).map(String::as_str)) {
+        ("config", Some("show")) => return show_config(&config),
+        ("presets", Some("list")) => return list_presets(&config, &reporter),
+        ("verify", _) => std::process::exit(verify(&args[1..], &config)),
+        ("doctor", _) => std::process::exit(doctor(&args[1..], &reporter)),
+        _ => {}
     }
 
-    // Separate directory names from flags and permissions
-    let mut dirs: Vec<String> = Vec::new();
-    let mut flags: Vec<String> = Vec::new();
-    let mut permissions: Option<u32> = None;
-    let mut verbose = false;
+    let parsed = cli::parse_args_with_config(&args, &config);
+    let reporter = Reporter::new(parsed.request.color, &parsed.request.palette);
+    for error in &parsed.errors {
+        reporter.error(error, "");
+    }
+    if !parsed.errors.is_empty() {
+        std::process::exit(exit_code::USAGE);
+    }
 
-    let mut i = 1;
-    while i < args.len() {
-        let arg = &args[i];
+    if parsed.request.dirs.is_empty() {
+        reporter.error("No directories provided.", "");
+        std::process::exit(exit_code::USAGE);
+    }
 
-        if arg.starts_with("-") {
-            // Check if it's a permission tag (e.g., -700)
-            if arg.len() > 1 && arg.chars().nth(1).unwrap().is_numeric() {
-                // Validate permission format (must be 3 digits between 000-777)
-                let perm_str = &arg[1..];
-                if perm_str.len() <= 3 && perm_str.chars().all(|c| c >= '0' && c <= '7') {
-                    if let Ok(perm) = u32::from_str_radix(perm_str, 8) {
-                        permissions = Some(perm);
-                    } else {
-                        eprintln!("\x1b[1;31mInvalid permission format: {}\x1b[0m", arg);
-                    }
-                } else {
-                    eprintln!("\x1b[1;31mInvalid permission format: {}. Must be 3 octal digits (000-777).\x1b[0m", arg);
-                }
-            }
-            // Check for verbose flag
-            else if arg == "--verbose" || arg == "-v" {
-                verbose = true;
-            }
-            // It's a regular action flag
-            else {
-                flags.push(arg.clone());
-            }
-        } else {
-            // Not a flag, must be a directory
-            dirs.push(arg.clone());
-        }
+    let mut request = parsed.request;
+    fill_author_from_git(&mut request);
 
-        i += 1;
+    let plan = match Plan::new(&request) {
+        Ok(plan) => plan,
+        Err(e) => {
+            reporter.error(e, "");
+            std::process::exit(exit_code::USAGE);
+        }
+    };
+    if request.dry_run {
+        let changes = dry_run::describe(&plan);
+        if request.output == Format::Json {
+            println!("{}", output::dry_run_json(&changes));
+            return;
+        }
+        println!(
+            "{}",
+            reporter
+                .stdout
+                .paint(Role::Notice, "Dry run, no changes will be made:")
+        );
+        for line in changes {
+            println!("  {}", line);
+        }
+        return;
     }
 
-    if dirs.is_empty() {
-        eprintln!("\x1b[1;31mNo directories provided.\x1b[0m");
-        std::process::exit(1);
+    // Fail before creating anything rather than after the first directory
+    let missing = toolchain::check(&plan);
+    for problem in &missing {
+        reporter.error("Missing tool:", problem);
     }
+    let (toolchains, outdated) = match missing.is_empty() {
+        true => check_versions(&plan),
+        false => (Vec::new(), false),
+    };
 
-    // Process each directory
-    for dir in dirs {
-        let path = Path::new(&dir);
-        if path.exists() {
-            println!("\x1b[1;33mDirectory already exists:\x1b[0m {}", dir);
-        } else if let Err(e) = fs::create_dir_all(&dir) {
-            eprintln!("\x1b[1;31mFailed to create directory {}:\x1b[0m {}", dir, e);
-            continue;
-        } else if verbose {
-            match std::fs::canonicalize(&dir) {
-                Ok(full_path) => println!(
-                    "\x1b[1;33mCreating directory:\x1b[0m {}",
-                    full_path.display()
-                ),
-                Err(_) => println!("\x1b[1;33mCreating directory:\x1b[0m {}", dir),
-            }
-        }
+    let mut report = if !missing.is_empty() || outdated {
+        Report::refused(&plan)
+    } else {
+        execute(&plan)
+    };
+    report.toolchains = toolchains;
+    report.missing_tools = missing;
+    match request.output {
+        Format::Json => println!("{}", output::report_json(&report)),
+        // The missing or outdated tools were already reported
+        Format::Text if report.refused => {}
+        Format::Text if !report.is_success() => print_summary(&report, &reporter),
+        Format::Text => {}
+    }
+    std::process::exit(report.exit_code());
+}
 
-        // Set permissions if specified
-        if let Some(mode) = permissions {
-            match fs::metadata(&dir) {
-                Ok(metadata) => {
-                    let mut perms = metadata.permissions();
-                    perms.set_mode(mode);
-                    if let Err(e) = fs::set_permissions(&dir, perms) {
-                        eprintln!(
-                            "\x1b[1;31mFailed to set permissions {} on {}:\x1b[0m {}",
-                            mode, dir, e
-                        );
-                    } else if verbose {
-                        println!("\x1b[1;32mSet permissions {:o} on {}\x1b[0m", mode, dir);
-                    }
-                }
-                Err(e) => eprintln!("\x1b[1;31mFailed to get metadata for {}:\x1b[0m {}", dir, e),
-            }
+/// Checks the plan's minimum toolchain versions, and whether the run is
+/// refused because one is not met and the plan refuses outdated toolchains.
+fn check_versions(plan: &Plan) -> (Vec<VersionCheck>, bool) {
+    let reporter = Reporter::for_plan(plan);
+    let checks = toolchain::check_versions(plan);
+    let mut refused = false;
+    for check in &checks {
+        if check.is_met() {
+            reporter.detail(Role::Success, "Toolchain:", check);
+        } else if plan.outdated == Outdated::Warn {
+            reporter.warn("Warning:", check);
+        } else {
+            reporter.error("Outdated tool:", check);
+            refused = true;
         }
+    }
+    (checks, refused)
+}
 
-        let dir_path = Path::new(&dir);
+/// Falls back to `git config user.name`/`user.email` for generated files,
+/// whenever a built-in template, user template or spec may use `{{ author }}`.
+fn fill_author_from_git(request: &mut CreateRequest) {
+    let renders = request
+        .actions
+        .iter()
+        .any(|action| matches!(action, Action::Readme | Action::License))
+        || !request.templates.is_empty()
+        || request.spec.is_some();
+    if !renders {
+        return;
+    }
+    let git_config = |key: &str| {
+        let output = Command::new("git").args(["config", key]).output().ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+    if request.author_name.is_none() {
+        request.author_name = git_config("user.name");
+    }
+    if request.author_email.is_none() {
+        request.author_email = git_config("user.email");
+    }
+}
 
-        // Helper to run commands within a directory
-        let run_command = |cmd: &str| {
-            let output = Command::new("sh")
-                .arg("-c")
-                .arg(cmd)
-                .current_dir(dir_path)
-                .output();
+fn show_config(config: &Config) {
+    match Config::path() {
+        Some(path) if path.exists() => println!("# Config file: {}", path.display()),
+        Some(path) => println!("# Config file: {} (not found)", path.display()),
+        None => println!("# Config file: none"),
+    }
+    for name in config::ENV_VARS {
+        if let Ok(value) = env::var(name) {
+            println!("# Environment: {}={}", name, value);
+        }
+    }
+    println!();
+    print!("{}", config.to_toml());
+}
 
-            if let Ok(output) = output {
-                if output.status.success() {
-                    if verbose {
-                        let _stdout = String::from_utf8_lossy(&output.stdout);
-                        println!("\x1b[1;32mSuccessfully executed:\x1b[0m {} in {}", cmd, dir);
-                    }
+fn list_presets(config: &Config, reporter: &Reporter) {
+    if config.presets.is_empty() {
+        println!("No presets defined. Add [preset.<name>] tables to the config file.");
+        return;
+    }
+    for preset in &config.presets {
+        match config.resolve_preset(&preset.name) {
+            Ok(resolved) => {
+                let extends = if preset.extends.is_empty() {
+                    String::new()
                 } else {
-                    // Always show errors regardless of verbose flag
-                    eprintln!(
-                        "\x1b[1;31mFailed to execute:\x1b[0m {} in {} {}",
-                        cmd,
-                        dir,
-                        String::from_utf8_lossy(&output.stderr)
-                    );
+                    format!(" (extends {})", preset.extends.join(", "))
+                };
+                println!(
+                    "{}{}: {}",
+                    reporter
+                        .stdout
+                        .paint(Role::Highlight, format_args!("@{}", preset.name)),
+                    extends,
+                    resolved.to_args().join(" ")
+                );
+                for requirement in &resolved.requires {
+                    println!("  requires {}", requirement);
                 }
-            } else {
-                eprintln!("\x1b[1;31mError running:\x1b[0m {} in {}", cmd, dir);
             }
-        };
-
-        // Process each flag for the current directory
-        for flag in &flags {
-            match flag.as_str() {
-                "--git" | "-g" => run_command("git init"),
-                "--npm" | "-n" => run_command("npm init -y"),
-                "--bun" | "-b" => run_command("bun init"),
-                "--yarn" | "-y" => run_command("yarn init -y"),
-                "--pnpm" | "-p" => run_command("pnpm init"),
-                "--cargo" | "-c" => run_command("cargo init"),
-                "--go" | "-go" => run_command(&format!("go mod init {}", dir)),
-                "--deno" | "-d" => {
-                    if let Err(e) = fs::write(
-                        dir_path.join("deno.json"),
-                        "{\n  \"importMap\": \"./import_map.json\"\n}",
-                    ) {
-                        eprintln!(
-                            "\x1b[1;31mFailed to create deno.json in {}:\x1b[0m {}",
-                            dir, e
-                        );
-                    } else if verbose {
-                        println!(
-                            "\x1b[1;32mSuccessfully created deno.json in {}.\x1b[0m",
-                            dir
-                        );
-                    }
-                }
-                "--docker" | "-do" => {
-                    let dockerfile_content = r#"
-                        # Base image (Default: Debian)
-                        ARG BASE_IMAGE=debian:latest
-                        FROM $BASE_IMAGE AS builder
-
-                        # Set working directory
-                        WORKDIR /app
+            Err(e) => reporter.error(format_args!("@{}: {}", preset.name, e), ""),
+        }
+    }
+}
 
-                        # Copy project files
-                        COPY . .
+/// `makedir verify --mtree <file> [root]`, returning the exit code.
+fn verify(args: &[String], config: &Config) -> i32 {
+    // `--mtree` means something else to the usual parser, which handles the rest
+    let mut spec = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mtree" => spec = args.next(),
+            _ => rest.push(arg.clone()),
+        }
+    }
+    let parsed = cli::parse_args_with_config(&rest, config);
+    let request = parsed.request;
+    let reporter = Reporter::new(request.color, &request.palette);
+    for error in &parsed.errors {
+        reporter.error(error, "");
+    }
+    if !parsed.errors.is_empty() {
+        return exit_code::USAGE;
+    }
 
-                        # Install dependencies based on the selected stack
-                        ARG STACK=node
-                        RUN case "$STACK" in \
-                                node) apt update && apt install -y curl && curl -fsSL https://deb.nodesource.com/setup_16.x | bash - && apt install -y nodejs ;; \
-                                python) apt update && apt install -y python3 python3-pip ;; \
-                                rust) apt update && apt install -y curl && curl https://sh.rustup.rs -sSf | sh -s -- -y ;; \
-                                go) apt update && apt install -y golang ;; \
-                                deno) curl -fsSL https://deno.land/install.sh | sh ;; \
-                                *) echo "No valid stack specified"; exit 1 ;; \
-                            esac
+    // Only the flags that apply to every command are allowed
+    let mut expected = cli::parse_args_with_config(&[], config).request;
+    expected.dirs = request.dirs.clone();
+    expected.color = request.color;
+    expected.output = request.output;
+    let spec = match spec {
+        Some(spec) if request == expected && request.dirs.len() <= 1 => spec,
+        _ => {
+            reporter.error("Usage: makedir verify --mtree <file> [root]", "");
+            return exit_code::USAGE;
+        }
+    };
+    let root = request
+        .dirs
+        .first()
+        .map_or(".".to_string(), |dir| dir.to_string_lossy().into_owned());
+    let manifest = match Manifest::load(Path::new(spec)) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            reporter.error(format_args!("Invalid mtree spec: {}", e), "");
+            return exit_code::USAGE;
+        }
+    };
 
-                        # Expose port (Modify as needed)
-                        EXPOSE 3000
+    let problems = manifest.verify(Path::new(&root));
+    let code = match problems.is_empty() {
+        true => exit_code::SUCCESS,
+        false => exit_code::MISMATCH,
+    };
+    if request.output == Format::Json {
+        println!("{}", output::verify_json(code, &problems));
+        return code;
+    }
+    if problems.is_empty() {
+        println!(
+            "{}",
+            reporter
+                .stdout
+                .paint(Role::Success, format_args!("{} matches {}", root, spec))
+        );
+        return exit_code::SUCCESS;
+    }
+    println!(
+        "{}",
+        reporter.stdout.paint(
+            Role::Error,
+            format_args!(
+                "{} differs from {} in {} entries:",
+                root,
+                spec,
+                problems.len()
+            )
+        )
+    );
+    for problem in problems {
+        println!("  {}", problem);
+    }
+    exit_code::MISMATCH
+}
 
-                        # Command to run the application (Modify based on project type)
-                        CMD ["echo", "Container is running, customize CMD as needed!"]
-                        "#;
+/// `makedir doctor [flags]`: where each tool is and its version, returning
+/// the exit code.
+fn doctor(args: &[String], reporter: &Reporter) -> i32 {
+    let mut tools: Vec<&Tool> = Vec::new();
+    for arg in args {
+        match Action::from_name(arg) {
+            Some(action) => tools.extend(Tool::for_action(action)),
+            None => {
+                reporter.error(format_args!("Unknown flag: {}", arg), "");
+                return exit_code::USAGE;
+            }
+        }
+    }
+    if args.is_empty() {
+        tools = TOOLS.iter().collect();
+    }
 
-                    let dockerfile_path = dir_path.join("Dockerfile");
-                    if let Err(e) = fs::write(&dockerfile_path, dockerfile_content) {
-                        eprintln!(
-                            "\x1b[1;31mFailed to create Dockerfile in {}:\x1b[0m {}",
-                            dir_path.display(),
-                            e
-                        );
-                    } else if verbose {
-                        println!(
-                            "\x1b[1;32mSuccessfully created Dockerfile in {}.\x1b[0m",
-                            dir_path.display()
-                        );
-                    }
-                }
-                "--readme" | "-r" => {
-                    let readme_content = format!(
-                        "# Project Title\n\n\
-                        Simple overview of use/purpose.\n\n\
-                        ## Description\n\n\
-                        An in-depth paragraph about your project and overview of use.\n\n\
-                        ## Getting Started\n\n\
-                        ### Dependencies\n\n\
-                        * Describe any prerequisites, libraries, OS version, etc., needed before installing program.\n\
-                        * ex. Windows 10\n\n\
-                        ### Installing\n\n\
-                        * How/where to download your program\n\
-                        * Any modifications needed to be made to files/folders\n\n\
-                        ### Executing program\n\n\
-                        * How to run the program\n\
-                        * Step-by-step bullets\n\
-                        ```bash\n\
-                        code blocks for commands\n\
-                        ```\n\n\
-                        ## Help\n\n\
-                        Any advice for common problems or issues.\n\
-                        ```bash\n\
-                        command to run if program contains helper info\n\
-                        ```\n\n\
-                        ## Authors\n\n\
-                        Contributors names and contact info\n\
-                        ex. [@soup-ms](https://github.com/soup-ms)\n\n\
-                        ## Version History\n\n\
-                        * v0.2.0\n\
-                            * Various bug fixes and optimizations\n\
-                            * See [commit change]() or See [release history]()\n\
-                        * v0.1.0\n\
-                            * Initial Release\n\n\
-                        ## License\n\n\
-                        This project is licensed under the [NAME HERE] License - see the LICENSE.md file for details\n\n\
-                        ## Acknowledgments\n\
-                        https://twitter.com/dompizzie\n"
-                    );
+    let mut missing = 0;
+    for tool in &tools {
+        let flag = format!("--{:<6}", tool.action.name());
+        let status = runner::resolve(tool.program)
+            .ok_or_else(|| format!("not on PATH. It is provided by {}.", tool.provided_by))
+            .and_then(|path| {
+                let version = tool.version(&path)?;
+                Ok(format!("{} ({})", version, path.display()))
+            });
+        match status {
+            Ok(status) => println!("  {}", reporter.stdout.line(Role::Success, flag, status)),
+            Err(e) => {
+                missing += 1;
+                println!(
+                    "  {}",
+                    reporter.stdout.line(
+                        Role::Error,
+                        flag,
+                        format_args!("{}: {}", tool.program, e)
+                    )
+                );
+            }
+        }
+    }
 
-                    if let Err(e) = fs::write(dir_path.join("README.md"), &readme_content) {
-                        eprintln!(
-                            "\x1b[1;31mFailed to create README.md in {}:\x1b[0m {}",
-                            dir, e
-                        );
-                    } else if verbose {
-                        println!(
-                            "\x1b[1;32mSuccessfully created README.md in {}.\x1b[0m",
-                            dir
-                        );
-                    }
-                }
-                "--license" | "-l" => {
-                    let license_content = format!(
-                        "MIT License\n\n\
-                        Copyright (c) [YEAR] [YOUR NAME]\n\n\
-                        Permission is hereby granted, free of charge, to any person obtaining a copy\n\
-                        of this software and associated documentation files (the \"Software\"), to deal\n\
-                        in the Software without restriction, including without limitation the rights\n\
-                        to use, copy, modify, merge, publish, distribute, sublicense, and/or sell\n\
-                        copies of the Software, and to permit persons to whom the Software is\n\
-                        furnished to do so, subject to the following conditions:\n\n\
-                        The above copyright notice and this permission notice shall be included in all\n\
-                        copies or substantial portions of the Software.\n\n\
-                        THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR\n\
-                        IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,\n\
-                        FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE\n\
-                        AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER\n\
-                        LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,\n\
-                        OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE\n\
-                        SOFTWARE.\n"
-                    );
+    if missing == 0 {
+        println!(
+            "{}",
+            reporter.stdout.paint(Role::Success, "All tools found.")
+        );
+        exit_code::SUCCESS
+    } else {
+        println!(
+            "{}",
+            reporter.stdout.paint(
+                Role::Error,
+                format_args!("{} of {} tools missing or broken.", missing, tools.len())
+            )
+        );
+        exit_code::INITIALIZER
+    }
+}
 
-                    if let Err(e) = fs::write(dir_path.join("LICENSE"), &license_content) {
-                        eprintln!(
-                            "\x1b[1;31mFailed to create LICENSE file in {}:\x1b[0m {}",
-                            dir, e
-                        );
-                    } else if verbose {
-                        println!(
-                            "\x1b[1;32mSuccessfully created LICENSE file in {}.\x1b[0m",
-                            dir
-                        );
-                    }
-                }
-                _ => eprintln!("\x1b[1;31mUnknown flag:\x1b[0m {}", flag),
-            }
+fn print_summary(report: &Report, reporter: &Reporter) {
+    let failed: Vec<_> = report.failed().collect();
+    reporter.error(
+        format_args!(
+            "{} of {} directories failed:",
+            failed.len(),
+            report.targets.len() + report.skipped.len()
+        ),
+        "",
+    );
+    for target in failed {
+        for error in &target.errors {
+            eprintln!("  {}: {}", target.dir.display(), error);
         }
     }
+    for dir in &report.skipped {
+        eprintln!("  {}: skipped", dir.display());
+    }
 }
@@ -0,0 +1,251 @@
+//! Permissions given with `-###`, `--mode` or a `mode` setting.
+//!
+//! A mode is either octal, which sets the permissions outright, or chmod's
+//! symbolic syntax (`u=rwx,g=rx,o=`, `g+ws`, `a-w`, `+X`), which changes the
+//! permissions a path already has.
+
+use std::fmt;
+
+/// Parses permissions written as up to 3 octal digits, e.g. `755` or `0`.
+pub fn parse_octal(s: &str) -> Option<u32> {
+    if s.is_empty() || s.len() > 3 || !s.chars().all(|c| ('0'..='7').contains(&c)) {
+        return None;
+    }
+    u32::from_str_radix(s, 8).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Permissions including the setuid, setgid and sticky bits, e.g. `2775`.
+    Octal(u32),
+    /// Changes applied in order to the current permissions.
+    Symbolic { text: String, clauses: Vec<Clause> },
+}
+
+/// One comma-separated part of a symbolic mode, e.g. `go-w`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clause {
+    /// Bits of the classes named before the operator; empty means all of
+    /// them, leaving alone the bits set in the umask.
+    who: u32,
+    actions: Vec<(char, Perms)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Perms {
+    /// Any of `rwxXst`.
+    Bits(String),
+    /// The current permissions of `u`, `g` or `o`.
+    Copy(char),
+}
+
+const USER: u32 = 0o4700;
+const GROUP: u32 = 0o2070;
+const OTHER: u32 = 0o1007;
+
+impl Mode {
+    /// Parses 1 to 4 octal digits or a symbolic mode.
+    pub fn parse(s: &str) -> Result<Mode, String> {
+        if s.chars().all(|c| c.is_ascii_digit()) && !s.is_empty() {
+            return match u32::from_str_radix(s, 8) {
+                Ok(mode) if s.len() <= 4 => Ok(Mode::Octal(mode)),
+                _ => Err(format!(
+                    "invalid mode `{}`, expected up to 4 octal digits like 755 or 2775",
+                    s
+                )),
+            };
+        }
+        let clauses = s
+            .split(',')
+            .map(parse_clause)
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("invalid mode `{}`: {}", s, e))?;
+        Ok(Mode::Symbolic {
+            text: s.to_string(),
+            clauses,
+        })
+    }
+
+    /// What `--shared` adds to every directory: group write and setgid, so
+    /// new files belong to the directory's group.
+    pub fn shared() -> Mode {
+        Mode::Symbolic {
+            text: "g+ws".to_string(),
+            clauses: vec![Clause {
+                who: GROUP,
+                actions: vec![('+', Perms::Bits("ws".to_string()))],
+            }],
+        }
+    }
+
+    /// The permissions a path with permissions `current` ends up with, where
+    /// `umask` is the process umask from [`umask`].
+    pub fn apply(&self, current: u32, is_dir: bool, umask: u32) -> u32 {
+        match self {
+            Mode::Octal(mode) => *mode,
+            Mode::Symbolic { clauses, .. } => {
+                clauses.iter().fold(current & 0o7777, |mode, clause| {
+                    clause.apply(mode, is_dir, umask)
+                })
+            }
+        }
+    }
+}
+
+impl From<u32> for Mode {
+    fn from(mode: u32) -> Mode {
+        Mode::Octal(mode)
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Octal(mode) => write!(f, "{:03o}", mode),
+            Mode::Symbolic { text, .. } => f.write_str(text),
+        }
+    }
+}
+
+/// Which of the directories created on the way to a target get a mode, from
+/// `--parents-mode`. Directories that already exist are never changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ParentsMode {
+    /// Only the target itself, like `mkdir -p -m`.
+    #[default]
+    Leaf,
+    /// Every directory makedir creates gets the target's mode.
+    All,
+    /// New parent directories get this mode instead.
+    Mode(Mode),
+}
+
+impl ParentsMode {
+    /// Parses `leaf`, `all` or a mode.
+    pub fn parse(s: &str) -> Result<ParentsMode, String> {
+        match s {
+            "leaf" => Ok(ParentsMode::Leaf),
+            "all" => Ok(ParentsMode::All),
+            _ => Mode::parse(s)
+                .map(ParentsMode::Mode)
+                .map_err(|e| format!("{}; expected leaf, all or a mode", e)),
+        }
+    }
+
+    /// The mode for new parents of a target whose own mode is `leaf`.
+    pub fn for_parents<'a>(&'a self, leaf: Option<&'a Mode>) -> Option<&'a Mode> {
+        match self {
+            ParentsMode::Leaf => None,
+            ParentsMode::All => leaf,
+            ParentsMode::Mode(mode) => Some(mode),
+        }
+    }
+}
+
+fn parse_clause(clause: &str) -> Result<Clause, String> {
+    let mut who = 0;
+    let mut chars = clause.chars().peekable();
+    while let Some(c) = chars.next_if(|c| "ugoa".contains(*c)) {
+        who |= match c {
+            'u' => USER,
+            'g' => GROUP,
+            'o' => OTHER,
+            _ => USER | GROUP | OTHER,
+        };
+    }
+
+    let mut actions = Vec::new();
+    while let Some(op) = chars.next() {
+        if !"+-=".contains(op) {
+            return Err(format!("expected `+`, `-` or `=` before `{}`", op));
+        }
+        let perms = match chars.peek() {
+            Some(&source @ ('u' | 'g' | 'o')) => {
+                chars.next();
+                Perms::Copy(source)
+            }
+            _ => {
+                let mut bits = String::new();
+                while let Some(c) = chars.next_if(|c| !"+-=".contains(*c)) {
+                    if !"rwxXst".contains(c) {
+                        return Err(format!("unknown permission `{}`", c));
+                    }
+                    bits.push(c);
+                }
+                Perms::Bits(bits)
+            }
+        };
+        actions.push((op, perms));
+    }
+    if actions.is_empty() {
+        return Err(format!("`{}` has no `+`, `-` or `=`", clause));
+    }
+    Ok(Clause { who, actions })
+}
+
+impl Clause {
+    fn apply(&self, mut mode: u32, is_dir: bool, umask: u32) -> u32 {
+        let (who, keep) = match self.who {
+            0 => (USER | GROUP | OTHER, umask),
+            who => (who, 0),
+        };
+        for (op, perms) in &self.actions {
+            let bits = match perms {
+                Perms::Bits(bits) => {
+                    let mut set = 0;
+                    for c in bits.chars() {
+                        set |= match c {
+                            'r' => 0o444,
+                            'w' => 0o222,
+                            'x' => 0o111,
+                            // Execute only for directories and files someone can already run
+                            'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                            's' => 0o6000,
+                            't' => 0o1000,
+                            _ => 0,
+                        };
+                    }
+                    set
+                }
+                Perms::Copy(source) => {
+                    let rwx = match source {
+                        'u' => (mode >> 6) & 0o7,
+                        'g' => (mode >> 3) & 0o7,
+                        _ => mode & 0o7,
+                    };
+                    rwx * 0o111
+                }
+            } & who
+                & !keep;
+            mode = match op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => (mode & !(who & !keep)) | bits,
+            };
+        }
+        mode
+    }
+}
+
+/// The process umask, which symbolic modes without `u`, `g`, `o` or `a` respect.
+///
+/// It can only be read by setting it, which briefly changes it for the whole
+/// process, so call this before starting any threads that create files.
+pub fn umask() -> u32 {
+    #[cfg(target_os = "macos")]
+    type ModeT = u16;
+    #[cfg(not(target_os = "macos"))]
+    type ModeT = u32;
+    extern "C" {
+        fn umask(mask: ModeT) -> ModeT;
+    }
+
+    // SAFETY: umask has no preconditions and cannot fail; the old value is
+    // put straight back.
+    #[allow(clippy::useless_conversion)] // mode_t is narrower on macOS
+    unsafe {
+        let old = umask(0o022);
+        umask(old);
+        u32::from(old)
+    }
+}
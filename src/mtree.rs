@@ -0,0 +1,508 @@
+//! BSD mtree(5) manifests: `--mtree <file>` builds the layout one describes
+//! and `makedir verify --mtree <file> <root>` checks a tree against it.
+//!
+//! Both the classic format, where a directory entry is followed by its
+//! contents and `..` climbs back out, and the full-path format written by
+//! `mtree -C` are read, along with `/set` and `/unset` defaults. Of the
+//! keywords only `type`, `mode`, `uid`, `gid`, `uname`, `gname` and
+//! `optional` are used; sizes, times and checksums are ignored. Existing
+//! files keep their contents: only their mode and owner are changed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::mode::Mode;
+use crate::owner::{Id, Owner};
+use crate::spec::{self, FileSpec, Node, Spec};
+use crate::toml::ParseError;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path relative to the root, `.` for the root itself.
+    pub path: String,
+    pub kind: Kind,
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub uname: Option<String>,
+    pub gname: Option<String>,
+    /// A missing entry is not reported by `verify`.
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Dir,
+    Link,
+    Block,
+    Char,
+    Fifo,
+    Socket,
+}
+
+impl Kind {
+    const ALL: [(&'static str, Kind); 7] = [
+        ("file", Kind::File),
+        ("dir", Kind::Dir),
+        ("link", Kind::Link),
+        ("block", Kind::Block),
+        ("char", Kind::Char),
+        ("fifo", Kind::Fifo),
+        ("socket", Kind::Socket),
+    ];
+
+    fn parse(s: &str) -> Option<Kind> {
+        Kind::ALL
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, kind)| *kind)
+    }
+
+    fn of(file_type: fs::FileType) -> Kind {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_dir() {
+            Kind::Dir
+        } else if file_type.is_symlink() {
+            Kind::Link
+        } else if file_type.is_block_device() {
+            Kind::Block
+        } else if file_type.is_char_device() {
+            Kind::Char
+        } else if file_type.is_fifo() {
+            Kind::Fifo
+        } else if file_type.is_socket() {
+            Kind::Socket
+        } else {
+            Kind::File
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = Kind::ALL
+            .iter()
+            .find(|(_, kind)| kind == self)
+            .map_or("file", |(name, _)| *name);
+        f.write_str(name)
+    }
+}
+
+/// A difference found by [`Manifest::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Missing {
+        path: PathBuf,
+        kind: Kind,
+    },
+    WrongKind {
+        path: PathBuf,
+        expected: Kind,
+        found: Kind,
+    },
+    Mode {
+        path: PathBuf,
+        expected: u32,
+        found: u32,
+    },
+    /// Only the parts of `expected` that are set were compared.
+    Owner {
+        path: PathBuf,
+        expected: Owner,
+        uid: u32,
+        gid: u32,
+    },
+    /// The entry names a user or group this system doesn't know.
+    UnknownOwner {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing { path, kind } => write!(f, "missing {} {}", kind, path.display()),
+            Problem::WrongKind {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} is a {}, expected a {}",
+                path.display(),
+                found,
+                expected
+            ),
+            Problem::Mode {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} has permissions {:o}, expected {:o}",
+                path.display(),
+                found,
+                expected
+            ),
+            Problem::Owner {
+                path,
+                expected,
+                uid,
+                gid,
+            } => write!(
+                f,
+                "{} is owned by {}:{}, expected {}",
+                path.display(),
+                uid,
+                gid,
+                expected
+            ),
+            Problem::UnknownOwner { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl Entry {
+    /// The owner the entry asks for. Names win over ids, as with `mtree -U`,
+    /// unless this system doesn't know the name and an id is given too.
+    pub fn owner(&self) -> Result<Owner, String> {
+        let resolve =
+            |name: &Option<String>, id: Option<u32>, lookup: fn(&str) -> _| match (name, id) {
+                (Some(name), id) => match lookup(name) {
+                    Ok(resolved) => Ok(Some(resolved)),
+                    Err(_) if id.is_some() => Ok(id.map(numeric)),
+                    Err(e) => Err(e),
+                },
+                (None, id) => Ok(id.map(numeric)),
+            };
+        Ok(Owner {
+            user: resolve(&self.uname, self.uid, Owner::user)?,
+            group: resolve(&self.gname, self.gid, Owner::group)?,
+        })
+    }
+}
+
+fn numeric(id: u32) -> Id {
+    Id {
+        name: id.to_string(),
+        id,
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Manifest::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Manifest, ParseError> {
+        let mut manifest = Manifest::default();
+        let mut defaults: HashMap<String, String> = HashMap::new();
+        // Directories entered in the classic format, innermost last
+        let mut cwd: Vec<String> = Vec::new();
+
+        for (number, line) in logical_lines(text) {
+            let error = |message: String| ParseError {
+                line: number,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or("");
+            match first {
+                "/set" => {
+                    for word in words {
+                        let (key, value) = word.split_once('=').unwrap_or((word, ""));
+                        defaults.insert(key.to_string(), value.to_string());
+                    }
+                    continue;
+                }
+                "/unset" => {
+                    for word in words {
+                        if word == "all" {
+                            defaults.clear();
+                        } else {
+                            defaults.remove(word);
+                        }
+                    }
+                    continue;
+                }
+                ".." => {
+                    if cwd.pop().is_none() {
+                        return Err(error("`..` climbs above the root".to_string()));
+                    }
+                    continue;
+                }
+                _ if first.starts_with('/') => {
+                    return Err(error(format!("unknown command `{}`", first)));
+                }
+                _ => {}
+            }
+
+            let name = unvis(first);
+            let mut keywords = defaults.clone();
+            for word in words {
+                let (key, value) = word.split_once('=').unwrap_or((word, ""));
+                keywords.insert(key.to_string(), value.to_string());
+            }
+            let keyword = |key: &str| keywords.get(key).map(String::as_str);
+
+            let full_path = name.contains('/');
+            let path = if full_path {
+                name.strip_prefix("./").unwrap_or(&name).to_string()
+            } else {
+                cwd.iter()
+                    .map(String::as_str)
+                    .chain([name.as_str()])
+                    .filter(|c| *c != ".")
+                    .collect::<Vec<_>>()
+                    .join("/")
+            };
+            let path = match path.trim_end_matches('/') {
+                "" | "." => ".".to_string(),
+                path => spec::relative_path(path, path).map_err(error)?.to_string(),
+            };
+
+            let kind = match keyword("type") {
+                Some(kind) => Kind::parse(kind)
+                    .ok_or_else(|| error(format!("`{}`: unknown type `{}`", path, kind)))?,
+                None => return Err(error(format!("`{}` has no type", path))),
+            };
+            let mode = match keyword("mode") {
+                Some(mode) => Some(parse_mode(mode).ok_or_else(|| {
+                    error(format!(
+                        "`{}`: invalid mode `{}`, expected octal digits",
+                        path, mode
+                    ))
+                })?),
+                None => None,
+            };
+            let numeric = |key: &str| -> Result<Option<u32>, ParseError> {
+                keyword(key)
+                    .map(|value| {
+                        value
+                            .parse()
+                            .map_err(|_| error(format!("`{}`: invalid {} `{}`", path, key, value)))
+                    })
+                    .transpose()
+            };
+
+            // Directories in the classic format contain the entries that follow
+            if kind == Kind::Dir && !full_path {
+                cwd.push(name.clone());
+            }
+            manifest.entries.push(Entry {
+                kind,
+                mode,
+                uid: numeric("uid")?,
+                gid: numeric("gid")?,
+                uname: keyword("uname").map(unvis),
+                gname: keyword("gname").map(unvis),
+                optional: keywords.contains_key("optional"),
+                path,
+            });
+        }
+        Ok(manifest)
+    }
+
+    /// The directories and files to create, with their modes and owners.
+    /// Files are created empty where they are missing; existing ones keep
+    /// their contents and only get the mode and owner.
+    ///
+    /// The spec is named `.`, so without a directory on the command line the
+    /// layout is built in the current directory, as `mtree -U` would.
+    pub fn to_spec(&self) -> Result<Spec, String> {
+        let mut spec = Spec {
+            name: Some(".".to_string()),
+            root: Node::default(),
+        };
+        for entry in &self.entries {
+            let mut parts: Vec<&str> = entry.path.split('/').filter(|p| *p != ".").collect();
+            let owner = entry
+                .owner()
+                .map_err(|e| format!("`{}`: {}", entry.path, e))?;
+            match entry.kind {
+                Kind::Dir => {
+                    let node = node_at(&mut spec.root, &parts);
+                    node.mode = entry.mode.map(Mode::Octal);
+                    node.owner = owner;
+                }
+                Kind::File => {
+                    let name = parts.pop().unwrap_or(".");
+                    node_at(&mut spec.root, &parts).files.push(FileSpec {
+                        path: name.to_string(),
+                        contents: None,
+                        mode: entry.mode.map(Mode::Octal),
+                        owner,
+                    });
+                }
+                kind => {
+                    return Err(format!(
+                        "`{}`: cannot create a {}, only files and directories",
+                        entry.path, kind
+                    ))
+                }
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Compares the tree at `root` with the manifest.
+    pub fn verify(&self, root: &Path) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for entry in &self.entries {
+            let path = if entry.path == "." {
+                root.to_path_buf()
+            } else {
+                root.join(&entry.path)
+            };
+            let metadata = match fs::symlink_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) => {
+                    if !entry.optional {
+                        problems.push(Problem::Missing {
+                            path,
+                            kind: entry.kind,
+                        });
+                    }
+                    continue;
+                }
+            };
+            let found = Kind::of(metadata.file_type());
+            if found != entry.kind {
+                problems.push(Problem::WrongKind {
+                    path,
+                    expected: entry.kind,
+                    found,
+                });
+                continue;
+            }
+            let mode = metadata.permissions().mode() & 0o7777;
+            match entry.mode {
+                Some(expected) if found != Kind::Link && mode != expected => {
+                    problems.push(Problem::Mode {
+                        path: path.clone(),
+                        expected,
+                        found: mode,
+                    })
+                }
+                _ => {}
+            }
+            match entry.owner() {
+                Ok(expected) => {
+                    let (uid, gid) = (metadata.uid(), metadata.gid());
+                    let user_differs = expected.uid().is_some_and(|id| id != uid);
+                    let group_differs = expected.gid().is_some_and(|id| id != gid);
+                    if user_differs || group_differs {
+                        problems.push(Problem::Owner {
+                            path,
+                            expected,
+                            uid,
+                            gid,
+                        });
+                    }
+                }
+                Err(message) => problems.push(Problem::UnknownOwner { path, message }),
+            }
+        }
+        problems
+    }
+}
+
+/// Finds the node for the directory at `parts`, adding any that are missing.
+fn node_at<'a>(root: &'a mut Node, parts: &[&str]) -> &'a mut Node {
+    let mut node = root;
+    for part in parts {
+        let index = match node.dirs.iter().position(|(name, _)| name == part) {
+            Some(index) => index,
+            None => {
+                node.dirs.push((part.to_string(), Node::default()));
+                node.dirs.len() - 1
+            }
+        };
+        node = &mut node.dirs[index].1;
+    }
+    node
+}
+
+/// Permissions as written by mtree: octal, up to `07777`.
+fn parse_mode(s: &str) -> Option<u32> {
+    if s.is_empty() || s.len() > 5 || !s.chars().all(|c| ('0'..='7').contains(&c)) {
+        return None;
+    }
+    u32::from_str_radix(s, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+}
+
+/// Joins lines continued with a trailing backslash, keeping the first line's number.
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let (number, mut joined) = pending.take().unwrap_or((i + 1, String::new()));
+        let trailing = line.len() - line.trim_end_matches('\\').len();
+        if trailing % 2 == 1 {
+            joined.push_str(&line[..line.len() - 1]);
+            joined.push(' ');
+            pending = Some((number, joined));
+        } else {
+            joined.push_str(line);
+            lines.push((number, joined));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// Decodes the vis(3) escapes mtree uses in names, such as `\040` for a space.
+fn unvis(s: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let digits = rest
+            .iter()
+            .take(3)
+            .take_while(|c| (b'0'..=b'7').contains(c))
+            .count();
+        if digits == 3 {
+            let code = rest[..3]
+                .iter()
+                .fold(0u32, |n, d| n * 8 + u32::from(d - b'0'));
+            bytes.push(code as u8);
+            rest = &rest[3..];
+        } else if let Some((&next, tail)) = rest.split_first() {
+            bytes.push(match next {
+                b's' => b' ',
+                b't' => b'\t',
+                b'n' => b'\n',
+                other => other,
+            });
+            rest = tail;
+        } else {
+            bytes.push(b'\\');
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
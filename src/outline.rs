@@ -0,0 +1,168 @@
+//! Directory trees written as an indented outline, built with `--from-tree`.
+//!
+//! Each line names one entry and its indentation says where it goes. A
+//! trailing `/` marks a directory, and so does having entries below it;
+//! everything else is an empty file, created only where there is none. The box-drawing prefixes printed by
+//! `tree` (and its `--charset=ascii` variant) count as indentation, so its
+//! output can be pasted as is:
+//!
+//! ```text
+//! billing
+//! ├── src/
+//! │   └── main.rs
+//! └── README.md
+//!
+//! 1 directory, 2 files
+//! ```
+//!
+//! When the outline has a single top-level directory, that directory is the
+//! spec's `name` and its entries are the tree, like a `tree` listing of it.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::owner::Owner;
+use crate::spec::{self, FileSpec, Node, Spec};
+use crate::toml::ParseError;
+
+/// Reads an outline from `source`, or from standard input when it is `-`.
+pub fn load(source: &str) -> Result<Spec, String> {
+    let text = if source == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("failed to read standard input: {}", e))?;
+        text
+    } else {
+        fs::read_to_string(source).map_err(|e| format!("failed to read {}: {}", source, e))?
+    };
+    let name = if source == "-" { "<stdin>" } else { source };
+    parse(&text).map_err(|e| format!("{}: {}", name, e))
+}
+
+struct Entry {
+    line: usize,
+    column: usize,
+    name: String,
+    /// Written with a trailing `/`.
+    dir: bool,
+    children: Vec<usize>,
+}
+
+pub fn parse(text: &str) -> Result<Spec, ParseError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut top = Vec::new();
+    // Open entries, innermost last
+    let mut stack: Vec<usize> = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let (column, rest) = strip_prefix(raw);
+        let rest = rest.trim_end();
+        if rest.is_empty() || rest.starts_with("```") || rest.starts_with("~~~") || is_summary(rest)
+        {
+            continue;
+        }
+        // Symlinks are listed as `name -> target`
+        let name = rest.split(" -> ").next().unwrap_or(rest);
+
+        while stack
+            .last()
+            .is_some_and(|&open| entries[open].column >= column)
+        {
+            stack.pop();
+        }
+        let index = entries.len();
+        match stack.last() {
+            Some(&parent) => entries[parent].children.push(index),
+            None => top.push(index),
+        }
+        stack.push(index);
+        entries.push(Entry {
+            line: i + 1,
+            column,
+            name: name.trim_end_matches('/').to_string(),
+            dir: name.ends_with('/'),
+            children: Vec::new(),
+        });
+    }
+
+    let mut spec = Spec::default();
+    match top[..] {
+        [] => {
+            return Err(ParseError {
+                line: 1,
+                message: "the tree is empty".to_string(),
+            })
+        }
+        // A listing of one directory describes that directory
+        [root] if entries[root].dir || !entries[root].children.is_empty() => {
+            let root = &entries[root];
+            spec.name = Path::new(&root.name)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            spec.root = node_for(&entries, &root.children)?;
+        }
+        _ => spec.root = node_for(&entries, &top)?,
+    }
+    Ok(spec)
+}
+
+fn node_for(entries: &[Entry], children: &[usize]) -> Result<Node, ParseError> {
+    let mut node = Node::default();
+    for &child in children {
+        let entry = &entries[child];
+        let path = spec::relative_path(&entry.name, &entry.name).map_err(|message| ParseError {
+            line: entry.line,
+            message,
+        })?;
+        if entry.dir || !entry.children.is_empty() {
+            node.dirs
+                .push((path.to_string(), node_for(entries, &entry.children)?));
+        } else {
+            node.files.push(FileSpec {
+                path: path.to_string(),
+                contents: None,
+                mode: None,
+                owner: Owner::default(),
+            });
+        }
+    }
+    Ok(node)
+}
+
+/// Splits a line into the column its name starts at and the name.
+fn strip_prefix(line: &str) -> (usize, &str) {
+    let mut column = 0;
+    let mut rest = line;
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some('\t') => column += 4 - column % 4,
+            Some(c) if c.is_whitespace() => column += 1,
+            // A connector, with the horizontal line that follows it
+            Some('│' | '├' | '└' | '┃' | '┣' | '┗' | '|' | '`') => {
+                column += 1;
+                while let Some('─' | '━' | '-') = chars.clone().next() {
+                    chars.next();
+                    column += 1;
+                }
+            }
+            _ => return (column, rest),
+        }
+        rest = chars.as_str();
+    }
+}
+
+/// Whether `line` is the `N directories, M files` line `tree` ends with.
+fn is_summary(line: &str) -> bool {
+    line.split(", ").all(|part| {
+        let mut words = part.split(' ');
+        let count = words.next().unwrap_or("");
+        let noun = words.next().unwrap_or("");
+        !count.is_empty()
+            && count.bytes().all(|b| b.is_ascii_digit())
+            && matches!(noun, "directory" | "directories" | "file" | "files")
+            && words.next().is_none()
+    })
+}
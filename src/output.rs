@@ -0,0 +1,291 @@
+//! Machine-readable output: the final `--output json` report and the
+//! `--events jsonl` stream of one JSON object per step.
+//!
+//! Either one claims stdout, so the usual progress messages move to stderr
+//! while they are enabled.
+
+use std::path::Path;
+
+use crate::acl::Acl;
+use crate::execute::{CommandReport, Failure, FailureKind, Report, TargetReport};
+use crate::json;
+use crate::mtree::Problem;
+use crate::owner::Owner;
+use crate::toml::{Table, Value};
+use crate::toolchain::VersionCheck;
+
+/// What makedir prints once it is done, from `--output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Colored messages for people.
+    #[default]
+    Text,
+    /// A single JSON document describing every directory.
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown output format `{}`, expected text or json",
+                s
+            )),
+        }
+    }
+}
+
+/// One step of the work, as streamed by `--events jsonl`.
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// Work on a directory named on the command line begins.
+    Start {
+        dir: &'a Path,
+    },
+    /// A directory, the target or one of its parents, was created.
+    Created {
+        path: &'a Path,
+    },
+    Exists {
+        path: &'a Path,
+    },
+    Owner {
+        path: &'a Path,
+        owner: &'a Owner,
+    },
+    Mode {
+        path: &'a Path,
+        mode: u32,
+    },
+    Acl {
+        path: &'a Path,
+        acl: &'a Acl,
+        default: bool,
+    },
+    /// A file was written or copied.
+    File {
+        path: &'a Path,
+    },
+    License {
+        path: &'a Path,
+        license: &'a str,
+    },
+    Command {
+        dir: &'a Path,
+        command: &'a CommandReport,
+    },
+    Error {
+        dir: &'a Path,
+        failure: &'a Failure,
+    },
+    RolledBack {
+        dir: &'a Path,
+    },
+    /// Work on a directory is over; `status` is as in the final report.
+    Finish {
+        target: &'a TargetReport,
+    },
+}
+
+impl Event<'_> {
+    pub fn to_json(&self) -> String {
+        let mut table = Table::new();
+        let mut set = |key: &str, value: Value| table.insert(key, value);
+        match self {
+            Event::Start { dir } => {
+                set("event", string("start"));
+                set("dir", path(dir));
+            }
+            Event::Created { path: created } => {
+                set("event", string("created"));
+                set("path", path(created));
+            }
+            Event::Exists { path: existing } => {
+                set("event", string("exists"));
+                set("path", path(existing));
+            }
+            Event::Owner {
+                path: changed,
+                owner,
+            } => {
+                set("event", string("owner"));
+                set("path", path(changed));
+                set("owner", string(&owner.to_string()));
+            }
+            Event::Mode {
+                path: changed,
+                mode,
+            } => {
+                set("event", string("mode"));
+                set("path", path(changed));
+                set("mode", string(&octal(*mode)));
+            }
+            Event::Acl {
+                path: changed,
+                acl,
+                default,
+            } => {
+                set("event", string("acl"));
+                set("path", path(changed));
+                set("acl", string(&acl.to_string()));
+                set("default", Value::Boolean(*default));
+            }
+            Event::File { path: written } => {
+                set("event", string("file"));
+                set("path", path(written));
+            }
+            Event::License {
+                path: changed,
+                license,
+            } => {
+                set("event", string("license"));
+                set("path", path(changed));
+                set("license", string(license));
+            }
+            Event::Command { dir, command } => {
+                set("event", string("command"));
+                set("dir", path(dir));
+                for (key, value) in command_json(command).iter() {
+                    set(key, value.clone());
+                }
+            }
+            Event::Error { dir, failure } => {
+                set("event", string("error"));
+                set("dir", path(dir));
+                set("kind", string(kind(failure.kind)));
+                set("message", string(&failure.message));
+            }
+            Event::RolledBack { dir } => {
+                set("event", string("rolled_back"));
+                set("dir", path(dir));
+            }
+            Event::Finish { target } => {
+                set("event", string("finish"));
+                set("dir", path(&target.dir));
+                set("status", string(target.status()));
+            }
+        }
+        json::to_string(&Value::Table(table))
+    }
+}
+
+/// The `--output json` document for a finished run.
+pub fn report_json(report: &Report) -> String {
+    let mut table = Table::new();
+    table.insert("success", Value::Boolean(report.is_success()));
+    table.insert("exit_code", Value::Integer(report.exit_code().into()));
+    let targets = report.targets.iter().map(target_json).collect();
+    table.insert("directories", Value::Array(targets));
+    let skipped = report.skipped.iter().map(|dir| path(dir)).collect();
+    table.insert("skipped", Value::Array(skipped));
+    let toolchains = report.toolchains.iter().map(toolchain_json).collect();
+    table.insert("toolchains", Value::Array(toolchains));
+    let missing = report
+        .missing_tools
+        .iter()
+        .map(|tool| string(tool))
+        .collect();
+    table.insert("missing_tools", Value::Array(missing));
+    json::to_string(&Value::Table(table))
+}
+
+/// The `--output json` document for `makedir verify`, listing every difference.
+pub fn verify_json(exit_code: i32, problems: &[Problem]) -> String {
+    let mut table = Table::new();
+    table.insert("success", Value::Boolean(problems.is_empty()));
+    table.insert("exit_code", Value::Integer(exit_code.into()));
+    let problems = problems
+        .iter()
+        .map(|problem| string(&problem.to_string()))
+        .collect();
+    table.insert("problems", Value::Array(problems));
+    json::to_string(&Value::Table(table))
+}
+
+/// The `--output json` document for `--dry-run`, listing what would change.
+pub fn dry_run_json(changes: &[String]) -> String {
+    let mut table = Table::new();
+    table.insert("dry_run", Value::Boolean(true));
+    let changes = changes.iter().map(|change| string(change)).collect();
+    table.insert("changes", Value::Array(changes));
+    json::to_string(&Value::Table(table))
+}
+
+fn target_json(target: &TargetReport) -> Value {
+    let mut table = Table::new();
+    table.insert("path", path(&target.dir));
+    table.insert("status", string(target.status()));
+    if let Some(mode) = target.mode {
+        table.insert("mode", string(&octal(mode)));
+    }
+    let files = target.files.iter().map(|file| path(file)).collect();
+    table.insert("files", Value::Array(files));
+    let commands = target
+        .commands
+        .iter()
+        .map(|command| Value::Table(command_json(command)))
+        .collect();
+    table.insert("commands", Value::Array(commands));
+    let errors = target
+        .errors
+        .iter()
+        .map(|failure| {
+            let mut error = Table::new();
+            error.insert("kind", string(kind(failure.kind)));
+            error.insert("message", string(&failure.message));
+            Value::Table(error)
+        })
+        .collect();
+    table.insert("errors", Value::Array(errors));
+    Value::Table(table)
+}
+
+fn toolchain_json(check: &VersionCheck) -> Value {
+    let mut table = Table::new();
+    table.insert("toolchain", string(check.requirement.toolchain.name));
+    table.insert("required", string(&check.requirement.constraint()));
+    match &check.found {
+        Ok(version) => table.insert("found", string(&version.to_string())),
+        Err(e) => table.insert("error", string(e)),
+    }
+    table.insert("satisfied", Value::Boolean(check.is_met()));
+    Value::Table(table)
+}
+
+fn command_json(command: &CommandReport) -> Table {
+    let mut table = Table::new();
+    table.insert("command", string(&command.command.to_string()));
+    let argv = command.command.argv().into_iter().map(string).collect();
+    table.insert("argv", Value::Array(argv));
+    // No exit code when the command could not be started or was killed by a signal
+    if let Some(code) = command.exit_code {
+        table.insert("exit_code", Value::Integer(code.into()));
+    }
+    table.insert("stdout", string(&command.stdout));
+    table.insert("stderr", string(&command.stderr));
+    if let Some(error) = &command.error {
+        table.insert("error", string(error));
+    }
+    table
+}
+
+fn kind(kind: FailureKind) -> &'static str {
+    match kind {
+        FailureKind::Filesystem => "filesystem",
+        FailureKind::Initializer => "initializer",
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+fn path(path: &Path) -> Value {
+    string(&path.to_string_lossy())
+}
+
+fn octal(mode: u32) -> String {
+    format!("{:04o}", mode)
+}
@@ -0,0 +1,312 @@
+//! Ownership from `--owner user[:group]` and `--group name`.
+//!
+//! Names are looked up in the system user and group databases when the
+//! command line is parsed, so an unknown user is a usage error rather than a
+//! failure halfway through. Numeric ids are accepted as well, and are all
+//! that works on platforms other than Linux and macOS.
+
+use std::fmt;
+use std::io;
+
+/// A user and group to give created directories; either may be left alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Owner {
+    pub user: Option<Id>,
+    pub group: Option<Id>,
+}
+
+/// A resolved user or group, keeping the name it was given as for messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Id {
+    pub name: String,
+    pub id: u32,
+}
+
+impl Owner {
+    /// Parses `user`, `user:group`, `user:` (the user's login group) or `:group`.
+    pub fn parse(spec: &str) -> Result<Owner, String> {
+        let (user, group) = match spec.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (spec, None),
+        };
+        let mut owner = Owner::default();
+        let mut login_group = None;
+        if !user.is_empty() {
+            let (uid, gid) = lookup_user(user)?;
+            owner.user = Some(Id {
+                name: user.to_string(),
+                id: uid,
+            });
+            login_group = Some(gid);
+        }
+        match group {
+            Some("") => match login_group {
+                Some(gid) => {
+                    let name = sys::group_by_id(gid)
+                        .map_err(|e| format!("cannot look up group {}: {}", gid, e))?;
+                    owner.group = Some(Id {
+                        name: name.unwrap_or_else(|| gid.to_string()),
+                        id: gid,
+                    })
+                }
+                None => return Err("expected user[:group], got `:`".to_string()),
+            },
+            Some(group) => owner.group = Some(Owner::group(group)?),
+            None => {}
+        }
+        if owner.is_empty() {
+            return Err("expected user[:group]".to_string());
+        }
+        Ok(owner)
+    }
+
+    /// Resolves a user name or id.
+    pub fn user(name: &str) -> Result<Id, String> {
+        let (uid, _) = lookup_user(name)?;
+        Ok(Id {
+            name: name.to_string(),
+            id: uid,
+        })
+    }
+
+    /// Resolves a group name or id, for `--group`.
+    pub fn group(name: &str) -> Result<Id, String> {
+        let found = sys::group_by_name(name)
+            .map_err(|e| format!("cannot look up group `{}`: {}", name, e))?;
+        let id = match found {
+            Some(gid) => gid,
+            None => name
+                .parse()
+                .map_err(|_| format!("unknown group `{}`", name))?,
+        };
+        Ok(Id {
+            name: name.to_string(),
+            id,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user.is_none() && self.group.is_none()
+    }
+
+    pub fn uid(&self) -> Option<u32> {
+        self.user.as_ref().map(|user| user.id)
+    }
+
+    pub fn gid(&self) -> Option<u32> {
+        self.group.as_ref().map(|group| group.id)
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(user) = &self.user {
+            f.write_str(&user.name)?;
+        }
+        if let Some(group) = &self.group {
+            write!(f, ":{}", group.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The uid and login gid of a user name or id.
+fn lookup_user(name: &str) -> Result<(u32, u32), String> {
+    let error = |e: io::Error| format!("cannot look up user `{}`: {}", name, e);
+    if let Some(entry) = sys::user_by_name(name).map_err(error)? {
+        return Ok(entry);
+    }
+    let uid: u32 = name
+        .parse()
+        .map_err(|_| format!("unknown user `{}`", name))?;
+    // A bare uid keeps its login group if it has an entry
+    let gid = sys::user_by_id(uid)
+        .map_err(error)?
+        .map_or(uid, |(_, gid)| gid);
+    Ok((uid, gid))
+}
+
+/// Calls to the system user and group databases, which go through NSS and so
+/// see LDAP or systemd users as well as `/etc/passwd`. `struct passwd` differs
+/// between platforms, so only the layouts of Linux and macOS are declared.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod sys {
+    use std::ffi::{CStr, CString};
+    use std::io;
+    use std::os::raw::{c_char, c_int};
+    use std::ptr;
+
+    #[repr(C)]
+    struct Passwd {
+        pw_name: *mut c_char,
+        pw_passwd: *mut c_char,
+        pw_uid: u32,
+        pw_gid: u32,
+        #[cfg(target_os = "macos")]
+        pw_change: i64,
+        #[cfg(target_os = "macos")]
+        pw_class: *mut c_char,
+        pw_gecos: *mut c_char,
+        pw_dir: *mut c_char,
+        pw_shell: *mut c_char,
+        #[cfg(target_os = "macos")]
+        pw_expire: i64,
+    }
+
+    #[repr(C)]
+    struct Group {
+        gr_name: *mut c_char,
+        gr_passwd: *mut c_char,
+        gr_gid: u32,
+        gr_mem: *mut *mut c_char,
+    }
+
+    extern "C" {
+        fn getpwnam_r(
+            name: *const c_char,
+            pwd: *mut Passwd,
+            buf: *mut c_char,
+            len: usize,
+            result: *mut *mut Passwd,
+        ) -> c_int;
+        fn getpwuid_r(
+            uid: u32,
+            pwd: *mut Passwd,
+            buf: *mut c_char,
+            len: usize,
+            result: *mut *mut Passwd,
+        ) -> c_int;
+        fn getgrnam_r(
+            name: *const c_char,
+            grp: *mut Group,
+            buf: *mut c_char,
+            len: usize,
+            result: *mut *mut Group,
+        ) -> c_int;
+        fn getgrgid_r(
+            gid: u32,
+            grp: *mut Group,
+            buf: *mut c_char,
+            len: usize,
+            result: *mut *mut Group,
+        ) -> c_int;
+    }
+
+    /// ERANGE on Linux and macOS: the buffer is too small for the entry.
+    const ERANGE: c_int = 34;
+
+    /// Runs one `get*_r` call, growing the string buffer until the entry
+    /// fits. `Ok(None)` means there is no such entry.
+    fn get<T>(
+        mut call: impl FnMut(*mut T, &mut [u8], *mut *mut T) -> c_int,
+    ) -> io::Result<Option<T>> {
+        let mut buf = vec![0u8; 1024];
+        loop {
+            let mut entry = std::mem::MaybeUninit::<T>::uninit();
+            let mut result = ptr::null_mut();
+            match call(entry.as_mut_ptr(), &mut buf, &mut result) {
+                0 if result.is_null() => return Ok(None),
+                // SAFETY: a zero return with a non-null result means the
+                // entry was filled in.
+                0 => return Ok(Some(unsafe { entry.assume_init() })),
+                ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+                code => return Err(io::Error::from_raw_os_error(code)),
+            }
+        }
+    }
+
+    fn c_name(name: &str) -> io::Result<CString> {
+        CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// The uid and login gid of the user called `name`.
+    pub(super) fn user_by_name(name: &str) -> io::Result<Option<(u32, u32)>> {
+        let name = c_name(name)?;
+        // SAFETY: the name is NUL-terminated and the buffer length is the
+        // buffer's own; the entry is only read after a successful call.
+        let entry = get(|pwd, buf: &mut [u8], result| unsafe {
+            getpwnam_r(
+                name.as_ptr(),
+                pwd,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                result,
+            )
+        })?;
+        Ok(entry.map(|pwd: Passwd| (pwd.pw_uid, pwd.pw_gid)))
+    }
+
+    /// The uid and login gid of the user with `uid`, if it has an entry.
+    pub(super) fn user_by_id(uid: u32) -> io::Result<Option<(u32, u32)>> {
+        // SAFETY: as for `user_by_name`.
+        let entry = get(|pwd, buf: &mut [u8], result| unsafe {
+            getpwuid_r(uid, pwd, buf.as_mut_ptr().cast(), buf.len(), result)
+        })?;
+        Ok(entry.map(|pwd: Passwd| (pwd.pw_uid, pwd.pw_gid)))
+    }
+
+    /// The gid of the group called `name`.
+    pub(super) fn group_by_name(name: &str) -> io::Result<Option<u32>> {
+        let name = c_name(name)?;
+        // SAFETY: as for `user_by_name`.
+        let entry = get(|grp, buf: &mut [u8], result| unsafe {
+            getgrnam_r(
+                name.as_ptr(),
+                grp,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                result,
+            )
+        })?;
+        Ok(entry.map(|grp: Group| grp.gr_gid))
+    }
+
+    /// The name of the group with `gid`, if it has an entry.
+    pub(super) fn group_by_id(gid: u32) -> io::Result<Option<String>> {
+        let mut buf = Vec::new();
+        // SAFETY: as for `user_by_name`; the name points into `buf`, so it is
+        // copied out inside the closure while the buffer is still alive.
+        let entry = get(|grp: *mut Group, b: &mut [u8], result| {
+            let code = unsafe { getgrgid_r(gid, grp, b.as_mut_ptr().cast(), b.len(), result) };
+            if code == 0 && !unsafe { *result }.is_null() {
+                buf = unsafe { CStr::from_ptr((*grp).gr_name) }
+                    .to_bytes()
+                    .to_vec();
+            }
+            code
+        })?;
+        Ok(entry.map(|_| String::from_utf8_lossy(&buf).into_owned()))
+    }
+}
+
+/// Elsewhere only numeric ids can be used.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod sys {
+    use std::io;
+
+    fn unsupported(name: &str) -> io::Result<()> {
+        match name.parse::<u32>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "user and group names are only supported on Linux and macOS",
+            )),
+        }
+    }
+
+    pub(super) fn user_by_name(name: &str) -> io::Result<Option<(u32, u32)>> {
+        unsupported(name).map(|()| None)
+    }
+
+    pub(super) fn user_by_id(_uid: u32) -> io::Result<Option<(u32, u32)>> {
+        Ok(None)
+    }
+
+    pub(super) fn group_by_name(name: &str) -> io::Result<Option<u32>> {
+        unsupported(name).map(|()| None)
+    }
+
+    pub(super) fn group_by_id(_gid: u32) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,498 @@
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::acl::Acl;
+use crate::action::Action;
+use crate::license::Expression;
+use crate::mode::{self, Mode, ParentsMode};
+use crate::output::Format;
+use crate::owner::Owner;
+use crate::reporter::{ColorChoice, Palette, Verbosity};
+use crate::runner::CommandSpec;
+use crate::spec::{Node, Spec};
+use crate::template::{self, Context, Value};
+use crate::templates;
+use crate::toolchain::{Outdated, Requirement};
+use crate::user_template::UserTemplate;
+
+/// What the user asked for: a set of directories and the actions to apply to each.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreateRequest {
+    pub dirs: Vec<PathBuf>,
+    pub actions: Vec<Action>,
+    pub mode: Option<Mode>,
+    /// Which new parent directories of each target get a mode too.
+    pub parents_mode: ParentsMode,
+    /// User and group for every directory makedir creates or is given.
+    pub owner: Owner,
+    /// Make every directory group-writable and setgid.
+    pub shared: bool,
+    /// Entries merged into every directory's access ACL.
+    pub acl: Acl,
+    /// Entries merged into every directory's default ACL, which new files
+    /// and directories inside inherit.
+    pub default_acl: Acl,
+    /// How much to print, from `-q` or `-v` up to `-vvv`.
+    pub verbosity: Verbosity,
+    /// What to print once done, from `--output`.
+    pub output: Format,
+    /// Stream a JSON object per step to stdout, from `--events jsonl`.
+    pub events: bool,
+    /// When to color messages, from `--color` or the config file.
+    pub color: ColorChoice,
+    pub palette: Palette,
+    /// Only describe the plan instead of executing it.
+    pub dry_run: bool,
+    /// Undo everything done to a directory if any of its steps fail.
+    pub atomic: bool,
+    /// Stop at the first directory that fails instead of processing the rest.
+    pub fail_fast: bool,
+    /// How many directories to process at once, from `-j`; 0 and 1 both
+    /// mean one at a time.
+    pub jobs: usize,
+    /// How long each external command may run before it is killed.
+    pub timeout: Option<Duration>,
+    /// Minimum toolchain versions, from `[requires]` and presets.
+    pub requires: Vec<Requirement>,
+    /// Whether a toolchain older than required stops the run.
+    pub outdated: Outdated,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// Prefix for `go mod init`; the module is named `<prefix>/<directory name>`.
+    pub go_module_prefix: Option<String>,
+    pub license: Option<String>,
+    /// Template directories copied into every directory after the built-in steps.
+    pub templates: Vec<UserTemplate>,
+    /// A tree to build inside every directory, from `--spec`.
+    pub spec: Option<Spec>,
+}
+
+/// The concrete steps makedir will perform, in order, for every directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    pub targets: Vec<Target>,
+    pub parents_mode: ParentsMode,
+    pub owner: Owner,
+    pub shared: bool,
+    pub acl: Acl,
+    pub default_acl: Acl,
+    pub verbosity: Verbosity,
+    pub output: Format,
+    pub events: bool,
+    pub color: ColorChoice,
+    pub palette: Palette,
+    pub atomic: bool,
+    pub fail_fast: bool,
+    pub jobs: usize,
+    pub timeout: Option<Duration>,
+    pub requires: Vec<Requirement>,
+    pub outdated: Outdated,
+    /// The process umask, read once when the plan is built so that worker
+    /// threads never have to change it to apply a symbolic mode.
+    pub umask: u32,
+}
+
+/// A single directory and everything that happens inside it after it is created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub dir: PathBuf,
+    pub mode: Option<Mode>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Run an external command with the target directory as working directory.
+    Run(CommandSpec),
+    /// Write a file, relative to the target directory.
+    WriteFile { name: String, contents: String },
+    /// Create an empty file unless one already exists.
+    CreateFile { name: String },
+    /// Create a directory, and any missing parents, relative to the target
+    /// directory.
+    CreateDir { name: String },
+    /// Copy a file unchanged to a path relative to the target directory.
+    CopyFile { from: PathBuf, name: String },
+    /// Change the mode of a path relative to the target directory, where
+    /// `.` is the directory itself.
+    SetMode { name: String, mode: Mode },
+    /// Change the owner of a path, named as for `SetMode`.
+    SetOwner { name: String, owner: Owner },
+    /// Set the license field of a Cargo.toml or package.json written by an initializer.
+    SetLicenseField { name: String, license: String },
+}
+
+impl Plan {
+    /// Builds the plan for `request`, or explains why its license or one of
+    /// its user templates is invalid.
+    pub fn new(request: &CreateRequest) -> Result<Plan, String> {
+        let mut request = request.clone();
+        for template in &request.templates {
+            for action in &template.flags {
+                if !request.actions.contains(action) {
+                    request.actions.push(*action);
+                }
+            }
+        }
+
+        let license = Expression::parse(request.license.as_deref().unwrap_or("MIT"))
+            .map_err(|e| format!("Invalid license: {}", e))?;
+        request.license = Some(license.to_string());
+
+        let spec = request.spec.take();
+        let mut targets = Vec::new();
+        for dir in &request.dirs {
+            match &spec {
+                Some(spec) => spec_targets(
+                    &spec.root,
+                    dir,
+                    &request,
+                    request.mode.as_ref(),
+                    &license,
+                    &mut targets,
+                )?,
+                None => targets.push(target_for(dir, &request, &license)?),
+            }
+        }
+
+        Ok(Plan {
+            targets,
+            parents_mode: request.parents_mode.clone(),
+            owner: request.owner.clone(),
+            shared: request.shared,
+            acl: request.acl.clone(),
+            default_acl: request.default_acl.clone(),
+            verbosity: request.verbosity,
+            output: request.output,
+            events: request.events,
+            color: request.color,
+            palette: request.palette.clone(),
+            atomic: request.atomic,
+            fail_fast: request.fail_fast,
+            jobs: request.jobs,
+            timeout: request.timeout,
+            requires: request.requires.clone(),
+            outdated: request.outdated,
+            umask: mode::umask(),
+        })
+    }
+}
+
+fn target_for(dir: &Path, request: &CreateRequest, license: &Expression) -> Result<Target, String> {
+    let mut steps: Vec<Step> = request
+        .actions
+        .iter()
+        .flat_map(|action| steps_for(*action, dir, request, license))
+        .collect();
+    if request.actions.contains(&Action::License) {
+        steps.extend(license_field_steps(request, license));
+    }
+    for template in &request.templates {
+        steps.extend(template_steps(template, dir, request)?);
+    }
+    Ok(Target {
+        dir: dir.to_path_buf(),
+        mode: request.mode.clone(),
+        steps,
+    })
+}
+
+/// Adds targets for a spec node at `dir` and every directory below it.
+///
+/// `request` holds what applies to this node: the root gets the command
+/// line's flags and templates, subdirectories only their own flags.
+/// `default_mode` is used by nodes that do not set a mode.
+fn spec_targets(
+    node: &Node,
+    dir: &Path,
+    request: &CreateRequest,
+    default_mode: Option<&Mode>,
+    license: &Expression,
+    targets: &mut Vec<Target>,
+) -> Result<(), String> {
+    let mut request = request.clone();
+    for action in &node.flags {
+        if !request.actions.contains(action) {
+            request.actions.push(*action);
+        }
+    }
+    request.mode = node.mode.as_ref().or(default_mode).cloned();
+
+    let mut target = target_for(dir, &request, license)?;
+    // chown can clear the setgid bit, so the mode is set again afterwards
+    if !node.owner.is_empty() {
+        target.steps.push(Step::SetOwner {
+            name: ".".to_string(),
+            owner: node.owner.clone(),
+        });
+        if let Some(mode) = &request.mode {
+            target.steps.push(Step::SetMode {
+                name: ".".to_string(),
+                mode: mode.clone(),
+            });
+        }
+    }
+    let context = template_context(dir, &request);
+    for file in &node.files {
+        let name = file.path.clone();
+        target.steps.push(match &file.contents {
+            Some(source) => Step::WriteFile {
+                contents: template::render(source, &context).map_err(|e| {
+                    format!("spec file `{}`: {}", dir.join(&file.path).display(), e)
+                })?,
+                name,
+            },
+            None => Step::CreateFile { name },
+        });
+        if !file.owner.is_empty() {
+            target.steps.push(Step::SetOwner {
+                name: file.path.clone(),
+                owner: file.owner.clone(),
+            });
+        }
+        if let Some(mode) = &file.mode {
+            target.steps.push(Step::SetMode {
+                name: file.path.clone(),
+                mode: mode.clone(),
+            });
+        }
+    }
+    targets.push(target);
+
+    for (name, child) in &node.dirs {
+        let child_request = CreateRequest {
+            actions: Vec::new(),
+            templates: Vec::new(),
+            ..request.clone()
+        };
+        spec_targets(
+            child,
+            &dir.join(name),
+            &child_request,
+            default_mode,
+            license,
+            targets,
+        )?;
+    }
+    Ok(())
+}
+
+fn steps_for(
+    action: Action,
+    dir: &Path,
+    request: &CreateRequest,
+    license: &Expression,
+) -> Vec<Step> {
+    let run = |program: &str, args: &[&str]| Step::Run(CommandSpec::new(program, args));
+    let write = |name: &str, source: &str| Step::WriteFile {
+        name: name.to_string(),
+        contents: template::render(source, &template_context(dir, request))
+            .expect("built-in templates are valid"),
+    };
+
+    let step = match action {
+        Action::Git => run("git", &["init"]),
+        Action::Npm => run("npm", &["init", "-y"]),
+        Action::Bun => run("bun", &["init", "-y"]),
+        Action::Yarn => run("yarn", &["init", "-y"]),
+        Action::Pnpm => run("pnpm", &["init"]),
+        Action::Cargo => run("cargo", &["init"]),
+        Action::Go => run("go", &["mod", "init", &go_module(dir, request)]),
+        Action::Deno => write("deno.json", templates::DENO_JSON),
+        Action::Docker => write("Dockerfile", templates::DOCKERFILE),
+        Action::Readme => write("README.md", templates::README),
+        Action::License => {
+            return license
+                .files()
+                .into_iter()
+                .map(|(name, license)| write(&name, license.text))
+                .collect()
+        }
+    };
+    vec![step]
+}
+
+/// Records the license in the manifests the initializers create.
+fn license_field_steps(request: &CreateRequest, license: &Expression) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let field = |name: &str| Step::SetLicenseField {
+        name: name.to_string(),
+        license: license.to_string(),
+    };
+    if request.actions.contains(&Action::Cargo) {
+        steps.push(field("Cargo.toml"));
+    }
+    if request.actions.iter().any(|action| {
+        matches!(
+            action,
+            Action::Npm | Action::Yarn | Action::Pnpm | Action::Bun
+        )
+    }) {
+        steps.push(field("package.json"));
+    }
+    steps
+}
+
+fn template_steps(
+    template: &UserTemplate,
+    dir: &Path,
+    request: &CreateRequest,
+) -> Result<Vec<Step>, String> {
+    let context = template_context(dir, request);
+    let render = |what: &str, source: &str| {
+        template::render(source, &context)
+            .map_err(|e| format!("template `{}`, {}: {}", template.name, what, e))
+    };
+    let relative = |path: String| {
+        let valid = !path.is_empty()
+            && Path::new(&path)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if valid {
+            Ok(path)
+        } else {
+            Err(format!(
+                "template `{}`: `{}` is not a path inside the directory",
+                template.name, path
+            ))
+        }
+    };
+
+    let mut steps = Vec::new();
+    for path in &template.empty_dirs {
+        steps.push(Step::CreateDir {
+            name: relative(render(path, path)?)?,
+        });
+    }
+    for file in &template.files {
+        let name = relative(render(&file.path, file.target())?)?;
+        match &file.text {
+            // Written files get the umask defaults, so the source mode is copied over
+            Some(text) => {
+                steps.push(Step::WriteFile {
+                    contents: render(&file.path, text)?,
+                    name: name.clone(),
+                });
+                steps.push(Step::SetMode {
+                    name,
+                    mode: Mode::Octal(file.mode),
+                });
+            }
+            None => steps.push(Step::CopyFile {
+                from: template.root.join(&file.path),
+                name,
+            }),
+        }
+    }
+    for (path, mode) in &template.permissions {
+        steps.push(Step::SetMode {
+            name: relative(render(path, path)?)?,
+            mode: mode.clone(),
+        });
+    }
+    for command in &template.commands {
+        let mut args = Vec::new();
+        for arg in &command.args {
+            args.push(render(&command.program, arg)?);
+        }
+        steps.push(Step::Run(CommandSpec {
+            program: command.program.clone(),
+            args,
+        }));
+    }
+    Ok(steps)
+}
+
+/// The path a step's `name` refers to, where `.` is `dir` itself.
+pub(crate) fn step_path(dir: &Path, name: &str) -> PathBuf {
+    if name == "." {
+        dir.to_path_buf()
+    } else {
+        dir.join(name)
+    }
+}
+
+fn go_module(dir: &Path, request: &CreateRequest) -> String {
+    match (&request.go_module_prefix, dir.file_name()) {
+        (Some(prefix), Some(name)) => format!(
+            "{}/{}",
+            prefix.trim_end_matches('/'),
+            name.to_string_lossy()
+        ),
+        _ => dir.display().to_string(),
+    }
+}
+
+fn author(request: &CreateRequest) -> Option<String> {
+    match (&request.author_name, &request.author_email) {
+        (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+        (Some(name), None) => Some(name.clone()),
+        (None, Some(email)) => Some(email.clone()),
+        (None, None) => None,
+    }
+}
+
+/// The variables generated files are rendered with.
+///
+/// `project` is the directory name, `flags.<name>` is true for every selected
+/// action, `tools` lists the selected initializers and `package_manager` is
+/// the first of npm, yarn, pnpm or bun that was selected, or empty.
+pub fn template_context(dir: &Path, request: &CreateRequest) -> Context {
+    let project = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string());
+
+    let mut flags = Context::new();
+    for action in Action::ALL {
+        flags.insert(action.name(), request.actions.contains(&action));
+    }
+    let tools = request
+        .actions
+        .iter()
+        .filter(|action| !matches!(action, Action::Readme | Action::License | Action::Docker))
+        .map(|action| Value::from(action.name()))
+        .collect();
+    let package_manager = request
+        .actions
+        .iter()
+        .find(|action| {
+            matches!(
+                action,
+                Action::Npm | Action::Yarn | Action::Pnpm | Action::Bun
+            )
+        })
+        .map_or("", |action| action.name());
+
+    let mut context = Context::new();
+    context.insert("project", project);
+    context.insert("dir", dir.display().to_string());
+    context.insert("year", template::current_year().to_string());
+    context.insert("author", author(request).unwrap_or_default());
+    context.insert(
+        "author_name",
+        request.author_name.clone().unwrap_or_default(),
+    );
+    context.insert(
+        "author_email",
+        request.author_email.clone().unwrap_or_default(),
+    );
+    let license = request.license.as_deref().unwrap_or("MIT");
+    let license_files = Expression::parse(license)
+        .map(|expression| {
+            let names: Vec<String> = expression
+                .files()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            names.join(" and ")
+        })
+        .unwrap_or_else(|_| "LICENSE".to_string());
+    context.insert("license", license);
+    context.insert("license_files", license_files);
+    context.insert("go_module", go_module(dir, request));
+    context.insert("flags", flags);
+    context.insert("tools", Value::List(tools));
+    context.insert("package_manager", package_manager);
+    context
+}
@@ -0,0 +1,105 @@
+use crate::action::Action;
+use crate::config::{flag_list, mode_value, requirements, requirements_table};
+use crate::mode::Mode;
+use crate::toml::{Table, Value};
+use crate::toolchain::Requirement;
+
+/// A named set of flags from a `[preset.<name>]` config table.
+///
+/// ```toml
+/// [preset.rust]
+/// extends = "base"
+/// flags = ["readme", "cargo", "license"]
+/// mode = "755"
+///
+/// [preset.rust.requires]
+/// rust = ">=1.75"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    /// Presets whose flags and mode are applied first.
+    pub extends: Vec<String>,
+    pub flags: Vec<Action>,
+    pub mode: Option<Mode>,
+    /// Minimum toolchain versions for directories made with the preset.
+    pub requires: Vec<Requirement>,
+}
+
+/// A preset with everything it extends folded in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedPreset {
+    pub flags: Vec<Action>,
+    pub mode: Option<Mode>,
+    pub requires: Vec<Requirement>,
+}
+
+impl Preset {
+    pub(crate) fn from_table(name: &str, table: &Table) -> Result<Preset, String> {
+        let mut preset = Preset {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let key = |field: &str| format!("preset.{}.{}", name, field);
+
+        for (field, value) in table.iter() {
+            match field {
+                "flags" => preset.flags = flag_list(&key(field), value)?,
+                "mode" => preset.mode = Some(mode_value(&key(field), value)?),
+                "requires" => preset.requires = requirements(&key(field), value)?,
+                "extends" => {
+                    preset.extends = match value {
+                        Value::String(parent) => vec![parent.clone()],
+                        Value::Array(items) => items
+                            .iter()
+                            .map(|item| item.as_str().map(str::to_string))
+                            .collect::<Option<_>>()
+                            .ok_or_else(|| format!("`{}` must contain strings", key(field)))?,
+                        _ => return Err(format!("`{}` must be a string or array", key(field))),
+                    }
+                }
+                _ => return Err(format!("unknown key `{}` in `[preset.{}]`", field, name)),
+            }
+        }
+
+        Ok(preset)
+    }
+
+    pub(crate) fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        if !self.extends.is_empty() {
+            let parents = self.extends.iter().cloned().map(Value::String).collect();
+            table.insert("extends", Value::Array(parents));
+        }
+        let flags = self
+            .flags
+            .iter()
+            .map(|action| Value::String(action.name().to_string()))
+            .collect();
+        table.insert("flags", Value::Array(flags));
+        if let Some(mode) = &self.mode {
+            table.insert("mode", Value::String(mode.to_string()));
+        }
+        if !self.requires.is_empty() {
+            table.insert("requires", Value::Table(requirements_table(&self.requires)));
+        }
+        table
+    }
+}
+
+impl ResolvedPreset {
+    /// The preset written out as the command-line flags it stands for.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = self
+            .flags
+            .iter()
+            .map(|action| format!("--{}", action.name()))
+            .collect();
+        match &self.mode {
+            Some(Mode::Octal(mode)) if *mode <= 0o777 => args.push(format!("-{:03o}", mode)),
+            Some(mode) => args.push(format!("--mode={}", mode)),
+            None => {}
+        }
+        args
+    }
+}
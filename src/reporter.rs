@@ -0,0 +1,354 @@
+//! Everything makedir prints for people goes through a [`Reporter`].
+//!
+//! Color is decided per stream: `--color always` or `never` (or the config
+//! file's `color`) wins, otherwise `NO_COLOR` turns it off, `CLICOLOR_FORCE`
+//! turns it on, and it is used only when the stream is a terminal. The
+//! escape codes for each [`Role`] come from a configurable [`Palette`].
+
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::output::{Event, Format};
+use crate::plan::Plan;
+use crate::runner::Stream;
+
+/// When to color output, from `--color` or the config file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only on terminals, unless `NO_COLOR` or `CLICOLOR_FORCE` say otherwise.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(s: &str) -> Result<ColorChoice, String> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "unknown color choice `{}`, expected auto, always or never",
+                s
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// Whether a stream gets color, given whether it is a terminal and a
+    /// way to read the environment.
+    pub fn enabled(self, terminal: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+        let set = |name| var(name).is_some_and(|value| !value.is_empty());
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if set("NO_COLOR") => false,
+            ColorChoice::Auto if set("CLICOLOR_FORCE") => {
+                var("CLICOLOR_FORCE").as_deref() != Some("0")
+            }
+            ColorChoice::Auto => terminal,
+        }
+    }
+}
+
+/// How much makedir prints, from `-q` or up to three `-v`s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Errors and notices such as "Directory already exists:".
+    #[default]
+    Normal,
+    /// Every step, from `-v`.
+    Steps,
+    /// Also the output of initializers as it is printed, from `-vv`.
+    Output,
+    /// Also timings and the resolved argv of each command, from `-vvv`.
+    Debug,
+}
+
+impl Verbosity {
+    /// The level for `count` `-v` flags.
+    pub fn from_count(count: usize) -> Verbosity {
+        match count {
+            0 => Verbosity::Normal,
+            1 => Verbosity::Steps,
+            2 => Verbosity::Output,
+            _ => Verbosity::Debug,
+        }
+    }
+}
+
+/// What a piece of output means, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Error,
+    /// Headings and progress such as "Directory already exists:".
+    Notice,
+    Success,
+    /// Flags and names in listings.
+    Highlight,
+}
+
+/// SGR parameters for each role, such as `1;31` for bold red.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub error: String,
+    pub notice: String,
+    pub success: String,
+    pub highlight: String,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {
+            error: "1;31".to_string(),
+            notice: "1;33".to_string(),
+            success: "1;32".to_string(),
+            highlight: "32".to_string(),
+        }
+    }
+}
+
+impl Palette {
+    pub const ROLES: [&'static str; 4] = ["error", "notice", "success", "highlight"];
+
+    /// Sets the codes for one role, e.g. `set("error", "1;35")`.
+    pub fn set(&mut self, role: &str, codes: &str) -> Result<(), String> {
+        if !codes.split(';').all(|code| code.parse::<u8>().is_ok()) {
+            return Err(format!(
+                "invalid color `{}` for {}, expected SGR codes like 1;31",
+                codes, role
+            ));
+        }
+        let slot = match role {
+            "error" => &mut self.error,
+            "notice" => &mut self.notice,
+            "success" => &mut self.success,
+            "highlight" => &mut self.highlight,
+            _ => {
+                return Err(format!(
+                    "unknown color role `{}`, expected {}",
+                    role,
+                    Palette::ROLES.join(", ")
+                ))
+            }
+        };
+        *slot = codes.to_string();
+        Ok(())
+    }
+
+    /// Applies `role=codes` pairs separated by `:`, as in `MAKEDIR_COLORS`.
+    pub fn apply(&mut self, spec: &str) -> Result<(), String> {
+        for pair in spec.split(':').filter(|pair| !pair.is_empty()) {
+            let (role, codes) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected role=codes, got `{}`", pair))?;
+            self.set(role, codes)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, role: &str) -> Option<&str> {
+        match role {
+            "error" => Some(&self.error),
+            "notice" => Some(&self.notice),
+            "success" => Some(&self.success),
+            "highlight" => Some(&self.highlight),
+            _ => None,
+        }
+    }
+
+    fn codes(&self, role: Role) -> &str {
+        match role {
+            Role::Error => &self.error,
+            Role::Notice => &self.notice,
+            Role::Success => &self.success,
+            Role::Highlight => &self.highlight,
+        }
+    }
+}
+
+/// Colors text for one stream, or leaves it plain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Painter {
+    pub color: bool,
+    pub palette: Palette,
+}
+
+impl Painter {
+    pub fn paint(&self, role: Role, text: impl fmt::Display) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", self.palette.codes(role), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// `head` in the color of `role`, then `rest` uncolored.
+    pub fn line(&self, role: Role, head: impl fmt::Display, rest: impl fmt::Display) -> String {
+        let rest = rest.to_string();
+        if rest.is_empty() {
+            self.paint(role, head)
+        } else {
+            format!("{} {}", self.paint(role, head), rest)
+        }
+    }
+}
+
+/// Where and how progress, errors and events are printed.
+#[derive(Debug, Default)]
+pub struct Reporter {
+    pub stdout: Painter,
+    pub stderr: Painter,
+    verbosity: Verbosity,
+    events: bool,
+    /// Machine-readable output owns stdout, so messages go to stderr.
+    messages_to_stderr: bool,
+    /// Lines held back until [`Reporter::flush`], so that directories
+    /// processed in parallel don't interleave their output.
+    buffer: Option<Mutex<Vec<(Stream, String)>>>,
+}
+
+impl Reporter {
+    /// A reporter for the real stdout and stderr.
+    pub fn new(choice: ColorChoice, palette: &Palette) -> Reporter {
+        let painter = |terminal| Painter {
+            color: choice.enabled(terminal, |name| env::var(name).ok()),
+            palette: palette.clone(),
+        };
+        Reporter {
+            stdout: painter(io::stdout().is_terminal()),
+            stderr: painter(io::stderr().is_terminal()),
+            ..Default::default()
+        }
+    }
+
+    /// A reporter with the color, verbosity and output settings of `plan`.
+    pub fn for_plan(plan: &Plan) -> Reporter {
+        Reporter {
+            verbosity: plan.verbosity,
+            events: plan.events,
+            messages_to_stderr: plan.events || plan.output == Format::Json,
+            ..Reporter::new(plan.color, &plan.palette)
+        }
+    }
+
+    /// Whether messages at `level` are printed.
+    pub fn shows(&self, level: Verbosity) -> bool {
+        self.verbosity >= level
+    }
+
+    /// A progress message: `head` colored by `role`, then `rest`.
+    pub fn say(&self, role: Role, head: impl fmt::Display, rest: impl fmt::Display) {
+        if !self.shows(Verbosity::Normal) {
+            return;
+        }
+        if self.messages_to_stderr {
+            self.emit(Stream::Stderr, self.stderr.line(role, head, rest));
+        } else {
+            self.emit(Stream::Stdout, self.stdout.line(role, head, rest));
+        }
+    }
+
+    /// Like [`Reporter::say`], but only with `--verbose`.
+    pub fn detail(&self, role: Role, head: impl fmt::Display, rest: impl fmt::Display) {
+        if self.shows(Verbosity::Steps) {
+            self.say(role, head, rest);
+        }
+    }
+
+    /// Debugging information such as timings, only with `-vvv`.
+    pub fn debug(&self, message: impl fmt::Display) {
+        if self.shows(Verbosity::Debug) {
+            self.say(Role::Highlight, "debug:", message);
+        }
+    }
+
+    /// A line an initializer printed in `dir`, shown with `-vv`. Lines keep
+    /// the stream they were printed on, prefixed with the directory.
+    pub fn command_line(&self, dir: &Path, stream: Stream, line: &str) {
+        if !self.shows(Verbosity::Output) {
+            return;
+        }
+        let prefix = format!("[{}]", dir.display());
+        match stream {
+            Stream::Stdout if !self.messages_to_stderr => self.emit(
+                Stream::Stdout,
+                self.stdout.line(Role::Highlight, prefix, line),
+            ),
+            _ => self.emit(
+                Stream::Stderr,
+                self.stderr.line(Role::Highlight, prefix, line),
+            ),
+        }
+    }
+
+    /// A warning on stderr, which `--quiet` hides: `head` in the notice
+    /// color, then `rest`.
+    pub fn warn(&self, head: impl fmt::Display, rest: impl fmt::Display) {
+        if self.shows(Verbosity::Normal) {
+            self.emit(Stream::Stderr, self.stderr.line(Role::Notice, head, rest));
+        }
+    }
+
+    /// An error on stderr: `head` in the error color, then `rest`.
+    pub fn error(&self, head: impl fmt::Display, rest: impl fmt::Display) {
+        self.emit(Stream::Stderr, self.stderr.line(Role::Error, head, rest));
+    }
+
+    pub(crate) fn event(&self, event: Event) {
+        if self.events {
+            self.emit(Stream::Stdout, event.to_json());
+        }
+    }
+
+    /// A reporter with the same settings that holds its lines back until
+    /// [`Reporter::flush`].
+    pub(crate) fn buffered(&self) -> Reporter {
+        Reporter {
+            stdout: self.stdout.clone(),
+            stderr: self.stderr.clone(),
+            verbosity: self.verbosity,
+            events: self.events,
+            messages_to_stderr: self.messages_to_stderr,
+            buffer: Some(Mutex::default()),
+        }
+    }
+
+    /// Prints the lines held back by a [`Reporter::buffered`] reporter in
+    /// one go, while no other thread can print.
+    pub(crate) fn flush(&self) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+        let lines = std::mem::take(&mut *buffer.lock().unwrap());
+        let mut stdout = io::stdout().lock();
+        let mut stderr = io::stderr().lock();
+        for (stream, line) in lines {
+            let _ = match stream {
+                Stream::Stdout => writeln!(stdout, "{}", line),
+                Stream::Stderr => writeln!(stderr, "{}", line),
+            };
+        }
+    }
+
+    fn emit(&self, stream: Stream, line: String) {
+        match (&self.buffer, stream) {
+            (Some(buffer), _) => buffer.lock().unwrap().push((stream, line)),
+            (None, Stream::Stdout) => println!("{}", line),
+            (None, Stream::Stderr) => eprintln!("{}", line),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::{self as unix_fs, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::acl;
+use crate::reporter::{Reporter, Role};
+
+/// Everything makedir changed while working on one target, so that an
+/// `--atomic` run can undo it if a later step fails.
+///
+/// Only changes makedir made itself are recorded: a directory that already
+/// existed is never removed, only the entries that appeared inside it.
+#[derive(Debug, Default)]
+pub(crate) struct Journal {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+enum Entry {
+    /// A directory created by `create_dir_all`.
+    CreatedDir(PathBuf),
+    /// A file or directory written by a step or an external command.
+    CreatedPath(PathBuf),
+    /// A file that existed before and was overwritten, with its old contents.
+    Overwrote(PathBuf, Vec<u8>),
+    /// A permission change, with the previous mode.
+    ChangedMode(PathBuf, u32),
+    /// An ownership change, with the previous uid and gid.
+    ChangedOwner(PathBuf, u32, u32),
+    /// An access (false) or default (true) ACL change, with the previous ACL.
+    ChangedAcl(PathBuf, bool, Option<Vec<u8>>),
+}
+
+impl Journal {
+    pub(crate) fn created_dir(&mut self, path: PathBuf) {
+        self.entries.push(Entry::CreatedDir(path));
+    }
+
+    /// Records a file about to be written, remembering its contents if it exists.
+    pub(crate) fn writing_file(&mut self, path: &Path) {
+        match fs::read(path) {
+            Ok(old) => self.entries.push(Entry::Overwrote(path.to_path_buf(), old)),
+            Err(_) => self.entries.push(Entry::CreatedPath(path.to_path_buf())),
+        }
+    }
+
+    pub(crate) fn changing_mode(&mut self, path: &Path) {
+        if let Ok(metadata) = fs::metadata(path) {
+            let mode = metadata.permissions().mode() & 0o7777;
+            self.entries
+                .push(Entry::ChangedMode(path.to_path_buf(), mode));
+        }
+    }
+
+    pub(crate) fn changing_owner(&mut self, path: &Path) {
+        if let Ok(metadata) = fs::metadata(path) {
+            self.entries.push(Entry::ChangedOwner(
+                path.to_path_buf(),
+                metadata.uid(),
+                metadata.gid(),
+            ));
+        }
+    }
+
+    pub(crate) fn changing_acl(&mut self, path: &Path, default: bool) {
+        if let Ok(previous) = acl::save(path, default) {
+            self.entries
+                .push(Entry::ChangedAcl(path.to_path_buf(), default, previous));
+        }
+    }
+
+    /// Records every entry of `dir` that is not in `before`.
+    pub(crate) fn new_entries_since(&mut self, dir: &Path, before: &HashSet<OsString>) {
+        for name in snapshot(dir) {
+            if !before.contains(&name) {
+                self.entries.push(Entry::CreatedPath(dir.join(name)));
+            }
+        }
+    }
+
+    /// Undoes every recorded change, newest first, returning any that could not be undone.
+    pub(crate) fn rollback(self, reporter: &Reporter) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for entry in self.entries.into_iter().rev() {
+            let (path, result) = match entry {
+                Entry::CreatedDir(path) => {
+                    let result = fs::remove_dir(&path);
+                    (path, result)
+                }
+                Entry::CreatedPath(path) => {
+                    let result = if path.is_dir() {
+                        fs::remove_dir_all(&path)
+                    } else {
+                        fs::remove_file(&path)
+                    };
+                    (path, result)
+                }
+                Entry::Overwrote(path, old) => {
+                    let result = fs::write(&path, old);
+                    (path, result)
+                }
+                Entry::ChangedMode(path, mode) => {
+                    let result = fs::set_permissions(&path, fs::Permissions::from_mode(mode));
+                    (path, result)
+                }
+                Entry::ChangedOwner(path, uid, gid) => {
+                    let result = unix_fs::chown(&path, Some(uid), Some(gid));
+                    (path, result)
+                }
+                Entry::ChangedAcl(path, default, previous) => {
+                    let result = acl::restore(&path, default, previous.as_deref());
+                    (path, result)
+                }
+            };
+
+            match result {
+                Ok(()) => reporter.detail(Role::Notice, "Rolled back:", path.display()),
+                Err(e) => {
+                    reporter.error(format_args!("Failed to roll back {}:", path.display()), &e);
+                    errors.push(format!("failed to roll back {}: {}", path.display(), e));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// The names of the entries currently in `dir`.
+pub(crate) fn snapshot(dir: &Path) -> HashSet<OsString> {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name()).collect())
+        .unwrap_or_default()
+}
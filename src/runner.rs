@@ -0,0 +1,270 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// An external command as a program and its arguments.
+///
+/// Commands are always run directly, never through a shell, so arguments
+/// such as directory names are passed through exactly as given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CommandSpec {
+    pub fn new(program: &str, args: &[&str]) -> CommandSpec {
+        CommandSpec {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// The full argument vector, program first.
+    pub fn argv(&self) -> Vec<&str> {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// Formats the argv with shell quoting, so it can be read back unambiguously.
+impl fmt::Display for CommandSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quoted: Vec<String> = self.argv().into_iter().map(quote).collect();
+        f.write_str(&quoted.join(" "))
+    }
+}
+
+fn quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./=:@+,%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Which output stream of a command a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Environment given to every command so that initializers never stop to
+/// ask a question: most JavaScript tools skip prompts under `CI`, git never
+/// asks for credentials and npm answers yes.
+pub const NONINTERACTIVE_ENV: [(&str, &str); 3] = [
+    ("CI", "1"),
+    ("GIT_TERMINAL_PROMPT", "0"),
+    ("npm_config_yes", "true"),
+];
+
+/// Receives each line a command prints, without the line ending.
+pub type LineHandler<'a> = dyn Fn(Stream, &str) + Sync + 'a;
+
+/// How [`Runner::run_with`] runs a command.
+#[derive(Clone, Copy, Default)]
+pub struct RunOptions<'a> {
+    /// Kill the command, and everything it started, after this long.
+    pub timeout: Option<Duration>,
+    /// Called with each line of output as the command prints it.
+    pub on_line: Option<&'a LineHandler<'a>>,
+}
+
+/// Runs external commands on behalf of [`crate::execute_with`].
+pub trait Runner {
+    fn run(&self, command: &CommandSpec, dir: &Path) -> io::Result<Output>;
+
+    /// Like [`Runner::run`], with `options`. By default there is no timeout
+    /// and the lines are passed to `on_line` once the command is done.
+    fn run_with(
+        &self,
+        command: &CommandSpec,
+        dir: &Path,
+        options: &RunOptions,
+    ) -> io::Result<Output> {
+        let output = self.run(command, dir)?;
+        if let Some(on_line) = options.on_line {
+            for (stream, bytes) in [
+                (Stream::Stdout, &output.stdout),
+                (Stream::Stderr, &output.stderr),
+            ] {
+                for line in String::from_utf8_lossy(bytes).lines() {
+                    on_line(stream, line);
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Runs commands as child processes of makedir, with stdin from
+/// `/dev/null` and [`NONINTERACTIVE_ENV`] set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner;
+
+impl Runner for SystemRunner {
+    fn run(&self, command: &CommandSpec, dir: &Path) -> io::Result<Output> {
+        self.run_with(command, dir, &RunOptions::default())
+    }
+
+    /// Reads stdout and stderr as the command prints them. A command that
+    /// times out fails with [`io::ErrorKind::TimedOut`].
+    fn run_with(
+        &self,
+        command: &CommandSpec,
+        dir: &Path,
+        options: &RunOptions,
+    ) -> io::Result<Output> {
+        let mut builder = Command::new(&command.program);
+        builder
+            .args(&command.args)
+            .current_dir(dir)
+            .envs(NONINTERACTIVE_ENV)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // A group of its own lets a timeout kill everything the command
+        // started. Without a timeout it stays in ours, so Ctrl-C reaches it.
+        if options.timeout.is_some() {
+            builder.process_group(0);
+        }
+        let mut child = builder.spawn()?;
+
+        let ignore = |_: Stream, _: &str| {};
+        let on_line = options.on_line.unwrap_or(&ignore);
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        let (status, stdout, stderr) = thread::scope(|scope| {
+            let stdout = scope.spawn(|| read_lines(stdout, Stream::Stdout, on_line));
+            let stderr = scope.spawn(|| read_lines(stderr, Stream::Stderr, on_line));
+            let status = wait(&mut child, options.timeout);
+            let stdout = stdout.join().expect("stdout reader panicked");
+            (
+                status,
+                stdout,
+                stderr.join().expect("stderr reader panicked"),
+            )
+        });
+        Ok(Output {
+            status: status?,
+            stdout: stdout?,
+            stderr: stderr?,
+        })
+    }
+}
+
+/// Waits for `child`, killing its process group once `timeout` has passed.
+fn wait(child: &mut Child, timeout: Option<Duration>) -> io::Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return child.wait();
+    };
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        thread::sleep(POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+    }
+
+    kill_group(child.id());
+    child.wait()?;
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("timed out after {}", format_duration(timeout)),
+    ))
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn kill_group(pid: u32) {
+    extern "C" {
+        fn kill(pid: i32, sig: i32) -> i32;
+    }
+    const SIGKILL: i32 = 9;
+    // SAFETY: kill takes no pointers. The negative pid names the process
+    // group the child leads, which holds only it and what it started.
+    unsafe {
+        kill(-(pid as i32), SIGKILL);
+    }
+}
+
+/// Parses a timeout such as `90`, `90s`, `500ms`, `2m` or `1h`; plain
+/// numbers are seconds and `0` means no timeout.
+pub fn parse_duration(s: &str) -> Result<Option<Duration>, String> {
+    let error = || {
+        format!(
+            "invalid duration `{}`, expected a number of seconds or e.g. 500ms, 30s, 2m, 1h",
+            s
+        )
+    };
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number.parse().map_err(|_| error())?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(number),
+        "" | "s" => Duration::from_secs(number),
+        "m" => Duration::from_secs(number * 60),
+        "h" => Duration::from_secs(number * 3600),
+        _ => return Err(error()),
+    };
+    Ok((!duration.is_zero()).then_some(duration))
+}
+
+/// Formats a duration the way [`parse_duration`] reads it, in the largest
+/// unit that is exact.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    for (unit, size) in [("h", 3_600_000), ("m", 60_000), ("s", 1000)] {
+        if millis != 0 && millis.is_multiple_of(size) {
+            return format!("{}{}", millis / size, unit);
+        }
+    }
+    format!("{}ms", millis)
+}
+
+/// Reads `pipe` to the end, passing each line to `on_line` on the way.
+fn read_lines(
+    pipe: Option<impl Read>,
+    stream: Stream,
+    on_line: &LineHandler,
+) -> io::Result<Vec<u8>> {
+    let mut all = Vec::new();
+    let Some(pipe) = pipe else {
+        return Ok(all);
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        on_line(
+            stream,
+            String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']),
+        );
+        all.append(&mut line);
+    }
+    Ok(all)
+}
+
+/// Finds the executable `program` would run as, searching `PATH` unless
+/// the name contains a slash.
+pub fn resolve(program: &str) -> Option<PathBuf> {
+    let executable = |path: &Path| {
+        fs::metadata(path)
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    };
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return executable(&path).then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| executable(path))
+}
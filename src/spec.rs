@@ -0,0 +1,237 @@
+//! Directory trees described in a spec file and built with `--spec <file>`.
+//!
+//! Specs can be written in YAML, TOML or JSON, chosen by the file extension.
+//! Every directory node may set a mode, turn on built-in flags, list files
+//! and nest further directories:
+//!
+//! ```yaml
+//! name: billing
+//! flags: [git, readme]
+//! files:
+//!   .env: ""
+//!   docs/notes.md:
+//!     template: templates/notes.md
+//!     mode: "600"
+//! dirs:
+//!   src:
+//!     mode: "750"
+//!     flags: [cargo]
+//!   scripts:
+//!     files:
+//!       deploy.sh: { contents: "#!/bin/sh\n", mode: "755" }
+//! ```
+//!
+//! File contents, whether written inline or read from a `template` file
+//! relative to the spec, are rendered like the built-in templates. A file
+//! given neither is only created when missing, so an existing one is kept.
+
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::action::Action;
+use crate::config;
+use crate::mode::Mode;
+use crate::owner::Owner;
+use crate::toml::{self, Table, Value};
+use crate::{json, yaml};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spec {
+    /// Directory to create when none is given on the command line.
+    pub name: Option<String>,
+    pub root: Node,
+}
+
+/// A directory in the spec.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Node {
+    pub mode: Option<Mode>,
+    /// Only set by mtree manifests, which record an owner for every entry.
+    pub owner: Owner,
+    /// Initializers and generated files for this directory only.
+    pub flags: Vec<Action>,
+    pub files: Vec<FileSpec>,
+    /// Subdirectories, by path relative to this one.
+    pub dirs: Vec<(String, Node)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSpec {
+    /// Path relative to the directory the file is listed in.
+    pub path: String,
+    /// Template source for the file's contents, or `None` to create an
+    /// empty file only where there is none, leaving an existing one as it is.
+    pub contents: Option<String>,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+impl Spec {
+    pub fn load(path: &Path) -> Result<Spec, String> {
+        let format = Format::from_path(path).ok_or_else(|| {
+            format!(
+                "{}: unknown spec format, expected .yaml, .yml, .toml or .json",
+                path.display()
+            )
+        })?;
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        Spec::parse(&text, format, base).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parses a spec; `template` paths are resolved against `base`.
+    pub fn parse(text: &str, format: Format, base: &Path) -> Result<Spec, String> {
+        let table = match format {
+            Format::Yaml => yaml::parse(text),
+            Format::Toml => toml::parse(text),
+            Format::Json => json::parse(text),
+        }
+        .map_err(|e| e.to_string())?;
+
+        let mut spec = Spec::default();
+        let mut node = Table::new();
+        for (key, value) in table.iter() {
+            match key {
+                "name" => {
+                    let name = value
+                        .as_str()
+                        .ok_or_else(|| "`name` must be a string".to_string())?;
+                    spec.name = Some(relative_path("name", name)?.to_string());
+                }
+                _ => node.insert(key, value.clone()),
+            }
+        }
+        spec.root = read_node(&node, "", base)?;
+        Ok(spec)
+    }
+}
+
+fn read_node(table: &Table, at: &str, base: &Path) -> Result<Node, String> {
+    let mut node = Node::default();
+    for (key, value) in table.iter() {
+        let here = join(at, key);
+        match key {
+            "mode" => node.mode = Some(config::mode_value(&here, value)?),
+            "flags" => node.flags = config::flag_list(&here, value)?,
+            "files" => {
+                for (path, file) in entries(&here, value)? {
+                    let at = join(&here, path);
+                    node.files
+                        .push(read_file(relative_path(&at, path)?, file, &at, base)?);
+                }
+            }
+            "dirs" => {
+                for (path, dir) in entries(&here, value)? {
+                    let at = join(&here, path);
+                    let table = match dir {
+                        Value::Table(table) => table,
+                        _ => return Err(format!("`{}` must be a table", at)),
+                    };
+                    node.dirs.push((
+                        relative_path(&at, path)?.to_string(),
+                        read_node(table, &at, base)?,
+                    ));
+                }
+            }
+            _ => return Err(format!("unknown key `{}`", here)),
+        }
+    }
+    Ok(node)
+}
+
+fn read_file(path: &str, value: &Value, at: &str, base: &Path) -> Result<FileSpec, String> {
+    let mut file = FileSpec {
+        path: path.to_string(),
+        contents: None,
+        mode: None,
+        owner: Owner::default(),
+    };
+    let table = match value {
+        Value::String(contents) => {
+            file.contents = Some(contents.clone());
+            return Ok(file);
+        }
+        Value::Table(table) => table,
+        _ => return Err(format!("`{}` must be a string or a table", at)),
+    };
+
+    for (key, value) in table.iter() {
+        let here = join(at, key);
+        match key {
+            "contents" => {
+                file.contents = Some(
+                    value
+                        .as_str()
+                        .ok_or_else(|| format!("`{}` must be a string", here))?
+                        .to_string(),
+                )
+            }
+            "template" => {
+                let name = value
+                    .as_str()
+                    .ok_or_else(|| format!("`{}` must be a string", here))?;
+                let source = base.join(name);
+                file.contents = Some(fs::read_to_string(&source).map_err(|e| {
+                    format!("`{}`: failed to read {}: {}", here, source.display(), e)
+                })?);
+            }
+            "mode" => file.mode = Some(config::mode_value(&here, value)?),
+            _ => return Err(format!("unknown key `{}`", here)),
+        }
+    }
+    if table.get("contents").is_some() && table.get("template").is_some() {
+        return Err(format!("`{}` has both `contents` and `template`", at));
+    }
+    Ok(file)
+}
+
+/// The entries of a `files` or `dirs` table.
+fn entries<'a>(at: &str, value: &'a Value) -> Result<Vec<(&'a str, &'a Value)>, String> {
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("`{}` must be a table", at))?;
+    Ok(table.iter().collect())
+}
+
+/// Checks that `path` stays inside the directory it is relative to.
+pub(crate) fn relative_path<'a>(at: &str, path: &'a str) -> Result<&'a str, String> {
+    let valid = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(path.trim_end_matches('/'))
+    } else {
+        Err(format!(
+            "`{}`: `{}` must be a relative path without `..`",
+            at, path
+        ))
+    }
+}
+
+fn join(at: &str, key: &str) -> String {
+    if at.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", at, key)
+    }
+}
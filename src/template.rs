@@ -0,0 +1,596 @@
+//! A small template language for generated files.
+//!
+//! ```text
+//! # {{ project }}
+//! {% if flags.cargo %}
+//! cargo run
+//! {% elif package_manager %}
+//! {{ package_manager }} start
+//! {% endif %}
+//! {% for tool in tools %}
+//! * {{ tool | upper }}
+//! {% endfor %}
+//! ```
+//!
+//! `{{ expr }}` inserts a variable, optionally through filters (`default("x")`,
+//! `upper`, `lower`, `json`). `{% if %}`/`{% elif %}`/`{% else %}`/`{% endif %}`
+//! and `{% for x in list %}`/`{% endfor %}` control output, and `{# ... #}` is a
+//! comment. A line holding nothing but a `{% %}` tag is removed entirely, so
+//! block tags can sit on their own lines without leaving blank lines behind.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A value available to templates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Text(String),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(Context),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+            Value::Map(map) => !map.vars.is_empty(),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Value::Text(s) => s.clone(),
+            Value::Bool(b) => b.to_string(),
+            Value::List(items) => items
+                .iter()
+                .map(Value::to_text)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Value::Map(_) => "[map]".to_string(),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::Text(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+/// Named values a template is rendered with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    vars: Vec<(String, Value)>,
+}
+
+impl Context {
+    pub fn new() -> Context {
+        Context::default()
+    }
+
+    pub fn insert(&mut self, name: &str, value: impl Into<Value>) {
+        let value = value.into();
+        match self.vars.iter_mut().find(|(k, _)| k == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.vars.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.vars.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+    }
+
+    /// Looks up a dotted path such as `author.name`.
+    fn lookup(&self, path: &str) -> Option<&Value> {
+        let mut parts = path.split('.');
+        let mut value = self.get(parts.next()?)?;
+        for part in parts {
+            value = match value {
+                Value::Map(map) => map.get(part)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+impl From<Context> for Value {
+    fn from(map: Context) -> Value {
+        Value::Map(map)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses and renders `source` in one go.
+pub fn render(source: &str, context: &Context) -> Result<String, TemplateError> {
+    Template::parse(source)?.render(context)
+}
+
+/// The current year in UTC, for copyright lines.
+pub fn current_year() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let days = secs.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let month = (5 * doy + 2) / 153;
+    let year = yoe + era * 400;
+    if month >= 10 {
+        year + 1
+    } else {
+        year
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Expr {
+        line: usize,
+        expr: Expr,
+    },
+    If {
+        branches: Vec<(Condition, Vec<Node>)>,
+        otherwise: Vec<Node>,
+    },
+    For {
+        line: usize,
+        var: String,
+        list: String,
+        body: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expr {
+    path: String,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Default(String),
+    Upper,
+    Lower,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Truthy(String),
+    Not(Box<Condition>),
+    Equals(String, String),
+    NotEquals(String, String),
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Expr(usize, String),
+    Block(usize, String),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        let tokens = tokenize(source)?;
+        let mut tokens = tokens.into_iter().peekable();
+        let (nodes, end) = parse_nodes(&mut tokens)?;
+        if let Some((line, tag)) = end {
+            return Err(TemplateError {
+                line,
+                message: format!("unexpected `{{% {} %}}`", tag),
+            });
+        }
+        Ok(Template { nodes })
+    }
+
+    pub fn render(&self, context: &Context) -> Result<String, TemplateError> {
+        let mut out = String::new();
+        let mut scope = context.clone();
+        render_nodes(&self.nodes, &mut scope, &mut out)?;
+        Ok(out)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    // Whether the text before `rest` ends at the start of a line
+    let mut at_line_start = true;
+
+    while !rest.is_empty() {
+        let next = ["{{", "{%", "{#"]
+            .iter()
+            .filter_map(|open| rest.find(open).map(|i| (i, *open)))
+            .min();
+        let Some((start, open)) = next else {
+            tokens.push(Token::Text(rest.to_string()));
+            break;
+        };
+
+        let mut text = rest[..start].to_string();
+        line += text.matches('\n').count();
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let inner_start = start + 2;
+        let Some(len) = rest[inner_start..].find(close) else {
+            return Err(TemplateError {
+                line,
+                message: format!("`{}` is never closed", open),
+            });
+        };
+        let inner = rest[inner_start..inner_start + len].trim().to_string();
+        let mut after = &rest[inner_start + len + 2..];
+
+        // A block tag or comment alone on its line takes the whole line with it
+        let mut stripped = false;
+        if open != "{{" {
+            let (line_start, starts_line) = match text.rfind('\n') {
+                Some(i) => (i + 1, true),
+                None => (0, at_line_start),
+            };
+            let rest_of_line = after.find('\n').map(|i| &after[..i]).unwrap_or(after);
+            if starts_line && text[line_start..].trim().is_empty() && rest_of_line.trim().is_empty()
+            {
+                text.truncate(line_start);
+                after = match after.find('\n') {
+                    Some(i) => {
+                        line += 1;
+                        &after[i + 1..]
+                    }
+                    None => "",
+                };
+                stripped = true;
+            }
+        }
+        at_line_start = stripped;
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        let tag_line = line;
+        line += inner.matches('\n').count();
+        match open {
+            "{{" => tokens.push(Token::Expr(tag_line, inner)),
+            "{%" => tokens.push(Token::Block(tag_line, inner)),
+            _ => {}
+        }
+        rest = after;
+    }
+
+    Ok(tokens)
+}
+
+type Tokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
+
+/// A block tag and the line it is on.
+type Tag = (usize, String);
+
+/// Parses nodes until a block tag that closes the current block, which is returned.
+fn parse_nodes(tokens: &mut Tokens) -> Result<(Vec<Node>, Option<Tag>), TemplateError> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Expr(line, inner) => nodes.push(Node::Expr {
+                line,
+                expr: parse_expr(line, &inner)?,
+            }),
+            Token::Block(line, inner) => {
+                let keyword = inner.split_whitespace().next().unwrap_or("");
+                match keyword {
+                    "if" => nodes.push(parse_if(tokens, line, &inner)?),
+                    "for" => nodes.push(parse_for(tokens, line, &inner)?),
+                    "elif" | "else" | "endif" | "endfor" => {
+                        return Ok((nodes, Some((line, inner))))
+                    }
+                    _ => {
+                        return Err(TemplateError {
+                            line,
+                            message: format!("unknown tag `{{% {} %}}`", inner),
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_if(tokens: &mut Tokens, line: usize, inner: &str) -> Result<Node, TemplateError> {
+    let mut branches = Vec::new();
+    let mut condition = parse_condition(line, inner["if".len()..].trim())?;
+
+    loop {
+        let (body, end) = parse_nodes(tokens)?;
+        let Some((end_line, end_tag)) = end else {
+            return Err(TemplateError {
+                line,
+                message: "`{% if %}` without `{% endif %}`".to_string(),
+            });
+        };
+        branches.push((condition, body));
+
+        let keyword = end_tag.split_whitespace().next().unwrap_or("");
+        match keyword {
+            "elif" => condition = parse_condition(end_line, end_tag["elif".len()..].trim())?,
+            "else" => {
+                let (otherwise, end) = parse_nodes(tokens)?;
+                return match end {
+                    Some((_, tag)) if tag == "endif" => Ok(Node::If {
+                        branches,
+                        otherwise,
+                    }),
+                    _ => Err(TemplateError {
+                        line: end_line,
+                        message: "`{% else %}` without `{% endif %}`".to_string(),
+                    }),
+                };
+            }
+            "endif" => {
+                return Ok(Node::If {
+                    branches,
+                    otherwise: Vec::new(),
+                })
+            }
+            _ => {
+                return Err(TemplateError {
+                    line: end_line,
+                    message: format!("unexpected `{{% {} %}}` inside `{{% if %}}`", end_tag),
+                })
+            }
+        }
+    }
+}
+
+fn parse_for(tokens: &mut Tokens, line: usize, inner: &str) -> Result<Node, TemplateError> {
+    let words: Vec<&str> = inner.split_whitespace().collect();
+    let (var, list) = match words.as_slice() {
+        ["for", var, "in", list] => (var.to_string(), list.to_string()),
+        _ => {
+            return Err(TemplateError {
+                line,
+                message: "expected `{% for <name> in <list> %}`".to_string(),
+            })
+        }
+    };
+
+    let (body, end) = parse_nodes(tokens)?;
+    match end {
+        Some((_, tag)) if tag == "endfor" => Ok(Node::For {
+            line,
+            var,
+            list,
+            body,
+        }),
+        _ => Err(TemplateError {
+            line,
+            message: "`{% for %}` without `{% endfor %}`".to_string(),
+        }),
+    }
+}
+
+fn parse_condition(line: usize, text: &str) -> Result<Condition, TemplateError> {
+    if let Some(rest) = text.strip_prefix("not ") {
+        return Ok(Condition::Not(Box::new(parse_condition(
+            line,
+            rest.trim(),
+        )?)));
+    }
+    for (op, negate) in [("!=", true), ("==", false)] {
+        if let Some((left, right)) = text.split_once(op) {
+            let literal = parse_string(line, right.trim())?;
+            let path = parse_path(line, left.trim())?;
+            return Ok(if negate {
+                Condition::NotEquals(path, literal)
+            } else {
+                Condition::Equals(path, literal)
+            });
+        }
+    }
+    Ok(Condition::Truthy(parse_path(line, text)?))
+}
+
+fn parse_expr(line: usize, text: &str) -> Result<Expr, TemplateError> {
+    let mut parts = text.split('|');
+    let path = parse_path(line, parts.next().unwrap_or("").trim())?;
+    let mut filters = Vec::new();
+
+    for part in parts {
+        let part = part.trim();
+        let filter = match part {
+            "upper" => Filter::Upper,
+            "lower" => Filter::Lower,
+            "json" => Filter::Json,
+            _ => match part
+                .strip_prefix("default(")
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                Some(arg) => Filter::Default(parse_string(line, arg.trim())?),
+                None => {
+                    return Err(TemplateError {
+                        line,
+                        message: format!("unknown filter `{}`", part),
+                    })
+                }
+            },
+        };
+        filters.push(filter);
+    }
+
+    Ok(Expr { path, filters })
+}
+
+fn parse_path(line: usize, text: &str) -> Result<String, TemplateError> {
+    let valid = !text.is_empty()
+        && text
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    if valid {
+        Ok(text.to_string())
+    } else {
+        Err(TemplateError {
+            line,
+            message: format!("invalid variable name `{}`", text),
+        })
+    }
+}
+
+fn parse_string(line: usize, text: &str) -> Result<String, TemplateError> {
+    let quoted = text.len() >= 2
+        && ((text.starts_with('"') && text.ends_with('"'))
+            || (text.starts_with('\'') && text.ends_with('\'')));
+    if quoted {
+        Ok(text[1..text.len() - 1].to_string())
+    } else {
+        Err(TemplateError {
+            line,
+            message: format!("expected a quoted string, found `{}`", text),
+        })
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scope: &mut Context,
+    out: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Expr { line, expr } => out.push_str(&eval_expr(*line, expr, scope)?),
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                let body = branches
+                    .iter()
+                    .find(|(condition, _)| eval_condition(condition, scope))
+                    .map(|(_, body)| body)
+                    .unwrap_or(otherwise);
+                render_nodes(body, scope, out)?;
+            }
+            Node::For {
+                line,
+                var,
+                list,
+                body,
+            } => {
+                let items = match scope.lookup(list) {
+                    Some(Value::List(items)) => items.clone(),
+                    Some(_) => {
+                        return Err(TemplateError {
+                            line: *line,
+                            message: format!("`{}` is not a list", list),
+                        })
+                    }
+                    None => Vec::new(),
+                };
+                let shadowed = scope.get(var).cloned();
+                for item in items {
+                    scope.insert(var, item);
+                    render_nodes(body, scope, out)?;
+                }
+                match shadowed {
+                    Some(value) => scope.insert(var, value),
+                    None => scope.vars.retain(|(k, _)| k != var),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn eval_condition(condition: &Condition, scope: &Context) -> bool {
+    match condition {
+        Condition::Truthy(path) => scope.lookup(path).is_some_and(Value::is_truthy),
+        Condition::Not(inner) => !eval_condition(inner, scope),
+        Condition::Equals(path, literal) => {
+            scope.lookup(path).map(Value::to_text).as_deref() == Some(literal.as_str())
+        }
+        Condition::NotEquals(path, literal) => {
+            scope.lookup(path).map(Value::to_text).as_deref() != Some(literal.as_str())
+        }
+    }
+}
+
+fn eval_expr(line: usize, expr: &Expr, scope: &Context) -> Result<String, TemplateError> {
+    let mut value = scope.lookup(&expr.path).map(Value::to_text);
+
+    for filter in &expr.filters {
+        value = match filter {
+            Filter::Default(fallback) => match value {
+                Some(text) if !text.is_empty() => Some(text),
+                _ => Some(fallback.clone()),
+            },
+            Filter::Upper => value.map(|text| text.to_uppercase()),
+            Filter::Lower => value.map(|text| text.to_lowercase()),
+            Filter::Json => value.map(|text| json_string(&text)),
+        };
+    }
+
+    value.ok_or_else(|| TemplateError {
+        line,
+        message: format!("undefined variable `{}`", expr.path),
+    })
+}
+
+/// Quotes `text` as a JSON string literal.
+pub fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,126 @@
+//! Templates for the files generated by the built-in flags.
+//!
+//! They are rendered with [`crate::template`]; see `plan::template_context`
+//! for the variables available to them.
+
+pub const README: &str = "\
+# {{ project }}
+
+Simple overview of use/purpose.
+
+## Description
+
+An in-depth paragraph about your project and overview of use.
+
+## Getting Started
+
+### Dependencies
+
+{% if flags.cargo %}
+* Rust and Cargo, see https://rustup.rs
+{% endif %}
+{% if flags.go %}
+* Go, see https://go.dev/doc/install
+{% endif %}
+{% if flags.deno %}
+* Deno, see https://deno.com
+{% endif %}
+{% if package_manager %}
+* Node.js and {{ package_manager }}
+{% endif %}
+* Describe any prerequisites, libraries, OS version, etc., needed before installing program.
+* ex. Windows 10
+
+### Installing
+
+* How/where to download your program
+* Any modifications needed to be made to files/folders
+
+### Executing program
+
+* How to run the program
+* Step-by-step bullets
+```bash
+{% if flags.cargo %}
+cargo run
+{% elif flags.go %}
+go run .
+{% elif flags.deno %}
+deno run main.ts
+{% elif package_manager %}
+{{ package_manager }} start
+{% else %}
+code blocks for commands
+{% endif %}
+```
+
+## Help
+
+Any advice for common problems or issues.
+```bash
+command to run if program contains helper info
+```
+
+## Authors
+
+{% if author %}
+{{ author }}
+{% else %}
+Contributors names and contact info
+ex. [@soup-ms](https://github.com/soup-ms)
+{% endif %}
+{% if tools %}
+
+## Built With
+
+{% for tool in tools %}
+* {{ tool }}
+{% endfor %}
+{% endif %}
+
+## Version History
+
+* v0.1.0
+    * Initial Release
+
+## License
+
+{% if license_files == \"LICENSE\" %}
+This project is licensed under the {{ license }} License - see the LICENSE file for details
+{% else %}
+This project is licensed under {{ license }} - see {{ license_files }} for details
+{% endif %}
+
+## Acknowledgments
+https://twitter.com/dompizzie
+";
+
+pub const DOCKERFILE: &str = r#"# Base image (Default: Debian)
+ARG BASE_IMAGE=debian:latest
+FROM $BASE_IMAGE AS builder
+
+# Set working directory
+WORKDIR /app
+
+# Copy project files
+COPY . .
+
+# Install dependencies based on the selected stack
+ARG STACK={% if flags.cargo %}rust{% elif flags.go %}go{% elif flags.deno %}deno{% else %}node{% endif %}
+RUN case "$STACK" in \
+        node) apt update && apt install -y curl && curl -fsSL https://deb.nodesource.com/setup_16.x | bash - && apt install -y nodejs ;; \
+        python) apt update && apt install -y python3 python3-pip ;; \
+        rust) apt update && apt install -y curl && curl https://sh.rustup.rs -sSf | sh -s -- -y ;; \
+        go) apt update && apt install -y golang ;; \
+        deno) curl -fsSL https://deno.land/install.sh | sh ;; \
+        *) echo "No valid stack specified"; exit 1 ;; \
+    esac
+
+# Expose port (Modify as needed)
+EXPOSE 3000
+
+# Command to run the application (Modify based on project type)
+CMD ["echo", "Container is running, customize CMD as needed!"]
+"#;
+
+pub const DENO_JSON: &str = "{\n  \"importMap\": \"./import_map.json\"\n}";
@@ -0,0 +1,613 @@
+//! A small TOML reader and writer for makedir's own files.
+//!
+//! It covers the parts of TOML that config files, presets and manifests use:
+//! tables, arrays of tables, dotted keys, inline tables, arrays, strings,
+//! integers and booleans. Floats and dates are rejected.
+
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+/// A table that keeps keys in the order they were written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    entries: Vec<(String, Value)>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Inserts or replaces `key`, keeping its original position when replacing.
+    pub fn insert(&mut self, key: &str, value: Value) {
+        match self.get_mut(key) {
+            Some(existing) => *existing = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// A syntax error, with the 1-based line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Table, ParseError> {
+    Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    }
+    .document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments, as allowed inside arrays.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), ParseError> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None => Ok(()),
+            Some('\r') if self.peek_at(1) == Some('\n') => {
+                self.bump();
+                self.bump();
+                Ok(())
+            }
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => self.error(format!("unexpected `{}` at end of line", c)),
+        }
+    }
+
+    fn document(mut self) -> Result<Table, ParseError> {
+        let mut root = Table::new();
+        // Path of the table that key/value lines currently go into
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    let is_array = self.peek_at(1) == Some('[');
+                    self.bump();
+                    if is_array {
+                        self.bump();
+                    }
+                    self.skip_spaces();
+                    let path = self.key()?;
+                    self.skip_spaces();
+                    let close = if is_array { "]]" } else { "]" };
+                    if !self.starts_with(close) {
+                        return self.error(format!("expected `{}`", close));
+                    }
+                    for _ in 0..close.len() {
+                        self.bump();
+                    }
+                    self.end_of_line()?;
+
+                    let line = self.line;
+                    if is_array {
+                        push_array_table(&mut root, &path)
+                            .map_err(|message| ParseError { line, message })?;
+                    } else {
+                        table_at(&mut root, &path)
+                            .map_err(|message| ParseError { line, message })?;
+                    }
+                    current = path;
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.skip_spaces();
+                    if self.bump() != Some('=') {
+                        return self.error("expected `=` after key");
+                    }
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+
+                    let line = self.line;
+                    let table = table_at(&mut root, &current)
+                        .map_err(|message| ParseError { line, message })?;
+                    insert_dotted(table, &key, value)
+                        .map_err(|message| ParseError { line, message })?;
+                }
+            }
+        }
+    }
+
+    /// A possibly dotted key such as `preset."my app".flags`.
+    fn key(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let mut part = String::new();
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                            part.push(c);
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    if part.is_empty() {
+                        return self.error("expected a key");
+                    }
+                    part
+                }
+            };
+            parts.push(part);
+            self.skip_spaces();
+            if self.peek() == Some('.') {
+                self.bump();
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.multiline_basic().map(Value::String),
+            Some('\'') if self.starts_with("'''") => self.multiline_literal().map(Value::String),
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t') if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Boolean(true))
+            }
+            Some('f') if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Boolean(false))
+            }
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => self.integer(),
+            Some(c) => self.error(format!("unexpected `{}` where a value was expected", c)),
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, ParseError> {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.') {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+
+        let clean = text.replace('_', "");
+        let (negative, digits) = match clean.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, clean.strip_prefix('+').unwrap_or(&clean)),
+        };
+        let parsed = if let Some(octal) = digits.strip_prefix("0o") {
+            i64::from_str_radix(octal, 8)
+        } else if let Some(hex) = digits.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16)
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            i64::from_str_radix(binary, 2)
+        } else {
+            digits.parse::<i64>()
+        };
+
+        match parsed {
+            Ok(n) => Ok(Value::Integer(if negative { -n } else { n })),
+            Err(_) => self.error(format!("unsupported value `{}`", text)),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, ParseError> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('"') => return Ok(out),
+                Some('\\') => out.push(self.escape()?),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn multiline_basic(&mut self) -> Result<String, ParseError> {
+        self.pos += 3;
+        // A newline right after the opening quotes is trimmed
+        if self.peek() == Some('\n') {
+            self.bump();
+        }
+        let mut out = String::new();
+        loop {
+            if self.starts_with("\"\"\"") && self.peek_at(3) != Some('"') {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.bump() {
+                None => return self.error("unterminated multi-line string"),
+                Some('\\') if matches!(self.peek(), Some('\n' | ' ' | '\t' | '\r')) => {
+                    // Line-ending backslash: skip all following whitespace
+                    while matches!(self.peek(), Some('\n' | ' ' | '\t' | '\r')) {
+                        self.bump();
+                    }
+                }
+                Some('\\') => out.push(self.escape()?),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, ParseError> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('\'') => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn multiline_literal(&mut self) -> Result<String, ParseError> {
+        self.pos += 3;
+        if self.peek() == Some('\n') {
+            self.bump();
+        }
+        let mut out = String::new();
+        loop {
+            if self.starts_with("'''") && self.peek_at(3) != Some('\'') {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.bump() {
+                None => return self.error("unterminated multi-line string"),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('e') => '\u{1b}',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(kind @ ('u' | 'U')) => {
+                let len = if kind == 'u' { 4 } else { 8 };
+                let hex: String = (0..len).filter_map(|_| self.bump()).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return self.error(format!("invalid unicode escape `{}`", hex)),
+                }
+            }
+            Some(c) => return self.error(format!("invalid escape `\\{}`", c)),
+            None => return self.error("unterminated string"),
+        };
+        Ok(c)
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.bump();
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return self.error("expected `,` or `]` in array"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, ParseError> {
+        self.bump();
+        let mut table = Table::new();
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(Value::Table(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_spaces();
+            if self.bump() != Some('=') {
+                return self.error("expected `=` after key");
+            }
+            self.skip_spaces();
+            let value = self.value()?;
+            let line = self.line;
+            insert_dotted(&mut table, &key, value)
+                .map_err(|message| ParseError { line, message })?;
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => self.skip_spaces(),
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return self.error("expected `,` or `}` in inline table"),
+            }
+        }
+    }
+}
+
+/// Finds or creates the table at `path`, descending into the last element of arrays of tables.
+fn table_at<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for part in path {
+        if table.get(part).is_none() {
+            table.insert(part, Value::Table(Table::new()));
+        }
+        table = match table.get_mut(part) {
+            Some(Value::Table(next)) => next,
+            Some(Value::Array(items)) => match items.last_mut() {
+                Some(Value::Table(next)) => next,
+                _ => return Err(format!("`{}` is not a table", part)),
+            },
+            _ => return Err(format!("`{}` is not a table", part)),
+        };
+    }
+    Ok(table)
+}
+
+fn push_array_table(root: &mut Table, path: &[String]) -> Result<(), String> {
+    let (last, parents) = path.split_last().expect("keys are never empty");
+    let parent = table_at(root, parents)?;
+    match parent.get_mut(last) {
+        None => {
+            parent.insert(last, Value::Array(vec![Value::Table(Table::new())]));
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            items.push(Value::Table(Table::new()));
+            Ok(())
+        }
+        Some(_) => Err(format!("`{}` is not an array of tables", last)),
+    }
+}
+
+fn insert_dotted(table: &mut Table, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().expect("keys are never empty");
+    let table = table_at(table, parents)?;
+    if table.get(last).is_some() {
+        return Err(format!("duplicate key `{}`", last));
+    }
+    table.insert(last, value);
+    Ok(())
+}
+
+/// Serializes `table` as TOML, with nested tables written as `[sections]`.
+pub fn to_string(table: &Table) -> String {
+    let mut out = String::new();
+    write_table(&mut out, table, &[]);
+    out
+}
+
+fn write_table(out: &mut String, table: &Table, path: &[&str]) {
+    for (key, value) in table.iter() {
+        if !matches!(value, Value::Table(_)) {
+            let _ = writeln!(out, "{} = {}", format_key(key), format_value(value));
+        }
+    }
+    for (key, value) in table.iter() {
+        if let Value::Table(child) = value {
+            let mut child_path = path.to_vec();
+            child_path.push(key);
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let header: Vec<String> = child_path.iter().map(|k| format_key(k)).collect();
+            let _ = writeln!(out, "[{}]", header.join("."));
+            write_table(out, child, &child_path);
+        }
+    }
+}
+
+fn format_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        format_value(&Value::String(key.to_string()))
+    }
+}
+
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => {
+            let mut out = String::from("\"");
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    '\r' => out.push_str("\\r"),
+                    c if c.is_control() => {
+                        let _ = write!(out, "\\u{:04X}", c as u32);
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        Value::Integer(i) => i.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Table(table) => {
+            let items: Vec<String> = table
+                .iter()
+                .map(|(k, v)| format!("{} = {}", format_key(k), format_value(v)))
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! The external tools behind the initializer flags: where they are, which
+//! version is installed, and what provides them when they are missing.
+//!
+//! `makedir doctor` reports on all of them, and every run checks the tools
+//! its plan needs before creating anything, along with any minimum versions
+//! from `[requires]` in the config file or a preset.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::action::Action;
+use crate::plan::{Plan, Step};
+use crate::runner::{self, CommandSpec, RunOptions, Runner, SystemRunner};
+
+/// The program an initializer flag runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tool {
+    pub action: Action,
+    pub program: &'static str,
+    /// The arguments that make it print its version.
+    pub version_args: &'static [&'static str],
+    /// What to install to get it.
+    pub provided_by: &'static str,
+}
+
+/// Every flag that runs an external program, in flag order.
+pub const TOOLS: [Tool; 7] = [
+    Tool {
+        action: Action::Git,
+        program: "git",
+        version_args: &["--version"],
+        provided_by: "the git package",
+    },
+    Tool {
+        action: Action::Go,
+        program: "go",
+        version_args: &["version"],
+        provided_by: "the golang package (https://go.dev/dl)",
+    },
+    Tool {
+        action: Action::Cargo,
+        program: "cargo",
+        version_args: &["--version"],
+        provided_by: "rustup (https://rustup.rs) or the cargo package",
+    },
+    Tool {
+        action: Action::Npm,
+        program: "npm",
+        version_args: &["--version"],
+        provided_by: "the nodejs and npm packages (https://nodejs.org)",
+    },
+    Tool {
+        action: Action::Bun,
+        program: "bun",
+        version_args: &["--version"],
+        provided_by: "bun (https://bun.sh)",
+    },
+    Tool {
+        action: Action::Yarn,
+        program: "yarn",
+        version_args: &["--version"],
+        provided_by: "the yarn package, or `corepack enable` with Node.js",
+    },
+    Tool {
+        action: Action::Pnpm,
+        program: "pnpm",
+        version_args: &["--version"],
+        provided_by: "the pnpm package, or `corepack enable` with Node.js",
+    },
+];
+
+/// How long `--version` may take before a tool is considered broken.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl Tool {
+    pub fn for_action(action: Action) -> Option<&'static Tool> {
+        TOOLS.iter().find(|tool| tool.action == action)
+    }
+
+    pub fn for_program(program: &str) -> Option<&'static Tool> {
+        TOOLS.iter().find(|tool| tool.program == program)
+    }
+
+    /// The first line the tool prints for its version, e.g. `git version 2.43.0`.
+    pub fn version(&self, path: &Path) -> Result<String, String> {
+        version_line(path, self.version_args)
+    }
+
+    /// Why the tool is needed and where to get it, for when it is missing.
+    pub fn missing_message(&self) -> String {
+        format!(
+            "--{} needs `{}`, which is not on PATH. It is provided by {}.",
+            self.action.name(),
+            self.program,
+            self.provided_by
+        )
+    }
+}
+
+/// Runs `path` with `args` and returns the first line it prints.
+fn version_line(path: &Path, args: &[&str]) -> Result<String, String> {
+    let command = CommandSpec::new(&path.to_string_lossy(), args);
+    let options = RunOptions {
+        timeout: Some(VERSION_TIMEOUT),
+        on_line: None,
+    };
+    let output = SystemRunner
+        .run_with(&command, Path::new("."), &options)
+        .map_err(|e| format!("`{}` failed: {}", command, e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) if output.status.success() => Ok(line.to_string()),
+        _ => Err(format!("`{}` printed no version", command)),
+    }
+}
+
+/// Something a `[requires]` entry can name: the tool behind a flag, or the
+/// runtime underneath one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Toolchain {
+    pub name: &'static str,
+    /// The program asked for its version.
+    pub program: &'static str,
+    pub version_args: &'static [&'static str],
+    /// The programs whose use makes the requirement apply.
+    pub used_by: &'static [&'static str],
+}
+
+/// Toolchains that are not a flag's own tool. `npm --version` prints npm's
+/// version, not Node's, and `rust` reads better than `cargo` in a config.
+pub const RUNTIMES: [Toolchain; 2] = [
+    Toolchain {
+        name: "node",
+        program: "node",
+        version_args: &["--version"],
+        used_by: &["npm", "yarn", "pnpm"],
+    },
+    Toolchain {
+        name: "rust",
+        program: "rustc",
+        version_args: &["--version"],
+        used_by: &["cargo"],
+    },
+];
+
+impl Toolchain {
+    pub fn named(name: &str) -> Option<Toolchain> {
+        if let Some(runtime) = RUNTIMES.iter().find(|runtime| runtime.name == name) {
+            return Some(*runtime);
+        }
+        let tool = Tool::for_program(name)?;
+        Some(Toolchain {
+            name: tool.program,
+            program: tool.program,
+            version_args: tool.version_args,
+            used_by: std::slice::from_ref(&tool.program),
+        })
+    }
+
+    /// Every name [`Toolchain::named`] accepts, for error messages.
+    fn names() -> Vec<&'static str> {
+        let tools = TOOLS.iter().map(|tool| tool.program);
+        tools
+            .chain(RUNTIMES.iter().map(|runtime| runtime.name))
+            .collect()
+    }
+
+    /// The installed version, from the first version number its version
+    /// command prints.
+    pub fn version(&self) -> Result<Version, String> {
+        let path = runner::resolve(self.program)
+            .ok_or_else(|| format!("`{}` is not on PATH", self.program))?;
+        let line = version_line(&path, self.version_args)?;
+        Version::find(&line).ok_or_else(|| format!("no version number in `{}`", line))
+    }
+}
+
+/// A dotted version number such as `1.22.1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version(pub Vec<u64>);
+
+impl Version {
+    /// Parses `1.22` or `20.11.1`.
+    pub fn parse(s: &str) -> Result<Version, String> {
+        s.split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .map(Version)
+            .ok_or_else(|| format!("invalid version `{}`, expected e.g. 1.22", s))
+    }
+
+    /// The first version number in a line of `--version` output, so
+    /// `go version go1.22.1 linux/amd64` gives `1.22.1` and `v20.11.1`
+    /// gives `20.11.1`.
+    pub fn find(line: &str) -> Option<Version> {
+        let start = line.find(|c: char| c.is_ascii_digit())?;
+        let rest = &line[start..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        Version::parse(rest[..end].trim_end_matches('.')).ok()
+    }
+
+    /// Compares only as many components as `other` has, treating missing
+    /// ones as 0, so `20.11.1` counts as `20` against a requirement of `20`.
+    fn cmp_to(&self, other: &Version) -> Ordering {
+        let own = (0..other.0.len()).map(|i| self.0.get(i).copied().unwrap_or(0));
+        own.cmp(other.0.iter().copied())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(u64::to_string).collect();
+        f.write_str(&parts.join("."))
+    }
+}
+
+/// How a found version is compared with a required one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    AtLeast,
+    Above,
+    AtMost,
+    Below,
+    Exactly,
+}
+
+impl Comparison {
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        (">=", Comparison::AtLeast),
+        ("<=", Comparison::AtMost),
+        ("==", Comparison::Exactly),
+        (">", Comparison::Above),
+        ("<", Comparison::Below),
+        ("=", Comparison::Exactly),
+    ];
+
+    fn operator(self) -> &'static str {
+        match self {
+            Comparison::AtLeast => ">=",
+            Comparison::Above => ">",
+            Comparison::AtMost => "<=",
+            Comparison::Below => "<",
+            Comparison::Exactly => "=",
+        }
+    }
+}
+
+/// A version constraint on a toolchain, such as `go >=1.22`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub toolchain: Toolchain,
+    pub comparison: Comparison,
+    pub version: Version,
+}
+
+impl Requirement {
+    /// Parses a constraint for the toolchain called `name`. A bare version
+    /// such as `1.22` is a minimum; `>`, `<`, `<=` and `=` are also accepted.
+    pub fn parse(name: &str, constraint: &str) -> Result<Requirement, String> {
+        let toolchain = Toolchain::named(name).ok_or_else(|| {
+            format!(
+                "unknown toolchain `{}`, expected one of {}",
+                name,
+                Toolchain::names().join(", ")
+            )
+        })?;
+        let constraint = constraint.trim();
+        let (comparison, version) = Comparison::OPERATORS
+            .iter()
+            .find_map(|(operator, comparison)| {
+                constraint
+                    .strip_prefix(operator)
+                    .map(|version| (*comparison, version))
+            })
+            .unwrap_or((Comparison::AtLeast, constraint));
+        Ok(Requirement {
+            toolchain,
+            comparison,
+            version: Version::parse(version.trim())?,
+        })
+    }
+
+    pub fn is_met_by(&self, version: &Version) -> bool {
+        let ordering = version.cmp_to(&self.version);
+        match self.comparison {
+            Comparison::AtLeast => ordering != Ordering::Less,
+            Comparison::Above => ordering == Ordering::Greater,
+            Comparison::AtMost => ordering != Ordering::Greater,
+            Comparison::Below => ordering == Ordering::Less,
+            Comparison::Exactly => ordering == Ordering::Equal,
+        }
+    }
+
+    /// The constraint without the toolchain name, e.g. `>=1.22`.
+    pub fn constraint(&self) -> String {
+        format!("{}{}", self.comparison.operator(), self.version)
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.toolchain.name, self.constraint())
+    }
+}
+
+/// Adds `requirement` to `requirements`, replacing any earlier one for the
+/// same toolchain.
+pub(crate) fn add_requirement(requirements: &mut Vec<Requirement>, requirement: Requirement) {
+    requirements.retain(|existing| existing.toolchain.name != requirement.toolchain.name);
+    requirements.push(requirement);
+}
+
+/// What to do when a toolchain does not meet its requirement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Outdated {
+    /// Stop before creating anything, with exit code 3.
+    #[default]
+    Refuse,
+    /// Print a warning and carry on.
+    Warn,
+}
+
+impl Outdated {
+    pub fn parse(s: &str) -> Result<Outdated, String> {
+        match s {
+            "refuse" => Ok(Outdated::Refuse),
+            "warn" => Ok(Outdated::Warn),
+            _ => Err(format!("expected refuse or warn, got `{}`", s)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outdated::Refuse => "refuse",
+            Outdated::Warn => "warn",
+        }
+    }
+}
+
+/// A requirement and the version that was found for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionCheck {
+    pub requirement: Requirement,
+    /// The installed version, or why it could not be told.
+    pub found: Result<Version, String>,
+}
+
+impl VersionCheck {
+    pub fn is_met(&self) -> bool {
+        matches!(&self.found, Ok(version) if self.requirement.is_met_by(version))
+    }
+}
+
+impl fmt::Display for VersionCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.requirement.toolchain.name;
+        let constraint = self.requirement.constraint();
+        match &self.found {
+            Ok(version) if self.is_met() => {
+                write!(f, "{} {} satisfies {}", name, version, constraint)
+            }
+            Ok(version) => write!(
+                f,
+                "{} {} is installed, but {} is required",
+                name, version, constraint
+            ),
+            Err(e) => write!(f, "cannot check {} {}: {}", name, constraint, e),
+        }
+    }
+}
+
+/// Checks the plan's requirements whose toolchain the plan uses.
+pub fn check_versions(plan: &Plan) -> Vec<VersionCheck> {
+    check_versions_with(plan, Toolchain::version)
+}
+
+/// Like [`check_versions`], but finds versions through `version`.
+pub fn check_versions_with(
+    plan: &Plan,
+    version: impl Fn(&Toolchain) -> Result<Version, String>,
+) -> Vec<VersionCheck> {
+    let programs: HashSet<&str> = programs(plan).collect();
+    plan.requires
+        .iter()
+        .filter(|requirement| {
+            let used_by = requirement.toolchain.used_by;
+            used_by.iter().any(|program| programs.contains(program))
+        })
+        .map(|requirement| VersionCheck {
+            requirement: requirement.clone(),
+            found: version(&requirement.toolchain),
+        })
+        .collect()
+}
+
+/// The programs `plan` runs that are not on `PATH`, one message each.
+pub fn check(plan: &Plan) -> Vec<String> {
+    check_with(plan, runner::resolve)
+}
+
+/// Like [`check`], but looks programs up through `resolve`.
+pub fn check_with(plan: &Plan, resolve: impl Fn(&str) -> Option<PathBuf>) -> Vec<String> {
+    let mut checked = HashSet::new();
+    let mut problems = Vec::new();
+    for program in programs(plan) {
+        // Paths with a slash are relative to the new directory, which doesn't exist yet
+        if program.contains('/') || !checked.insert(program) || resolve(program).is_some() {
+            continue;
+        }
+        problems.push(match Tool::for_program(program) {
+            Some(tool) => tool.missing_message(),
+            None => format!("`{}` is not on PATH.", program),
+        });
+    }
+    problems
+}
+
+/// Every program a step of `plan` runs, repeats included.
+fn programs(plan: &Plan) -> impl Iterator<Item = &str> {
+    plan.targets
+        .iter()
+        .flat_map(|target| &target.steps)
+        .filter_map(|step| match step {
+            Step::Run(command) => Some(command.program.as_str()),
+            _ => None,
+        })
+}
@@ -0,0 +1,233 @@
+//! Template directories supplied by the user and applied with `--template <name>`.
+//!
+//! A template is a directory whose file tree, empty directories included, is
+//! copied into every new directory with its permissions. Paths are rendered
+//! with [`crate::template`], so `src/{{ project }}.rs` works as it does in the
+//! built-in files. Only files ending in `.tmpl` have their contents rendered,
+//! and lose the suffix: `README.md.tmpl` can use `{{ author }}`, while a
+//! workflow file using `${{ secrets.TOKEN }}` is copied as it is.
+//!
+//! An optional `makedir.toml` manifest at the top of the template adds flags,
+//! sets permissions and lists commands to run afterwards:
+//!
+//! ```toml
+//! flags = ["git"]
+//! commands = [["npm", "install"], ["git", "add", "."]]
+//!
+//! [permissions]
+//! "bin/dev" = "755"
+//! ```
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::action::Action;
+use crate::config::{self, Config};
+use crate::mode::Mode;
+use crate::runner::CommandSpec;
+use crate::toml;
+
+/// The manifest file, which is read but not copied.
+pub const MANIFEST: &str = "makedir.toml";
+
+/// The suffix of files whose contents are rendered.
+pub const RENDER_SUFFIX: &str = ".tmpl";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserTemplate {
+    pub name: String,
+    /// The template directory the files were read from.
+    pub root: PathBuf,
+    /// Every file in the template except the manifest, sorted by path.
+    pub files: Vec<TemplateFile>,
+    /// Directories with nothing in them, which no file would create.
+    pub empty_dirs: Vec<String>,
+    /// Built-in flags the template turns on.
+    pub flags: Vec<Action>,
+    /// Modes for files or directories, by path relative to the new directory.
+    pub permissions: Vec<(String, Mode)>,
+    /// Commands run in the new directory once the files are in place.
+    pub commands: Vec<CommandSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateFile {
+    /// Path relative to the template root, `/`-separated, before rendering.
+    pub path: String,
+    /// The file's text when it ends in [`RENDER_SUFFIX`] and is rendered.
+    pub text: Option<String>,
+    /// The permission bits of the source file.
+    pub mode: u32,
+}
+
+impl TemplateFile {
+    /// The path to write to, before rendering: rendered files lose their
+    /// [`RENDER_SUFFIX`].
+    pub fn target(&self) -> &str {
+        match &self.text {
+            Some(_) => self.path.strip_suffix(RENDER_SUFFIX).unwrap_or(&self.path),
+            None => &self.path,
+        }
+    }
+}
+
+impl UserTemplate {
+    /// Where templates are looked for, in order: `.makedir/templates` in the
+    /// current directory or its nearest ancestor that has one, then
+    /// `~/.config/makedir/templates`.
+    pub fn search_dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Ok(cwd) = env::current_dir() {
+            if let Some(local) = cwd
+                .ancestors()
+                .map(|dir| dir.join(".makedir").join("templates"))
+                .find(|dir| dir.is_dir())
+            {
+                dirs.push(local);
+            }
+        }
+        if let Some(dir) = Config::dir() {
+            dirs.push(dir.join("templates"));
+        }
+        dirs
+    }
+
+    /// Finds and loads the template called `name` from [`search_dirs`](Self::search_dirs).
+    pub fn find(name: &str) -> Result<UserTemplate, String> {
+        if name.is_empty() || name.contains('/') || name.starts_with('.') {
+            return Err(format!("invalid template name `{}`", name));
+        }
+        let dirs = UserTemplate::search_dirs();
+        match dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|dir| dir.is_dir())
+        {
+            Some(root) => UserTemplate::load(name, &root),
+            None => {
+                let searched: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+                Err(format!(
+                    "unknown template `{}` (looked in {})",
+                    name,
+                    searched.join(", ")
+                ))
+            }
+        }
+    }
+
+    /// Reads the template in `root`.
+    pub fn load(name: &str, root: &Path) -> Result<UserTemplate, String> {
+        let mut template = UserTemplate {
+            name: name.to_string(),
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        let manifest = root.join(MANIFEST);
+        if manifest.is_file() {
+            let text = fs::read_to_string(&manifest)
+                .map_err(|e| format!("failed to read {}: {}", manifest.display(), e))?;
+            template
+                .read_manifest(&text)
+                .map_err(|e| format!("invalid {}: {}", manifest.display(), e))?;
+        }
+
+        collect_files(root, "", &mut template)?;
+        template.files.sort_by(|a, b| a.path.cmp(&b.path));
+        template.empty_dirs.sort();
+        Ok(template)
+    }
+
+    fn read_manifest(&mut self, text: &str) -> Result<(), String> {
+        let root = toml::parse(text).map_err(|e| e.to_string())?;
+        for (key, value) in root.iter() {
+            match key {
+                "flags" => self.flags = config::flag_list("flags", value)?,
+                "permissions" => {
+                    let table = value
+                        .as_table()
+                        .ok_or_else(|| "`permissions` must be a table".to_string())?;
+                    for (path, mode) in table.iter() {
+                        let key = format!("permissions.{}", path);
+                        self.permissions
+                            .push((path.to_string(), config::mode_value(&key, mode)?));
+                    }
+                }
+                "commands" => {
+                    let commands = value
+                        .as_array()
+                        .ok_or_else(|| "`commands` must be an array".to_string())?;
+                    for command in commands {
+                        self.commands.push(command_value(command)?);
+                    }
+                }
+                _ => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a command written as an argv array, e.g. `["npm", "install"]`.
+fn command_value(value: &toml::Value) -> Result<CommandSpec, String> {
+    let error = || "`commands` entries must be non-empty arrays of strings".to_string();
+    let argv = value
+        .as_array()
+        .ok_or_else(error)?
+        .iter()
+        .map(|arg| arg.as_str().ok_or_else(error))
+        .collect::<Result<Vec<&str>, String>>()?;
+    match argv.split_first() {
+        Some((program, args)) => Ok(CommandSpec::new(program, args)),
+        None => Err(error()),
+    }
+}
+
+fn collect_files(dir: &Path, prefix: &str, template: &mut UserTemplate) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("failed to read {}: {}", dir.display(), e))?;
+    let mut empty = true;
+    for entry in entries {
+        empty = false;
+        let entry = entry.map_err(|e| format!("failed to read {}: {}", dir.display(), e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if path == MANIFEST {
+            continue;
+        }
+
+        let full = entry.path();
+        let metadata =
+            fs::metadata(&full).map_err(|e| format!("failed to read {}: {}", full.display(), e))?;
+        if metadata.is_dir() {
+            collect_files(&full, &path, template)?;
+            continue;
+        }
+        let text =
+            if path.ends_with(RENDER_SUFFIX) {
+                let bytes = fs::read(&full)
+                    .map_err(|e| format!("failed to read {}: {}", full.display(), e))?;
+                Some(String::from_utf8(bytes).map_err(|_| {
+                    format!("{} is not UTF-8, so it can't be rendered", full.display())
+                })?)
+            } else {
+                None
+            };
+        template.files.push(TemplateFile {
+            path,
+            text,
+            mode: metadata.permissions().mode() & 0o7777,
+        });
+    }
+    // The root is the new directory itself, which exists anyway
+    if empty && !prefix.is_empty() {
+        template.empty_dirs.push(prefix.to_string());
+    }
+    Ok(())
+}
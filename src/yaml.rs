@@ -0,0 +1,539 @@
+//! A small YAML reader producing the same values as [`crate::toml`].
+//!
+//! It covers what spec files need: block mappings and sequences, flow `[...]`
+//! and `{...}` collections on one line, plain, single- and double-quoted
+//! scalars, `|` and `>` block scalars, and comments. Plain scalars other than
+//! `true`, `false` and `null` are read as strings, so `mode: 755` stays
+//! `"755"`. `null` and empty values become empty tables. Anchors, tags and
+//! multi-document streams are not supported.
+
+use crate::toml::{ParseError, Table, Value};
+
+/// Parses a document whose top level is a mapping.
+pub fn parse(input: &str) -> Result<Table, ParseError> {
+    let mut parser = Parser {
+        lines: input
+            .lines()
+            .enumerate()
+            .map(|(i, raw)| Line::new(i + 1, raw))
+            .collect(),
+        pos: 0,
+    };
+    parser.document()
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    /// The line without its indentation.
+    text: String,
+}
+
+impl Line {
+    fn new(number: usize, raw: &str) -> Line {
+        let text = raw.trim_start_matches(' ');
+        Line {
+            number,
+            indent: raw.len() - text.len(),
+            text: text.trim_end_matches('\r').to_string(),
+        }
+    }
+
+    /// Whether the line holds nothing but whitespace or a comment.
+    fn is_blank(&self) -> bool {
+        let text = self.text.trim();
+        text.is_empty() || text.starts_with('#')
+    }
+
+    /// The text with any trailing comment removed.
+    fn content(&self) -> &str {
+        strip_comment(&self.text)
+    }
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, line: usize, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line,
+            message: message.into(),
+        })
+    }
+
+    /// Moves past blank lines, comments and document markers, returning the next line.
+    fn next_content(&mut self) -> Option<Line> {
+        while let Some(line) = self.lines.get(self.pos) {
+            let marker = line.indent == 0 && (line.content() == "---" || line.content() == "...");
+            if !line.is_blank() && !marker {
+                return Some(line.clone());
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    fn document(&mut self) -> Result<Table, ParseError> {
+        let first = match self.next_content() {
+            Some(line) => line,
+            None => return Ok(Table::new()),
+        };
+        let value = self.block(first.indent)?;
+        if let Some(line) = self.next_content() {
+            return self.error(line.number, "unexpected indentation");
+        }
+        match value {
+            Value::Table(table) => Ok(table),
+            other => self.error(
+                first.number,
+                format!(
+                    "expected a mapping at the top level, found {}",
+                    other.type_name()
+                ),
+            ),
+        }
+    }
+
+    /// Parses the node starting at the current line, which is indented by `indent`.
+    fn block(&mut self, indent: usize) -> Result<Value, ParseError> {
+        let line = self.next_content().expect("caller checked for a line");
+        if line.text.starts_with('\t') {
+            return self.error(line.number, "tabs are not allowed for indentation");
+        }
+        let text = line.content();
+        if is_sequence_item(text) {
+            self.sequence(indent)
+        } else if split_key(text).is_some() {
+            self.mapping(indent)
+        } else {
+            self.pos += 1;
+            inline(text, line.number)
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, ParseError> {
+        let mut table = Table::new();
+        while let Some(line) = self.next_content() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return self.error(line.number, "unexpected indentation");
+            }
+            let (key, rest) = match split_key(line.content()) {
+                Some(pair) => pair,
+                None => return self.error(line.number, "expected `key: value`"),
+            };
+            let key = key_text(key, line.number)?;
+            if table.get(&key).is_some() {
+                return self.error(line.number, format!("duplicate key `{}`", key));
+            }
+            self.pos += 1;
+            let value = self.value_after(rest.to_string(), &line)?;
+            table.insert(&key, value);
+        }
+        Ok(Value::Table(table))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, ParseError> {
+        let mut items = Vec::new();
+        while let Some(line) = self.next_content() {
+            if line.indent < indent || !is_sequence_item(line.content()) {
+                break;
+            }
+            if line.indent > indent {
+                return self.error(line.number, "unexpected indentation");
+            }
+            let item = line.text[1..].trim_start();
+            let offset = indent + line.text.len() - item.len();
+            let content = strip_comment(item);
+
+            if content.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, indent)?);
+            } else if is_sequence_item(content) || split_key(content).is_some() {
+                // `- key: value` starts a mapping indented to where `key` is
+                self.lines[self.pos] = Line {
+                    number: line.number,
+                    indent: offset,
+                    text: item.to_string(),
+                };
+                items.push(self.block(offset)?);
+            } else if content.starts_with('|') || content.starts_with('>') {
+                self.pos += 1;
+                items.push(Value::String(self.block_scalar(
+                    content,
+                    indent,
+                    line.number,
+                )?));
+            } else {
+                self.pos += 1;
+                items.push(inline(content, line.number)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// The value of a mapping entry, given the text after its colon.
+    fn value_after(&mut self, rest: String, line: &Line) -> Result<Value, ParseError> {
+        if rest.is_empty() {
+            self.nested(line.indent, line.indent + 1)
+        } else if rest.starts_with('|') || rest.starts_with('>') {
+            Ok(Value::String(self.block_scalar(
+                &rest,
+                line.indent,
+                line.number,
+            )?))
+        } else {
+            inline(&rest, line.number)
+        }
+    }
+
+    /// A node on the following lines, or an empty table when there is none.
+    ///
+    /// A sequence may sit at the same indentation as its key, as in
+    /// `dirs:\n- a\n- b`; anything else must be indented further.
+    fn nested(&mut self, parent: usize, min_indent: usize) -> Result<Value, ParseError> {
+        match self.next_content() {
+            Some(next) if next.indent >= min_indent => self.block(next.indent),
+            Some(next) if next.indent == parent && is_sequence_item(next.content()) => {
+                self.sequence(parent)
+            }
+            _ => Ok(Value::Table(Table::new())),
+        }
+    }
+
+    /// Reads a `|` or `>` block scalar whose header is on the previous line.
+    fn block_scalar(
+        &mut self,
+        header: &str,
+        parent: usize,
+        number: usize,
+    ) -> Result<String, ParseError> {
+        let folded = header.starts_with('>');
+        let mut chomp = ' ';
+        let mut explicit = None;
+        for c in header[1..].trim().chars() {
+            match c {
+                '-' | '+' => chomp = c,
+                '1'..='9' => explicit = c.to_digit(10).map(|d| parent + d as usize),
+                _ => return self.error(number, "invalid block scalar header"),
+            }
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut indent = explicit;
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.text.trim().is_empty() {
+                lines.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            let content_indent = *indent.get_or_insert(line.indent);
+            if line.indent < content_indent || line.indent <= parent {
+                break;
+            }
+            lines.push(format!(
+                "{}{}",
+                " ".repeat(line.indent - content_indent),
+                line.text
+            ));
+            self.pos += 1;
+        }
+
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        let body = &lines[..lines.len() - trailing];
+        let mut text = if folded { fold(body) } else { body.join("\n") };
+        if !body.is_empty() {
+            match chomp {
+                '-' => {}
+                '+' => text.push_str(&"\n".repeat(trailing + 1)),
+                _ => text.push('\n'),
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// Joins the lines of a `>` scalar: single line breaks become spaces, while
+/// blank lines and more-indented lines keep their line breaks.
+fn fold(lines: &[String]) -> String {
+    let mut text = String::new();
+    let mut previous_literal = true;
+    for (i, line) in lines.iter().enumerate() {
+        let literal = line.is_empty() || line.starts_with(' ');
+        if i > 0 {
+            if literal || previous_literal {
+                text.push('\n');
+            } else {
+                text.push(' ');
+            }
+        }
+        text.push_str(line);
+        previous_literal = literal;
+    }
+    text
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits `key: value` into the key and the trimmed value.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+    let key_end = if text.starts_with(['"', '\'']) {
+        let quote = text.chars().next()?;
+        let mut escaped = false;
+        let close = text[1..].char_indices().find(|&(_, c)| {
+            let found = c == quote && !escaped;
+            escaped = quote == '"' && c == '\\' && !escaped;
+            found
+        })?;
+        close.0 + 2
+    } else {
+        let colon = text
+            .char_indices()
+            .find(|&(i, c)| c == ':' && text[i + 1..].chars().next().is_none_or(|n| n == ' '))?;
+        colon.0
+    };
+    let rest = text[key_end..].strip_prefix(':')?;
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((text[..key_end].trim_end(), rest.trim()))
+}
+
+fn key_text(key: &str, line: usize) -> Result<String, ParseError> {
+    match inline(key, line)? {
+        Value::String(s) => Ok(s),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => Err(ParseError {
+            line,
+            message: format!("invalid key `{}`", key),
+        }),
+    }
+}
+
+/// Cuts a `#` comment that starts outside quotes after whitespace.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return text[..i].trim_end(),
+            None => {}
+        }
+        previous = c;
+    }
+    text.trim_end()
+}
+
+/// Parses a value written on a single line.
+fn inline(text: &str, line: usize) -> Result<Value, ParseError> {
+    let mut flow = Flow {
+        chars: text.trim().chars().collect(),
+        pos: 0,
+        line,
+    };
+    let value = flow.value(false)?;
+    flow.skip_spaces();
+    if flow.pos < flow.chars.len() {
+        return flow.error("unexpected text after value");
+    }
+    Ok(value)
+}
+
+/// A parser for flow collections and quoted scalars within one line.
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Flow {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+    }
+
+    /// Parses a value; inside a flow collection, plain scalars end at `,`, `]` and `}`.
+    fn value(&mut self, in_flow: bool) -> Result<Value, ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => self.sequence(),
+            Some('{') => self.mapping(),
+            Some('"') => self.double_quoted().map(Value::String),
+            Some('\'') => self.single_quoted().map(Value::String),
+            Some('&' | '*' | '!') => self.error("anchors, aliases and tags are not supported"),
+            _ => Ok(self.plain(in_flow)),
+        }
+    }
+
+    fn plain(&mut self, in_flow: bool) -> Value {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if in_flow && (c == ',' || c == ']' || c == '}') {
+                break;
+            }
+            if in_flow && c == ':' && matches!(self.chars.get(self.pos + 1), None | Some(' ')) {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.trim() {
+            "true" | "True" | "TRUE" => Value::Boolean(true),
+            "false" | "False" | "FALSE" => Value::Boolean(false),
+            "" | "~" | "null" | "Null" | "NULL" => Value::Table(Table::new()),
+            text => Value::String(text.to_string()),
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                None => return self.error("unterminated `[`"),
+                _ => {}
+            }
+            items.push(self.value(true)?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return self.error("expected `,` or `]`"),
+            }
+        }
+    }
+
+    fn mapping(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut table = Table::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Table(table));
+                }
+                None => return self.error("unterminated `{`"),
+                _ => {}
+            }
+            let key = match self.value(true)? {
+                Value::String(key) => key,
+                _ => return self.error("expected a key"),
+            };
+            self.skip_spaces();
+            let value = if self.peek() == Some(':') {
+                self.pos += 1;
+                self.value(true)?
+            } else {
+                Value::Table(Table::new())
+            };
+            if table.get(&key).is_some() {
+                return self.error(format!("duplicate key `{}`", key));
+            }
+            table.insert(&key, value);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return self.error("expected `,` or `}`"),
+            }
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<String, ParseError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') if self.chars.get(self.pos + 1) == Some(&'\'') => {
+                    out.push('\'');
+                    self.pos += 2;
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+                None => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, ParseError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return self.error("unterminated string"),
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self.peek();
+                    self.pos += 1;
+                    match escape {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some('r') => out.push('\r'),
+                        Some('0') => out.push('\0'),
+                        Some(c @ ('"' | '\\' | '/' | ' ')) => out.push(c),
+                        Some(kind @ ('x' | 'u' | 'U')) => {
+                            let len = match kind {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let hex: String = self.chars.iter().skip(self.pos).take(len).collect();
+                            self.pos += len;
+                            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                Some(c) if hex.len() == len => out.push(c),
+                                _ => return self.error("invalid escape in string"),
+                            }
+                        }
+                        _ => return self.error("invalid escape in string"),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use makedir::acl::{Acl, Tag};
+use makedir::cli::parse_args;
+use makedir::dry_run::describe;
+use makedir::mode::Mode;
+use makedir::{execute, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acl(entries: &str) -> Acl {
+        Acl::parse(entries).unwrap().0
+    }
+
+    /// Whether the filesystem holding temporary directories takes ACLs.
+    fn supported(dir: &std::path::Path) -> bool {
+        acl("u::rwx").apply(dir, false).is_ok()
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let (access, default) = Acl::parse("g:1234:rwx, u::rw-,o::5,m:r,d:group:0:r-x").unwrap();
+        assert_eq!(access.to_string(), "g:1234:rwx,u::rw-,o::r-x,m::r--");
+        assert_eq!(access.entries[0].perms, 0o7);
+        assert!(matches!(&access.entries[0].tag, Tag::Group(id) if id.id == 1234));
+        assert_eq!(default.to_string(), "g:0:r-x");
+
+        let named = acl("user:root:rx");
+        assert!(matches!(&named.entries[0].tag, Tag::User(id) if id.id == 0));
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        assert!(Acl::parse("g:devs").unwrap_err().contains("type:qualifier"));
+        assert!(Acl::parse("x::rwx")
+            .unwrap_err()
+            .contains("unknown type `x`"));
+        assert!(Acl::parse("g::rwz")
+            .unwrap_err()
+            .contains("unknown permission `z`"));
+        assert!(Acl::parse("o:root:r").is_err());
+        assert!(Acl::parse("g:no-such-group-here:rwx")
+            .unwrap_err()
+            .contains("unknown group"));
+        assert!(Acl::parse("g:0:").is_err());
+    }
+
+    #[test]
+    fn test_cli_options() {
+        let parsed = parse_args(&args(&[
+            "team",
+            "--acl",
+            "g:0:rwx,d:g:0:rwx",
+            "--default-acl",
+            "u:0:rx",
+        ]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.acl.to_string(), "g:0:rwx");
+        assert_eq!(parsed.request.default_acl.to_string(), "g:0:rwx,u:0:r-x");
+
+        let parsed = parse_args(&args(&["team", "--acl", "g:0:rwq"]));
+        assert!(parsed.errors[0].starts_with("--acl: invalid entry `g:0:rwq`"));
+        let parsed = parse_args(&args(&["team", "--default-acl"]));
+        assert!(!parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_default_acl_is_inherited() {
+        let temp_dir = tempdir().unwrap();
+        if !supported(temp_dir.path()) {
+            return;
+        }
+        let dir = temp_dir.path().join("team");
+        let request = CreateRequest {
+            dirs: vec![dir.clone()],
+            mode: Some(Mode::Octal(0o750)),
+            acl: acl("g:1234:rwx"),
+            default_acl: acl("g:1234:rwx"),
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+
+        let access = Acl::read(&dir, false).unwrap().unwrap();
+        assert_eq!(access.to_string(), "u::rwx,g::r-x,g:1234:rwx,m::rwx,o::---");
+        // The group bits of the mode now show the mask
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o770);
+
+        let default = Acl::read(&dir, true).unwrap().unwrap();
+        assert_eq!(
+            default.to_string(),
+            "u::rwx,g::r-x,g:1234:rwx,m::rwx,o::---"
+        );
+        fs::create_dir(dir.join("sub")).unwrap();
+        let inherited = Acl::read(&dir.join("sub"), false).unwrap().unwrap();
+        assert!(inherited.to_string().contains("g:1234:rwx"));
+    }
+
+    #[test]
+    fn test_entries_merge_into_existing_acl() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        if !supported(dir) {
+            return;
+        }
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).unwrap();
+        acl("g:1234:r-x").apply(dir, false).unwrap();
+        acl("g:1234:rwx,u:0:r").apply(dir, false).unwrap();
+        assert_eq!(
+            Acl::read(dir, false).unwrap().unwrap().to_string(),
+            "u::rwx,u:0:r--,g::---,g:1234:rwx,m::rwx,o::---"
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let request = CreateRequest {
+            dirs: vec!["team".into()],
+            acl: acl("g:1234:rwx"),
+            default_acl: acl("g:1234:rwx,o::-"),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe(&Plan::new(&request).unwrap()),
+            vec![
+                "create directory team",
+                "set ACL g:1234:rwx on team",
+                "set default ACL g:1234:rwx,o::--- on team",
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+
+use makedir::mode::Mode;
+use makedir::{execute, CommandSpec, Plan, Step, Target};
+use tempfile::tempdir;
+
+use common::sh;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atomic_plan(target: Target) -> Plan {
+        Plan {
+            targets: vec![target],
+            atomic: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_failed_step_removes_created_directories() {
+        let temp_dir = tempdir().unwrap();
+        let parent = temp_dir.path().join("parent");
+        let test_dir = parent.join("child");
+
+        let report = execute(&atomic_plan(Target {
+            dir: test_dir.clone(),
+            mode: Some(Mode::Octal(0o750)),
+            steps: vec![
+                Step::WriteFile {
+                    name: "README.md".to_string(),
+                    contents: "hello".to_string(),
+                },
+                sh("mkdir .cache && false"),
+            ],
+        }));
+
+        assert!(!report.is_success());
+        assert!(report.targets[0].rolled_back);
+        assert!(!report.targets[0].created);
+        assert!(!parent.exists(), "Created parents should be removed too");
+        assert!(temp_dir.path().exists());
+    }
+
+    #[test]
+    fn test_existing_directory_is_kept_and_restored() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("existing");
+        fs::create_dir(&test_dir).unwrap();
+        fs::write(test_dir.join("README.md"), "original").unwrap();
+        fs::write(test_dir.join("notes.txt"), "keep me").unwrap();
+
+        let report = execute(&atomic_plan(Target {
+            dir: test_dir.clone(),
+            mode: None,
+            steps: vec![
+                Step::WriteFile {
+                    name: "README.md".to_string(),
+                    contents: "generated".to_string(),
+                },
+                sh("touch new.txt && false"),
+            ],
+        }));
+
+        assert!(report.targets[0].rolled_back);
+        assert!(
+            test_dir.exists(),
+            "Existing directory must never be removed"
+        );
+        assert_eq!(
+            fs::read_to_string(test_dir.join("README.md")).unwrap(),
+            "original"
+        );
+        assert!(test_dir.join("notes.txt").exists());
+        assert!(!test_dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_steps_after_failure_are_skipped() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("skipped");
+        let marker = temp_dir.path().join("marker");
+
+        execute(&atomic_plan(Target {
+            dir: test_dir.clone(),
+            mode: None,
+            steps: vec![
+                Step::Run(CommandSpec::new("false", &[])),
+                Step::Run(CommandSpec::new("touch", &[marker.to_str().unwrap()])),
+            ],
+        }));
+
+        assert!(!test_dir.exists());
+        assert!(!marker.exists(), "Steps after a failure should not run");
+    }
+
+    #[test]
+    fn test_non_atomic_run_keeps_partial_work() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("partial");
+
+        let report = execute(&Plan {
+            targets: vec![Target {
+                dir: test_dir.clone(),
+                mode: None,
+                steps: vec![
+                    Step::Run(CommandSpec::new("false", &[])),
+                    Step::WriteFile {
+                        name: "LICENSE".to_string(),
+                        contents: "MIT".to_string(),
+                    },
+                ],
+            }],
+            ..Default::default()
+        });
+
+        assert!(!report.targets[0].rolled_back);
+        assert!(test_dir.join("LICENSE").exists());
+    }
+
+    #[test]
+    fn test_atomic_flag_rolls_back_failed_initializer() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("atomic_cli");
+
+        // A go that is installed but fails, ahead of any real one on PATH
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fs::write(bin.join("go"), "#!/bin/sh\nexit 1\n").unwrap();
+        fs::set_permissions(bin.join("go"), fs::Permissions::from_mode(0o755)).unwrap();
+        let path = format!("{}:{}", bin.display(), env::var("PATH").unwrap_or_default());
+
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("--")
+            .arg(test_dir.to_str().unwrap())
+            .arg("--readme")
+            .arg("--go")
+            .arg("--atomic")
+            .env("PATH", path)
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Rolling back"));
+        assert!(!test_dir.exists(), "Directory should be rolled back");
+    }
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use makedir::cli::{self, parse_args};
+use makedir::reporter::{ColorChoice, Painter, Palette, Role};
+use makedir::{config, Config};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled(choice: ColorChoice, terminal: bool, env: &[(&str, &str)]) -> bool {
+        choice.enabled(terminal, |name| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    /// Runs makedir with stdout and stderr piped, so never on a terminal.
+    fn run(extra: &[&str], env: &[(&str, &str)]) -> Output {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("a");
+        let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let mut command = Command::new("cargo");
+        command
+            .arg("run")
+            .arg("--quiet")
+            .arg("--manifest-path")
+            .arg(&manifest)
+            .arg("--")
+            .arg(&dir)
+            .arg(&dir)
+            .args(extra)
+            .env("XDG_CONFIG_HOME", temp_dir.path())
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR_FORCE");
+        for name in config::ENV_VARS {
+            command.env_remove(name);
+        }
+        command.envs(env.iter().copied());
+        command.output().unwrap()
+    }
+
+    fn has_escapes(output: &Output) -> bool {
+        output.stdout.contains(&0x1b) || output.stderr.contains(&0x1b)
+    }
+
+    #[test]
+    fn test_auto_detection() {
+        assert!(enabled(ColorChoice::Auto, true, &[]));
+        assert!(!enabled(ColorChoice::Auto, false, &[]));
+        assert!(!enabled(ColorChoice::Auto, true, &[("NO_COLOR", "1")]));
+        // An empty NO_COLOR does not count
+        assert!(enabled(ColorChoice::Auto, true, &[("NO_COLOR", "")]));
+        assert!(enabled(
+            ColorChoice::Auto,
+            false,
+            &[("CLICOLOR_FORCE", "1")]
+        ));
+        assert!(!enabled(
+            ColorChoice::Auto,
+            true,
+            &[("CLICOLOR_FORCE", "0")]
+        ));
+        assert!(!enabled(
+            ColorChoice::Auto,
+            false,
+            &[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]
+        ));
+    }
+
+    #[test]
+    fn test_explicit_choice_wins() {
+        assert!(enabled(ColorChoice::Always, false, &[("NO_COLOR", "1")]));
+        assert!(!enabled(
+            ColorChoice::Never,
+            true,
+            &[("CLICOLOR_FORCE", "1")]
+        ));
+        assert_eq!(ColorChoice::parse("always"), Ok(ColorChoice::Always));
+        assert!(ColorChoice::parse("sometimes")
+            .unwrap_err()
+            .contains("expected auto, always or never"));
+    }
+
+    #[test]
+    fn test_palette() {
+        let mut palette = Palette::default();
+        palette.apply("error=1;35:highlight=4;36").unwrap();
+        let painter = Painter {
+            color: true,
+            palette,
+        };
+        assert_eq!(painter.paint(Role::Error, "x"), "\x1b[1;35mx\x1b[0m");
+        assert_eq!(painter.paint(Role::Notice, "x"), "\x1b[1;33mx\x1b[0m");
+        assert_eq!(
+            painter.line(Role::Highlight, "head", "rest"),
+            "\x1b[4;36mhead\x1b[0m rest"
+        );
+
+        let plain = Painter::default();
+        assert_eq!(plain.line(Role::Error, "Failed:", "why"), "Failed: why");
+        assert_eq!(plain.line(Role::Error, "Failed", ""), "Failed");
+
+        let mut palette = Palette::default();
+        assert!(palette
+            .apply("fatal=31")
+            .unwrap_err()
+            .contains("unknown color role"));
+        assert!(palette
+            .apply("error=red")
+            .unwrap_err()
+            .contains("SGR codes"));
+        assert!(palette.apply("error").is_err());
+    }
+
+    #[test]
+    fn test_usage_colors() {
+        let plain = cli::usage(&Painter::default());
+        assert_eq!(plain, cli::USAGE);
+        assert!(!cli::USAGE.contains('\x1b'));
+
+        let colored = cli::usage(&Painter {
+            color: true,
+            palette: Palette::default(),
+        });
+        assert!(colored.starts_with("\x1b[1;33mUsage:\x1b[0m makedir"));
+        assert!(colored.contains("    \x1b[32m--git,     -g\x1b[0m         Initialize"));
+        assert!(colored.contains("\x1b[32m--license, -l\x1b[0m         Generate"));
+        assert!(colored.contains("\x1b[32m--preset, @\x1b[0m<name>"));
+        assert!(colored.contains("               \x1b[32m-###\x1b[0m       Set"));
+        assert!(colored.contains("\n    0  Success."));
+    }
+
+    #[test]
+    fn test_config_and_environment() {
+        let config =
+            Config::from_toml("[defaults]\ncolor = \"never\"\n\n[colors]\nsuccess = \"32\"\n")
+                .unwrap();
+        assert_eq!(config.color, Some(ColorChoice::Never));
+        assert_eq!(config.palette.success, "32");
+        assert_eq!(config.palette.error, Palette::default().error);
+        let toml = config.to_toml();
+        assert!(toml.contains("color = \"never\""));
+        assert!(toml.contains("[colors]\nsuccess = \"32\"\n"));
+        assert!(!toml.contains("error ="));
+
+        assert!(Config::from_toml("[colors]\nwarning = \"33\"\n").is_err());
+        assert!(Config::from_toml("[defaults]\ncolor = \"yes\"\n").is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_env(|name| match name {
+                "MAKEDIR_COLOR" => Some("always".to_string()),
+                "MAKEDIR_COLORS" => Some("notice=35".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config.color, Some(ColorChoice::Always));
+        assert_eq!(config.palette.notice, "35");
+    }
+
+    #[test]
+    fn test_cli_option() {
+        let parsed = parse_args(&args(&["a", "--color=never"]));
+        assert_eq!(parsed.request.color, ColorChoice::Never);
+        let parsed = parse_args(&args(&["a", "--color", "always"]));
+        assert_eq!(parsed.request.color, ColorChoice::Always);
+        let parsed = parse_args(&args(&["a"]));
+        assert_eq!(parsed.request.color, ColorChoice::Auto);
+        let parsed = parse_args(&args(&["a", "--color", "rainbow"]));
+        assert!(parsed.errors[0].starts_with("--color:"));
+    }
+
+    #[test]
+    fn test_piped_output_is_plain() {
+        // The second mention of the directory reports that it already exists
+        let output = run(&[], &[]);
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Directory already exists:"));
+        assert!(!has_escapes(&output));
+
+        let output = run(&["--bogus"], &[]);
+        assert!(!output.stderr.is_empty());
+        assert!(!has_escapes(&output));
+    }
+
+    #[test]
+    fn test_forced_colors() {
+        let output = run(&[], &[("CLICOLOR_FORCE", "1")]);
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .contains("\x1b[1;33mDirectory already exists:\x1b[0m"));
+
+        let output = run(&["--color=never"], &[("CLICOLOR_FORCE", "1")]);
+        assert!(!has_escapes(&output));
+
+        let output = run(
+            &["--color=always"],
+            &[("NO_COLOR", "1"), ("MAKEDIR_COLORS", "notice=35")],
+        );
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .contains("\x1b[35mDirectory already exists:\x1b[0m"));
+    }
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the integration tests.
+
+// Each test file is its own crate and uses only some of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use makedir::{config, CommandSpec, Step};
+
+pub fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// A step running `script` with `sh -c`.
+pub fn sh(script: &str) -> Step {
+    Step::Run(CommandSpec::new("sh", &["-c", script]))
+}
+
+/// The permission bits of `path`, including setuid, setgid and sticky.
+pub fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+/// The makedir binary, reading its config from `config_home` and ignoring
+/// any `MAKEDIR_*` variables set where the tests run.
+pub fn makedir(config_home: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_makedir"));
+    command.env("XDG_CONFIG_HOME", config_home);
+    for name in config::ENV_VARS {
+        command.env_remove(name);
+    }
+    command
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use makedir::cli::parse_args_with_config;
+use makedir::mode::Mode;
+use makedir::reporter::Verbosity;
+use makedir::toml::{self, Value};
+use makedir::{Action, CommandSpec, Config, Plan, Step};
+use tempfile::tempdir;
+
+use common::{args, makedir};
+
+const SAMPLE: &str = r#"
+# Applied to every directory
+[defaults]
+flags = ["git", "--readme"]
+mode = "750"
+verbose = true
+license = "MIT"
+
+[author]
+name = "Ada Lovelace"
+email = "ada@example.com"
+
+[go]
+module_prefix = "github.com/ada"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs makedir with its config directory pointed at `config_home`.
+    fn run_makedir(config_home: &Path, envs: &[(&str, &str)], args: &[&str]) -> Output {
+        makedir(config_home)
+            .args(args)
+            .envs(envs.iter().copied())
+            .output()
+            .unwrap()
+    }
+
+    fn write_config(config_home: &Path, text: &str) {
+        fs::create_dir_all(config_home.join("makedir")).unwrap();
+        fs::write(config_home.join("makedir/config.toml"), text).unwrap();
+    }
+
+    #[test]
+    fn test_toml_subset() {
+        let table = toml::parse(
+            r#"
+            title = "a \"quoted\" \u00e9"  # trailing comment
+            literal = 'C:\path'
+            mode = 0o755
+            list = [
+                "one",
+                "two",  # comment inside array
+            ]
+            inline = { a = 1, b.c = true }
+            [server.http]
+            port = 8080
+            [[item]]
+            name = "first"
+            [[item]]
+            name = "second"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(table.get("title").unwrap().as_str(), Some("a \"quoted\" é"));
+        assert_eq!(table.get("literal").unwrap().as_str(), Some(r"C:\path"));
+        assert_eq!(table.get("mode").unwrap().as_integer(), Some(0o755));
+        assert_eq!(table.get("list").unwrap().as_array().unwrap().len(), 2);
+        let inline = table.get("inline").unwrap().as_table().unwrap();
+        let b = inline.get("b").unwrap().as_table().unwrap();
+        assert_eq!(b.get("c"), Some(&Value::Boolean(true)));
+        let server = table.get("server").unwrap().as_table().unwrap();
+        let http = server.get("http").unwrap().as_table().unwrap();
+        assert_eq!(http.get("port").unwrap().as_integer(), Some(8080));
+        let items = table.get("item").unwrap().as_array().unwrap();
+        assert_eq!(
+            items[1].as_table().unwrap().get("name").unwrap().as_str(),
+            Some("second")
+        );
+    }
+
+    #[test]
+    fn test_toml_errors_report_line() {
+        let err = toml::parse("a = 1\nb = \n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = toml::parse("a = 1\na = 2\n").unwrap_err();
+        assert!(err.message.contains("duplicate key"));
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let table = toml::parse(SAMPLE).unwrap();
+        let written = toml::to_string(&table);
+        assert_eq!(toml::parse(&written).unwrap(), table);
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+
+        assert_eq!(config.flags, Some(vec![Action::Git, Action::Readme]));
+        assert_eq!(config.mode, Some(Mode::Octal(0o750)));
+        assert_eq!(config.verbose, Some(true));
+        assert_eq!(config.license.as_deref(), Some("MIT"));
+        assert_eq!(config.author_name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(config.author_email.as_deref(), Some("ada@example.com"));
+        assert_eq!(config.go_module_prefix.as_deref(), Some("github.com/ada"));
+        assert_eq!(Config::from_toml(&config.to_toml()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_rejects_mistakes() {
+        let err = Config::from_toml("[defaults]\nflag = [\"git\"]\n").unwrap_err();
+        assert!(err.contains("unknown key `flag`"));
+
+        let err = Config::from_toml("[defaults]\nmode = \"999\"\n").unwrap_err();
+        assert!(err.contains("invalid mode"));
+
+        let err = Config::from_toml("[defaults]\nflags = [\"svn\"]\n").unwrap_err();
+        assert!(err.contains("unknown flag `svn`"));
+    }
+
+    #[test]
+    fn test_environment_overrides_config_file() {
+        let mut config = Config::from_toml(SAMPLE).unwrap();
+        config
+            .apply_env(|name| match name {
+                "MAKEDIR_FLAGS" => Some("cargo, license".to_string()),
+                "MAKEDIR_MODE" => Some("700".to_string()),
+                "MAKEDIR_AUTHOR_NAME" => Some("Grace Hopper".to_string()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(config.flags, Some(vec![Action::Cargo, Action::License]));
+        assert_eq!(config.mode, Some(Mode::Octal(0o700)));
+        assert_eq!(config.author_name.as_deref(), Some("Grace Hopper"));
+        assert_eq!(config.author_email.as_deref(), Some("ada@example.com"));
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        let parsed = parse_args_with_config(&args(&["api", "-755", "--git", "--npm"]), &config);
+
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.mode, Some(Mode::Octal(0o755)));
+        assert_eq!(
+            parsed.request.actions,
+            vec![Action::Git, Action::Readme, Action::Npm]
+        );
+        assert_eq!(parsed.request.verbosity, Verbosity::Steps);
+    }
+
+    #[test]
+    fn test_config_settings_reach_the_plan() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        let parsed = parse_args_with_config(&args(&["svc/api", "--go", "--license"]), &config);
+        let plan = Plan::new(&parsed.request).unwrap();
+        let steps = &plan.targets[0].steps;
+
+        assert!(steps.contains(&Step::Run(CommandSpec::new(
+            "go",
+            &["mod", "init", "github.com/ada/api"]
+        ))));
+        let license = steps
+            .iter()
+            .find_map(|step| match step {
+                Step::WriteFile { name, contents } if name == "LICENSE" => Some(contents),
+                _ => None,
+            })
+            .unwrap();
+        assert!(license.contains("Ada Lovelace"));
+    }
+
+    #[test]
+    fn test_config_defaults_apply_from_cli() {
+        let temp_dir = tempdir().unwrap();
+        let config_home = temp_dir.path().join("config");
+        write_config(
+            &config_home,
+            "[defaults]\nflags = [\"readme\"]\nmode = \"700\"\n",
+        );
+        let test_dir = temp_dir.path().join("from_config");
+
+        let output = run_makedir(&config_home, &[], &[test_dir.to_str().unwrap()]);
+
+        assert!(output.status.success());
+        assert!(test_dir.join("README.md").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&test_dir).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode, 0o700);
+        }
+    }
+
+    #[test]
+    fn test_config_show_prints_merged_settings() {
+        let temp_dir = tempdir().unwrap();
+        write_config(temp_dir.path(), SAMPLE);
+
+        let output = run_makedir(
+            temp_dir.path(),
+            &[("MAKEDIR_MODE", "700")],
+            &["config", "show"],
+        );
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("config.toml"));
+        assert!(stdout.contains("MAKEDIR_MODE=700"));
+        assert!(stdout.contains("mode = \"700\""));
+        assert!(stdout.contains("name = \"Ada Lovelace\""));
+        assert!(stdout.contains("module_prefix = \"github.com/ada\""));
+        assert!(!Path::new("show").exists(), "No directories should be created");
+    }
+
+    #[test]
+    fn test_invalid_config_is_a_usage_error() {
+        let temp_dir = tempdir().unwrap();
+        write_config(temp_dir.path(), "[defaults\n");
+        let test_dir = temp_dir.path().join("never");
+
+        let output = run_makedir(temp_dir.path(), &[], &[test_dir.to_str().unwrap()]);
+
+        assert_eq!(output.status.code(), Some(makedir::exit_code::USAGE));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid config file"));
+        assert!(!test_dir.exists());
+    }
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use makedir::toolchain::{self, Tool, TOOLS};
+use makedir::{Action, CommandSpec, CreateRequest, Plan, Step, Target};
+use tempfile::tempdir;
+
+use common::makedir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the makedir binary with only `bin` on PATH.
+    fn run(args: &[&str], bin: &Path, cwd: &Path) -> Output {
+        makedir(cwd)
+            .args(args)
+            .current_dir(cwd)
+            .env("PATH", bin)
+            .output()
+            .unwrap()
+    }
+
+    fn fake_tool(bin: &Path, name: &str, script: &str) {
+        let path = bin.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_every_initializer_has_a_tool() {
+        for action in Action::ALL {
+            let runs_program = Plan::new(&CreateRequest {
+                dirs: vec!["a".into()],
+                actions: vec![action],
+                ..Default::default()
+            })
+            .unwrap()
+            .targets[0]
+                .steps
+                .iter()
+                .any(|step| matches!(step, Step::Run(_)));
+            assert_eq!(
+                Tool::for_action(action).is_some(),
+                runs_program,
+                "{:?}",
+                action
+            );
+        }
+        assert_eq!(Tool::for_program("go").unwrap().action, Action::Go);
+        assert_eq!(TOOLS.len(), 7);
+    }
+
+    #[test]
+    fn test_check_names_the_package() {
+        let request = CreateRequest {
+            dirs: vec!["a".into(), "b".into()],
+            actions: vec![Action::Git, Action::Go, Action::Pnpm],
+            ..Default::default()
+        };
+        let plan = Plan::new(&request).unwrap();
+        let problems = toolchain::check_with(&plan, |program| {
+            (program == "git").then(|| PathBuf::from("/usr/bin/git"))
+        });
+        // Each missing tool once, however many directories need it
+        assert_eq!(
+            problems,
+            vec![
+                "--go needs `go`, which is not on PATH. It is provided by the golang package (https://go.dev/dl).",
+                "--pnpm needs `pnpm`, which is not on PATH. It is provided by the pnpm package, or `corepack enable` with Node.js.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_covers_template_commands() {
+        let plan = Plan {
+            targets: vec![Target {
+                dir: "a".into(),
+                mode: None,
+                steps: vec![
+                    Step::Run(CommandSpec::new("makedir-no-such-tool", &[])),
+                    // Relative to the new directory, so it can't be checked yet
+                    Step::Run(CommandSpec::new("./setup.sh", &[])),
+                ],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            toolchain::check_with(&plan, |_| None),
+            vec!["`makedir-no-such-tool` is not on PATH."]
+        );
+    }
+
+    #[test]
+    fn test_missing_tool_fails_before_creating_anything() {
+        let temp_dir = tempdir().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+
+        let output = run(
+            &["first", "second", "--readme", "--cargo"],
+            &bin,
+            temp_dir.path(),
+        );
+        assert_eq!(output.status.code(), Some(3));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Missing tool: --cargo needs `cargo`"));
+        assert!(stderr.contains("rustup"));
+        assert!(!temp_dir.path().join("first").exists());
+
+        let output = run(
+            &["first", "--cargo", "--output", "json"],
+            &bin,
+            temp_dir.path(),
+        );
+        assert_eq!(output.status.code(), Some(3));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with("{\"success\":false,\"exit_code\":3,"));
+        assert!(stdout.contains("\"skipped\":[\"first\"]"));
+        assert!(stdout.contains("\"missing_tools\":[\"--cargo needs `cargo`"));
+        assert!(!temp_dir.path().join("first").exists());
+
+        // A dry run only describes the plan, so it doesn't need the tools
+        let output = run(&["first", "--cargo", "--dry-run"], &bin, temp_dir.path());
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn test_doctor_reports_versions() {
+        let temp_dir = tempdir().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fake_tool(&bin, "git", "echo 'git version 9.8.7'");
+        fake_tool(&bin, "go", "echo 'go version go1.99.1 linux/amd64'");
+
+        let output = run(&["doctor", "--git", "go"], &bin, temp_dir.path());
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let git = format!(
+            "  --git    git version 9.8.7 ({})",
+            bin.join("git").display()
+        );
+        assert!(stdout.contains(&git), "{}", stdout);
+        assert!(stdout.contains("  --go     go version go1.99.1 linux/amd64"));
+        assert!(stdout.ends_with("All tools found.\n"));
+
+        let output = run(&["doctor"], &bin, temp_dir.path());
+        assert_eq!(output.status.code(), Some(3));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.lines().count(), TOOLS.len() + 1);
+        assert!(stdout.contains("  --bun    bun: not on PATH. It is provided by bun"));
+        assert!(stdout.ends_with("5 of 7 tools missing or broken.\n"));
+
+        let output = run(&["doctor", "--bogus"], &bin, temp_dir.path());
+        assert_eq!(output.status.code(), Some(1));
+    }
+
+    #[test]
+    fn test_doctor_reports_broken_tools() {
+        let temp_dir = tempdir().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fake_tool(&bin, "yarn", "exit 1");
+
+        let output = run(&["doctor", "yarn"], &bin, temp_dir.path());
+        assert_eq!(output.status.code(), Some(3));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("yarn: `"));
+        assert!(stdout.contains("--version` printed no version"));
+    }
+
+    #[test]
+    fn test_double_dash_creates_directories_named_like_commands() {
+        let temp_dir = tempdir().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+
+        let output = run(&["--", "doctor", "verify"], &bin, temp_dir.path());
+        assert!(output.status.success());
+        assert!(temp_dir.path().join("doctor").is_dir());
+        assert!(temp_dir.path().join("verify").is_dir());
+    }
+}
@@ -0,0 +1,88 @@
+use std::process::Command;
+
+use makedir::dry_run::describe;
+use makedir::mode::Mode;
+use makedir::{Action, CreateRequest, Plan};
+use tempfile::tempdir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_lists_every_step_in_order() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("parent/child");
+
+        let request = CreateRequest {
+            dirs: vec![test_dir.clone()],
+            actions: vec![Action::Git, Action::Readme],
+            mode: Some(Mode::Octal(0o700)),
+            ..Default::default()
+        };
+        let lines = describe(&Plan::new(&request).unwrap());
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("create directory") && lines[0].ends_with("parent"));
+        assert!(lines[1].starts_with("create directory") && lines[1].ends_with("child"));
+        assert!(lines[2].starts_with("set permissions 700"));
+        assert!(lines[3].starts_with("run `git init`"));
+        assert!(lines[4].contains("README.md"));
+        assert!(!test_dir.exists(), "Nothing should be created");
+    }
+
+    #[test]
+    fn test_describe_shares_parents_between_targets() {
+        let temp_dir = tempdir().unwrap();
+        let parent = temp_dir.path().join("shared");
+
+        let request = CreateRequest {
+            dirs: vec![parent.join("a"), parent.join("b")],
+            ..Default::default()
+        };
+        let lines = describe(&Plan::new(&request).unwrap());
+
+        let parent_line = format!("create directory {}", parent.display());
+        assert_eq!(lines.iter().filter(|l| **l == parent_line).count(), 1);
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_describe_existing_directory() {
+        let temp_dir = tempdir().unwrap();
+
+        let request = CreateRequest {
+            dirs: vec![temp_dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let lines = describe(&Plan::new(&request).unwrap());
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("directory already exists"));
+    }
+
+    #[test]
+    fn test_dry_run_flag_touches_nothing() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("dry_run_cli");
+
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("--")
+            .arg(test_dir.to_str().unwrap())
+            .arg("--git")
+            .arg("--license")
+            .arg("-755")
+            .arg("--dry-run")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(!test_dir.exists(), "Directory should not be created");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("create directory"));
+        assert!(stdout.contains("set permissions 755"));
+        assert!(stdout.contains("git init"));
+        assert!(stdout.contains("LICENSE"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::process::Command;
+
+use makedir::{execute, exit_code, CommandSpec, FailureKind, Plan, Step, Target};
+use tempfile::tempdir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_makedir(args: &[&str]) -> (Option<i32>, String) {
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("--")
+            .args(args)
+            .output()
+            .expect("Failed to execute makedir");
+
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        (output.status.code(), stderr)
+    }
+
+    fn target(dir: std::path::PathBuf, steps: Vec<Step>) -> Target {
+        Target {
+            dir,
+            mode: None,
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_success_exits_zero() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("ok");
+
+        let (code, _) = run_makedir(&[test_dir.to_str().unwrap(), "--readme"]);
+
+        assert_eq!(code, Some(exit_code::SUCCESS));
+    }
+
+    #[test]
+    fn test_usage_error_exit_code() {
+        let (code, _) = run_makedir(&["somewhere", "--no-such-flag"]);
+
+        assert_eq!(code, Some(exit_code::USAGE));
+    }
+
+    #[test]
+    fn test_filesystem_error_exit_code() {
+        let temp_dir = tempdir().unwrap();
+        let blocker = temp_dir.path().join("file");
+        fs::write(&blocker, "not a directory").unwrap();
+        let test_dir = blocker.join("child");
+
+        let (code, stderr) = run_makedir(&[test_dir.to_str().unwrap()]);
+
+        assert_eq!(code, Some(exit_code::FILESYSTEM));
+        assert!(stderr.contains("1 of 1 directories failed"));
+    }
+
+    #[test]
+    fn test_keep_going_finishes_every_directory() {
+        let temp_dir = tempdir().unwrap();
+        let blocker = temp_dir.path().join("file");
+        fs::write(&blocker, "not a directory").unwrap();
+        let bad_dir = blocker.join("child");
+        let good_dir = temp_dir.path().join("good");
+
+        let (code, stderr) = run_makedir(&[
+            bad_dir.to_str().unwrap(),
+            good_dir.to_str().unwrap(),
+            "--keep-going",
+        ]);
+
+        assert_eq!(code, Some(exit_code::FILESYSTEM));
+        assert!(
+            good_dir.exists(),
+            "Later directories should still be created"
+        );
+        assert!(stderr.contains("1 of 2 directories failed"));
+        assert!(stderr.contains(bad_dir.to_str().unwrap()));
+    }
+
+    #[test]
+    fn test_fail_fast_stops_at_first_failure() {
+        let temp_dir = tempdir().unwrap();
+        let blocker = temp_dir.path().join("file");
+        fs::write(&blocker, "not a directory").unwrap();
+        let bad_dir = blocker.join("child");
+        let good_dir = temp_dir.path().join("good");
+
+        let (code, stderr) = run_makedir(&[
+            bad_dir.to_str().unwrap(),
+            good_dir.to_str().unwrap(),
+            "--fail-fast",
+        ]);
+
+        assert_eq!(code, Some(exit_code::FILESYSTEM));
+        assert!(!good_dir.exists(), "Later directories should be skipped");
+        assert!(stderr.contains("skipped"));
+    }
+
+    #[test]
+    fn test_initializer_failure_kind() {
+        let temp_dir = tempdir().unwrap();
+
+        let report = execute(&Plan {
+            targets: vec![target(
+                temp_dir.path().join("init"),
+                vec![Step::Run(CommandSpec::new("false", &[]))],
+            )],
+            ..Default::default()
+        });
+
+        assert_eq!(report.targets[0].errors[0].kind, FailureKind::Initializer);
+        assert_eq!(report.exit_code(), exit_code::INITIALIZER);
+    }
+
+    #[test]
+    fn test_fail_fast_report_lists_skipped_directories() {
+        let temp_dir = tempdir().unwrap();
+        let second = temp_dir.path().join("second");
+
+        let report = execute(&Plan {
+            targets: vec![
+                target(
+                    temp_dir.path().join("first"),
+                    vec![Step::Run(CommandSpec::new("false", &[]))],
+                ),
+                target(second.clone(), Vec::new()),
+            ],
+            fail_fast: true,
+            ..Default::default()
+        });
+
+        assert_eq!(report.targets.len(), 1);
+        assert_eq!(report.skipped, vec![second.clone()]);
+        assert!(!second.exists());
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_double_dash_ends_options() {
+        let temp_dir = tempdir().unwrap();
+        let dash_dir = temp_dir.path().join("-v");
+
+        let status = Command::new("cargo")
+            .current_dir(temp_dir.path())
+            .arg("run")
+            .arg("--manifest-path")
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .arg("--")
+            .arg("--")
+            .arg("-v")
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        assert!(
+            dash_dir.is_dir(),
+            "A dash-prefixed name after -- is a directory"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::path::PathBuf;
+
+use makedir::cli::parse_args;
+use makedir::expand::expand;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(word: &str) -> Vec<String> {
+        expand(word).unwrap()
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            expanded("src/{api,web,cli}"),
+            vec!["src/api", "src/web", "src/cli"]
+        );
+        assert_eq!(expanded("{a,b}-{1,2}"), vec!["a-1", "a-2", "b-1", "b-2"]);
+        assert_eq!(expanded("lib{,s}"), vec!["lib", "libs"]);
+    }
+
+    #[test]
+    fn test_numeric_ranges() {
+        assert_eq!(expanded("day{1..3}"), vec!["day1", "day2", "day3"]);
+        assert_eq!(expanded("{3..1}"), vec!["3", "2", "1"]);
+        assert_eq!(expanded("{0..10..5}"), vec!["0", "5", "10"]);
+        assert_eq!(expanded("{-1..1}"), vec!["-1", "0", "1"]);
+    }
+
+    #[test]
+    fn test_zero_padding() {
+        let days = expanded("day{01..30}");
+        assert_eq!(days.len(), 30);
+        assert_eq!(days[0], "day01");
+        assert_eq!(days[29], "day30");
+        assert_eq!(expanded("{8..010}"), vec!["008", "009", "010"]);
+    }
+
+    #[test]
+    fn test_alphabetic_ranges() {
+        assert_eq!(expanded("{a..e..2}"), vec!["a", "c", "e"]);
+        assert_eq!(expanded("{C..A}"), vec!["C", "B", "A"]);
+    }
+
+    #[test]
+    fn test_nesting() {
+        assert_eq!(
+            expanded("app/{src/{api,web},docs}"),
+            vec!["app/src/api", "app/src/web", "app/docs"]
+        );
+        assert_eq!(expanded("{x{1..2},y}"), vec!["x1", "x2", "y"]);
+    }
+
+    #[test]
+    fn test_literals_are_left_alone() {
+        assert_eq!(expanded("plain"), vec!["plain"]);
+        assert_eq!(expanded("{x}"), vec!["{x}"]);
+        assert_eq!(expanded("{a..}"), vec!["{a..}"]);
+        assert_eq!(expanded("{1..b}"), vec!["{1..b}"]);
+        assert_eq!(expanded("open{a,b"), vec!["open{a,b"]);
+        assert_eq!(expanded("{}"), vec!["{}"]);
+        assert_eq!(expanded("\\{a,b\\}"), vec!["{a,b}"]);
+        assert_eq!(expanded("{a\\,b,c}"), vec!["a,b", "c"]);
+        // Only backslashes before braces, commas and backslashes are escapes
+        assert_eq!(expanded("a\\b"), vec!["a\\b"]);
+        assert_eq!(expanded("a\\\\b"), vec!["a\\\\b"]);
+        assert_eq!(expanded("{x\\y,z}"), vec!["x\\y", "z"]);
+        assert_eq!(expanded("{a\\\\,b}"), vec!["a\\", "b"]);
+    }
+
+    #[test]
+    fn test_huge_expansions_are_rejected() {
+        assert!(expand("{1..100000}").is_err());
+        assert!(expand("{1..100}{1..100}{1..2}")
+            .unwrap_err()
+            .contains("more than"));
+    }
+
+    #[test]
+    fn test_cli_expands_directories() {
+        let parsed = parse_args(&args(&["src/{api,web}", "--git", "day{1..2}"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(
+            parsed.request.dirs,
+            vec![
+                PathBuf::from("src/api"),
+                PathBuf::from("src/web"),
+                PathBuf::from("day1"),
+                PathBuf::from("day2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_expand_keeps_names_literal() {
+        let parsed = parse_args(&args(&["src/{api,web}", "--no-expand"]));
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from("src/{api,web}")]);
+
+        // Names without braces are never changed, backslashes included
+        let parsed = parse_args(&args(&["a\\b"]));
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from("a\\b")]);
+    }
+
+    #[test]
+    fn test_cli_reports_huge_expansions() {
+        let parsed = parse_args(&args(&["{1..99999}"]));
+        assert!(parsed.errors[0].contains("Invalid directory"));
+    }
+}
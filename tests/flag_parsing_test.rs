@@ -1,10 +1,7 @@
 #[cfg(test)]
 mod flag_parsing_tests {
-    use std::env;
     use std::fs;
-    use std::path::Path;
     use std::process::Command;
-    use std::io::Write;
     use tempfile::tempdir;
 
     // Helper function to run the makedir binary with specific arguments
@@ -106,9 +103,9 @@ mod flag_parsing_tests {
         
         let (success, _, stderr) = run_makedir(&[test_dir_str, "-9999"]);
         
-        assert!(success, "Command should still succeed even with invalid permission");
+        assert!(!success, "Command should fail with a usage error");
         assert!(stderr.contains("Invalid permission format"), "Should show invalid permission error");
-        assert!(test_dir.exists(), "Directory should still be created");
+        assert!(!test_dir.exists(), "Nothing should be created on a usage error");
     }
 
     #[test]
@@ -119,9 +116,9 @@ mod flag_parsing_tests {
         
         let (success, _, stderr) = run_makedir(&[test_dir_str, "--nonexistent-flag"]);
         
-        assert!(success, "Command should still succeed");
+        assert!(!success, "Command should fail with a usage error");
         assert!(stderr.contains("Unknown flag"), "Should show unknown flag error");
-        assert!(test_dir.exists(), "Directory should still be created");
+        assert!(!test_dir.exists(), "Nothing should be created on a usage error");
     }
 
     #[test]
@@ -130,11 +127,12 @@ mod flag_parsing_tests {
         let test_dir = temp_dir.path().join("test_dash_only");
         let test_dir_str = test_dir.to_str().unwrap();
         
-        let (success, _, _) = run_makedir(&[test_dir_str, "-"]);
+        let (success, _, stderr) = run_makedir(&[test_dir_str, "-"]);
         
-        assert!(success, "Command should succeed");
-        assert!(test_dir.exists(), "Directory should be created");
-        // The "-" should be treated as a flag but not recognized, so it should show an unknown flag error
+        // The "-" is treated as a flag but not recognized, so it is a usage error
+        assert!(!success, "Command should fail with a usage error");
+        assert!(stderr.contains("Unknown flag"), "Should show unknown flag error");
+        assert!(!test_dir.exists(), "Nothing should be created on a usage error");
     }
 
     #[test]
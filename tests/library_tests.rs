@@ -0,0 +1,103 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use makedir::cli::parse_args;
+use makedir::mode::Mode;
+use makedir::reporter::Verbosity;
+use makedir::{execute, Action, CommandSpec, CreateRequest, Plan, Step};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args_splits_dirs_flags_and_mode() {
+        let parsed = parse_args(&args(&["api", "web", "-g", "--readme", "-750", "-v"]));
+
+        assert!(parsed.errors.is_empty());
+        assert_eq!(
+            parsed.request.dirs,
+            vec!["api".into(), "web".into()] as Vec<std::path::PathBuf>
+        );
+        assert_eq!(parsed.request.actions, vec![Action::Git, Action::Readme]);
+        assert_eq!(parsed.request.mode, Some(Mode::Octal(0o750)));
+        assert_eq!(parsed.request.verbosity, Verbosity::Steps);
+    }
+
+    #[test]
+    fn test_parse_args_collects_errors() {
+        let parsed = parse_args(&args(&["api", "--bogus", "-9999"]));
+
+        assert_eq!(parsed.request.dirs.len(), 1);
+        assert!(parsed
+            .errors
+            .iter()
+            .any(|e| e.contains("Unknown flag: --bogus")));
+        assert!(parsed
+            .errors
+            .iter()
+            .any(|e| e.contains("Invalid permission format")));
+    }
+
+    #[test]
+    fn test_plan_has_one_target_per_directory() {
+        let request = CreateRequest {
+            dirs: vec!["a".into(), "b".into()],
+            actions: vec![Action::Go, Action::License],
+            ..Default::default()
+        };
+        let plan = Plan::new(&request).unwrap();
+
+        assert_eq!(plan.targets.len(), 2);
+        assert_eq!(
+            plan.targets[1].steps[0],
+            Step::Run(CommandSpec::new("go", &["mod", "init", "b"]))
+        );
+        assert!(matches!(
+            &plan.targets[1].steps[1],
+            Step::WriteFile { name, .. } if name == "LICENSE"
+        ));
+    }
+
+    #[test]
+    fn test_execute_creates_directories_and_files() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("lib_exec");
+
+        let request = CreateRequest {
+            dirs: vec![test_dir.clone()],
+            actions: vec![Action::Readme, Action::Docker],
+            mode: Some(Mode::Octal(0o750)),
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+
+        assert!(report.is_success());
+        assert!(report.targets[0].created);
+        assert!(test_dir.join("README.md").exists());
+        assert!(fs::read_to_string(test_dir.join("Dockerfile"))
+            .unwrap()
+            .contains("FROM"));
+        let mode = fs::metadata(&test_dir).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o750);
+    }
+
+    #[test]
+    fn test_execute_reports_existing_directory() {
+        let temp_dir = tempdir().unwrap();
+
+        let request = CreateRequest {
+            dirs: vec![temp_dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+
+        assert!(report.is_success());
+        assert!(!report.targets[0].created);
+    }
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use std::fs;
+
+use makedir::cli::parse_args;
+use makedir::license::{self, Expression};
+use makedir::{execute, Action, Config, CreateRequest, Plan, Step, Target};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written_files(request: &CreateRequest) -> Vec<(String, String)> {
+        Plan::new(request).unwrap().targets[0]
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::WriteFile { name, contents } => Some((name.clone(), contents.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn set_field(manifest_name: &str, manifest: &str, license: &str) -> String {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("proj");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join(manifest_name), manifest).unwrap();
+
+        let plan = Plan {
+            targets: vec![Target {
+                dir: dir.clone(),
+                mode: None,
+                steps: vec![Step::SetLicenseField {
+                    name: manifest_name.to_string(),
+                    license: license.to_string(),
+                }],
+            }],
+            ..Default::default()
+        };
+        assert!(execute(&plan).is_success());
+        fs::read_to_string(dir.join(manifest_name)).unwrap()
+    }
+
+    #[test]
+    fn test_expressions_are_normalized() {
+        let expression = Expression::parse("mit or apache-2.0").unwrap();
+        assert_eq!(expression.to_string(), "MIT OR Apache-2.0");
+
+        let expression = Expression::parse("(MPL-2.0 AND BSD-3-Clause)").unwrap();
+        assert_eq!(expression.to_string(), "MPL-2.0 AND BSD-3-Clause");
+        assert_eq!(expression.licenses().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        assert!(Expression::parse("WTFPL")
+            .unwrap_err()
+            .contains("unsupported license `WTFPL`"));
+        assert!(Expression::parse("GPL-3.0-only WITH Classpath-exception-2.0").is_err());
+        assert!(Expression::parse("MIT OR").is_err());
+        assert!(Expression::parse("MIT XOR Apache-2.0").is_err());
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("GPL-3.0-only OR GPL-3.0-or-later")
+            .unwrap_err()
+            .contains("LICENSE-GPL"));
+    }
+
+    #[test]
+    fn test_catalog_has_full_texts() {
+        for id in [
+            "MIT",
+            "Apache-2.0",
+            "MPL-2.0",
+            "GPL-3.0-only",
+            "BSD-3-Clause",
+        ] {
+            let license = license::find(id).unwrap();
+            assert!(license.text.len() > 1000, "{} is too short", id);
+        }
+        assert!(license::find("Apache-2.0")
+            .unwrap()
+            .text
+            .contains("END OF TERMS AND CONDITIONS"));
+    }
+
+    #[test]
+    fn test_single_license_fills_holder_and_year() {
+        let request = CreateRequest {
+            dirs: vec!["proj".into()],
+            actions: vec![Action::License],
+            author_name: Some("Ada Lovelace".to_string()),
+            license: Some("BSD-3-Clause".to_string()),
+            ..Default::default()
+        };
+        let files = written_files(&request);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "LICENSE");
+        let year = makedir::template::current_year();
+        assert!(files[0]
+            .1
+            .contains(&format!("Copyright (c) {}, Ada Lovelace", year)));
+    }
+
+    #[test]
+    fn test_dual_license_writes_one_file_each() {
+        let request = CreateRequest {
+            dirs: vec!["proj".into()],
+            actions: vec![Action::License, Action::Readme],
+            author_name: Some("Ada".to_string()),
+            license: Some("MIT OR Apache-2.0".to_string()),
+            ..Default::default()
+        };
+        let files = written_files(&request);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["LICENSE-MIT", "LICENSE-APACHE", "README.md"]);
+        assert!(files[0].1.starts_with("MIT License"));
+        // Apache-2.0 has no copyright line to fill in and is kept verbatim
+        assert_eq!(files[1].1, license::find("Apache-2.0").unwrap().text);
+        assert!(files[2].1.contains("MIT OR Apache-2.0"));
+        assert!(files[2].1.contains("LICENSE-MIT and LICENSE-APACHE"));
+    }
+
+    #[test]
+    fn test_license_field_follows_initializers() {
+        let request = CreateRequest {
+            dirs: vec!["proj".into()],
+            actions: vec![Action::License, Action::Cargo, Action::Npm],
+            license: Some("MPL-2.0".to_string()),
+            ..Default::default()
+        };
+        let steps = &Plan::new(&request).unwrap().targets[0].steps;
+        let fields: Vec<&Step> = steps
+            .iter()
+            .filter(|step| matches!(step, Step::SetLicenseField { .. }))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                &Step::SetLicenseField {
+                    name: "Cargo.toml".to_string(),
+                    license: "MPL-2.0".to_string()
+                },
+                &Step::SetLicenseField {
+                    name: "package.json".to_string(),
+                    license: "MPL-2.0".to_string()
+                },
+            ]
+        );
+        // After the initializers that create the manifests
+        assert!(matches!(steps.last(), Some(Step::SetLicenseField { .. })));
+
+        let request = CreateRequest {
+            actions: vec![Action::Cargo],
+            ..request
+        };
+        let steps = &Plan::new(&request).unwrap().targets[0].steps;
+        assert_eq!(steps.len(), 1);
+    }
+
+    #[test]
+    fn test_sets_cargo_toml_license() {
+        let manifest = "[package]\nname = \"proj\"\nversion = \"0.1.0\"\n\n[dependencies]\n";
+        assert_eq!(
+            set_field("Cargo.toml", manifest, "MIT OR Apache-2.0"),
+            "[package]\nname = \"proj\"\nversion = \"0.1.0\"\nlicense = \"MIT OR Apache-2.0\"\n\n[dependencies]\n"
+        );
+
+        let manifest = "[package]\nname = \"proj\"\nlicense = \"ISC\"\n";
+        assert_eq!(
+            set_field("Cargo.toml", manifest, "MIT"),
+            "[package]\nname = \"proj\"\nlicense = \"MIT\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sets_package_json_license() {
+        let manifest = "{\n  \"name\": \"proj\",\n  \"license\": \"ISC\"\n}\n";
+        assert_eq!(
+            set_field("package.json", manifest, "Apache-2.0"),
+            "{\n  \"name\": \"proj\",\n  \"license\": \"Apache-2.0\"\n}\n"
+        );
+
+        let manifest = "{\n  \"name\": \"proj\"\n}\n";
+        assert_eq!(
+            set_field("package.json", manifest, "MIT"),
+            "{\n  \"license\": \"MIT\",\n  \"name\": \"proj\"\n}\n"
+        );
+
+        // Only the top-level key is replaced, not a nested one or a value
+        let manifest = "{\n  \"description\": \"\\\"license\\\": x\",\n  \"config\": { \"license\": \"ISC\" },\n  \"license\": \"ISC\"\n}\n";
+        assert_eq!(
+            set_field("package.json", manifest, "MIT"),
+            "{\n  \"description\": \"\\\"license\\\": x\",\n  \"config\": { \"license\": \"ISC\" },\n  \"license\": \"MIT\"\n}\n"
+        );
+
+        let manifest = "{\n  \"config\": { \"license\": \"ISC\" }\n}\n";
+        assert_eq!(
+            set_field("package.json", manifest, "MIT"),
+            "{\n  \"license\": \"MIT\",\n  \"config\": { \"license\": \"ISC\" }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_cli_license_expression_is_optional() {
+        let parsed = parse_args(&args(&["proj", "--license=MIT OR Apache-2.0"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.dirs.len(), 1);
+        assert_eq!(parsed.request.license.as_deref(), Some("MIT OR Apache-2.0"));
+
+        // The next argument is a directory, even when it reads as an expression
+        for list in [["-l", "docs"], ["--license", "mit"]] {
+            let parsed = parse_args(&args(&list));
+            assert_eq!(parsed.request.actions, vec![Action::License]);
+            assert_eq!(parsed.request.dirs, vec![std::path::PathBuf::from(list[1])]);
+            assert_eq!(parsed.request.license, None);
+        }
+
+        let parsed = parse_args(&args(&["proj", "--license=gpl-3.0-only"]));
+        assert_eq!(parsed.request.license.as_deref(), Some("GPL-3.0-only"));
+
+        let parsed = parse_args(&args(&["proj", "--license=Unlicense"]));
+        assert!(parsed.errors[0].contains("Invalid license"));
+    }
+
+    #[test]
+    fn test_config_accepts_spdx_expressions() {
+        let config = Config::from_toml("[defaults]\nlicense = \"apache-2.0 OR mit\"\n").unwrap();
+        assert_eq!(config.license.as_deref(), Some("Apache-2.0 OR MIT"));
+
+        assert!(Config::from_toml("[defaults]\nlicense = \"Nope\"\n").is_err());
+    }
+}
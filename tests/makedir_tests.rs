@@ -1,6 +1,5 @@
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
-use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::tempdir;
 
@@ -8,12 +7,6 @@ use tempfile::tempdir;
 mod tests {
     use super::*;
 
-    fn cleanup_test_dir(path: &Path) {
-        if path.exists() {
-            let _ = fs::remove_dir_all(path);
-        }
-    }
-
     #[test]
     fn test_basic_directory_creation() {
         let temp_dir = tempdir().unwrap();
@@ -99,7 +92,7 @@ mod tests {
         assert!(status.success());
         assert!(test_dir.join("README.md").exists());
         let content = fs::read_to_string(test_dir.join("README.md")).unwrap();
-        assert!(content.contains("# Project Title"));
+        assert!(content.contains("# readme_test"));
     }
 
     #[test]
@@ -153,7 +146,8 @@ mod tests {
             .output()
             .unwrap();
 
-        assert!(test_dir.exists()); // Directory should still be created
+        assert!(!output.status.success());
+        assert!(!test_dir.exists()); // Usage errors create nothing
         assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown flag"));
     }
 
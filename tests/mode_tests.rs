@@ -0,0 +1,170 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use makedir::cli::parse_args;
+use makedir::mode::{self, Mode};
+use makedir::preset::ResolvedPreset;
+use makedir::{dry_run, execute, Config, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(mode: &str, current: u32, is_dir: bool) -> u32 {
+        Mode::parse(mode).unwrap().apply(current, is_dir, 0o022)
+    }
+
+    #[test]
+    fn test_octal_modes() {
+        assert_eq!(Mode::parse("755"), Ok(Mode::Octal(0o755)));
+        assert_eq!(Mode::parse("2775"), Ok(Mode::Octal(0o2775)));
+        assert_eq!(Mode::parse("1777"), Ok(Mode::Octal(0o1777)));
+        assert_eq!(Mode::parse("0"), Ok(Mode::Octal(0)));
+        assert_eq!(apply("2775", 0o700, true), 0o2775);
+        assert!(Mode::parse("27750").is_err());
+        assert!(Mode::parse("789").unwrap_err().contains("octal"));
+    }
+
+    #[test]
+    fn test_absolute_symbolic_modes() {
+        assert_eq!(apply("u=rwx,g=rx,o=", 0o777, true), 0o750);
+        assert_eq!(apply("a=r", 0o755, false), 0o444);
+        assert_eq!(apply("u=rwx,go=", 0o4755, true), 0o700);
+        assert_eq!(apply("ug=rwx,o=rx,g+s", 0o700, true), 0o2775);
+    }
+
+    #[test]
+    fn test_relative_symbolic_modes() {
+        assert_eq!(apply("g+w", 0o755, true), 0o775);
+        assert_eq!(apply("go-rwx", 0o755, true), 0o700);
+        assert_eq!(apply("o+t", 0o777, true), 0o1777);
+        assert_eq!(apply("u+s,g+s", 0o755, true), 0o6755);
+        assert_eq!(apply("u+r-w", 0o200, false), 0o400);
+        assert_eq!(apply("g=u", 0o740, false), 0o770);
+        assert_eq!(apply("o+g", 0o750, true), 0o755);
+    }
+
+    #[test]
+    fn test_modes_without_a_class_respect_the_umask() {
+        let writable = Mode::parse("+w").unwrap();
+        assert_eq!(writable.apply(0o500, true, 0o022), 0o700);
+        assert_eq!(writable.apply(0o500, true, 0), 0o722);
+        assert_eq!(apply("a+w", 0o500, true), 0o722);
+
+        // The plan reads the umask up front, so worker threads never touch it
+        let plan = Plan::new(&CreateRequest::default()).unwrap();
+        assert_eq!(plan.umask, mode::umask());
+    }
+
+    #[test]
+    fn test_capital_x() {
+        // Directories and files someone can already execute
+        assert_eq!(apply("go+X", 0o700, true), 0o711);
+        assert_eq!(apply("go+X", 0o744, false), 0o755);
+        assert_eq!(apply("go+X", 0o644, false), 0o644);
+    }
+
+    #[test]
+    fn test_invalid_symbolic_modes() {
+        assert!(Mode::parse("u+q")
+            .unwrap_err()
+            .contains("unknown permission `q`"));
+        assert!(Mode::parse("urwx").is_err());
+        assert!(Mode::parse("g").is_err());
+        assert!(Mode::parse("u=rwx,").is_err());
+        assert!(Mode::parse("").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Mode::Octal(0o55).to_string(), "055");
+        assert_eq!(Mode::Octal(0o2775).to_string(), "2775");
+        assert_eq!(Mode::parse("g+ws").unwrap().to_string(), "g+ws");
+    }
+
+    #[test]
+    fn test_cli_mode_option() {
+        let parsed = parse_args(&args(&["shared", "--mode", "2775"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.mode, Some(Mode::Octal(0o2775)));
+
+        let parsed = parse_args(&args(&["shared", "--mode=u=rwx,g=rx,o="]));
+        assert_eq!(parsed.request.mode, Mode::parse("u=rwx,g=rx,o=").ok());
+
+        // The shorthand still works, and the last mode given wins
+        let parsed = parse_args(&args(&["shared", "--mode", "g+w", "-700"]));
+        assert_eq!(parsed.request.mode, Some(Mode::Octal(0o700)));
+
+        let parsed = parse_args(&args(&["shared", "--mode", "u+q"]));
+        assert!(parsed.errors[0].contains("unknown permission"));
+        let parsed = parse_args(&args(&["shared", "--mode"]));
+        assert!(!parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_special_bits_are_applied() {
+        let temp_dir = tempdir().unwrap();
+        let sticky = temp_dir.path().join("tmp");
+        let shared = temp_dir.path().join("shared");
+        let request = |dir, mode: &str| CreateRequest {
+            dirs: vec![dir],
+            mode: Some(Mode::parse(mode).unwrap()),
+            ..Default::default()
+        };
+
+        let report = execute(&Plan::new(&request(sticky.clone(), "1777")).unwrap());
+        assert!(report.is_success());
+        let mode = fs::metadata(&sticky).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o1777);
+
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o750)).unwrap();
+        let report = execute(&Plan::new(&request(shared.clone(), "g+ws,o+rX")).unwrap());
+        assert!(report.is_success());
+        let mode = fs::metadata(&shared).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o2775);
+    }
+
+    #[test]
+    fn test_dry_run_shows_mode_as_given() {
+        let request = CreateRequest {
+            dirs: vec!["shared".into()],
+            mode: Some(Mode::parse("g+s").unwrap()),
+            ..Default::default()
+        };
+        let lines = dry_run::describe(&Plan::new(&request).unwrap());
+        assert!(lines.contains(&"set permissions g+s on shared".to_string()));
+    }
+
+    #[test]
+    fn test_config_and_presets_accept_modes() {
+        let config =
+            Config::from_toml("[defaults]\nmode = \"2770\"\n\n[preset.team]\nmode = \"g+ws\"\n")
+                .unwrap();
+        assert_eq!(config.mode, Some(Mode::Octal(0o2770)));
+        assert!(config.to_toml().contains("mode = \"2770\""));
+
+        let team = config.resolve_preset("team").unwrap();
+        assert_eq!(team.to_args(), vec!["--mode=g+ws"]);
+        let plain = ResolvedPreset {
+            mode: Some(Mode::Octal(0o750)),
+            ..Default::default()
+        };
+        assert_eq!(plain.to_args(), vec!["-750"]);
+
+        assert!(Config::from_toml("[defaults]\nmode = \"u+z\"\n").is_err());
+        // A bare 755 is decimal, and 0o755 can't be told apart from it once parsed
+        for number in ["755", "0o755"] {
+            let err = Config::from_toml(&format!("[defaults]\nmode = {}\n", number)).unwrap_err();
+            assert!(err.contains("must be a string like"), "{}", err);
+        }
+        assert!(Config::from_toml("[preset.x]\nmode = 700\n")
+            .unwrap_err()
+            .contains("`preset.x.mode` must be a string like \"700\""));
+    }
+}
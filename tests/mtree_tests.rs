@@ -0,0 +1,311 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use makedir::cli::parse_args;
+use makedir::exit_code;
+use makedir::mtree::{Kind, Manifest, Problem};
+use makedir::{execute, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::{args, mode};
+
+const CLASSIC: &str = r"#mtree
+# Generated by hand
+/set type=file uid={uid} gid={gid} mode=0644
+.               type=dir mode=0755
+    bin         type=dir mode=0750
+        run\040me   mode=0755
+        tool \
+                    mode=0700 size=12 sha256digest=abc
+    ..
+    etc         type=dir mode=0755
+        conf    optional
+    ..
+/unset mode
+    share       type=dir
+    ..
+..
+";
+
+const FULL_PATH: &str = "\
+#mtree v2.0
+/set uid={uid} gid={gid}
+. type=dir mode=0755
+./bin type=dir mode=0750
+./bin/run\\040me type=file mode=0755
+./bin/tool type=file mode=0700
+./etc type=dir mode=0755
+./etc/conf type=file mode=0644 optional
+./share type=dir
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The uid and gid this test runs as.
+    fn ids() -> (u32, u32) {
+        let metadata = fs::metadata(tempdir().unwrap().path()).unwrap();
+        (metadata.uid(), metadata.gid())
+    }
+
+    /// Fills in `{uid}` and `{gid}` with ours, so that building the
+    /// manifest works without root.
+    fn with_ids(text: &str) -> String {
+        let (uid, gid) = ids();
+        text.replace("{uid}", &uid.to_string())
+            .replace("{gid}", &gid.to_string())
+    }
+
+    fn manifest(text: &str) -> Manifest {
+        Manifest::parse(&with_ids(text)).unwrap()
+    }
+
+    #[test]
+    fn test_classic_format() {
+        let manifest = manifest(CLASSIC);
+        let paths: Vec<&str> = manifest.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ".",
+                "bin",
+                "bin/run me",
+                "bin/tool",
+                "etc",
+                "etc/conf",
+                "share"
+            ]
+        );
+        let tool = &manifest.entries[3];
+        assert_eq!(tool.kind, Kind::File);
+        assert_eq!(tool.mode, Some(0o700));
+        assert_eq!(tool.uid, Some(ids().0));
+        assert!(manifest.entries[5].optional);
+        assert_eq!(manifest.entries[6].mode, None);
+    }
+
+    #[test]
+    fn test_full_path_format() {
+        assert_eq!(manifest(FULL_PATH).to_spec(), manifest(CLASSIC).to_spec());
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let err = Manifest::parse(". type=dir\n..\n..\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(Manifest::parse("a mode=0644\n")
+            .unwrap_err()
+            .message
+            .contains("no type"));
+        assert!(Manifest::parse("a type=door\n").is_err());
+        assert!(Manifest::parse("a type=file mode=0999\n").is_err());
+        assert!(Manifest::parse("./../x type=file\n").is_err());
+        assert!(Manifest::parse("/include other\n").is_err());
+
+        let link = Manifest::parse("l type=link link=target\n").unwrap();
+        assert!(link.to_spec().unwrap_err().contains("cannot create a link"));
+    }
+
+    #[test]
+    fn test_creates_layout_with_modes() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let request = CreateRequest {
+            dirs: vec![root.clone()],
+            spec: Some(manifest(CLASSIC).to_spec().unwrap()),
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(report.is_success());
+
+        assert_eq!(mode(&root), 0o755);
+        assert_eq!(mode(&root.join("bin")), 0o750);
+        assert_eq!(mode(&root.join("bin/run me")), 0o755);
+        assert_eq!(mode(&root.join("bin/tool")), 0o700);
+        assert_eq!(mode(&root.join("etc/conf")), 0o644);
+        assert!(root.join("share").is_dir());
+
+        let manifest = manifest(CLASSIC);
+        assert_eq!(manifest.verify(&root), vec![]);
+    }
+
+    #[test]
+    fn test_existing_files_keep_their_contents() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("README.md"), "# Keep me\n").unwrap();
+        let text = ". type=dir\nREADME.md type=file mode=0600\ndocs/new.md type=file\n";
+        let request = CreateRequest {
+            dirs: vec![root.clone()],
+            spec: Some(manifest(text).to_spec().unwrap()),
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(report.is_success());
+
+        assert_eq!(
+            fs::read_to_string(root.join("README.md")).unwrap(),
+            "# Keep me\n"
+        );
+        assert_eq!(mode(&root.join("README.md")), 0o600);
+        assert_eq!(fs::read_to_string(root.join("docs/new.md")).unwrap(), "");
+        let written: Vec<&PathBuf> = report.targets.iter().flat_map(|t| &t.files).collect();
+        assert_eq!(written, vec![&root.join("docs/new.md")]);
+    }
+
+    #[test]
+    fn test_owners() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("file"), "").unwrap();
+        let (uid, gid) = ids();
+
+        let text = format!(". type=dir uid={} gid={}\n", uid, gid);
+        assert_eq!(manifest(&text).verify(root), vec![]);
+        let spec = manifest(&text).to_spec().unwrap();
+        assert_eq!(spec.root.owner.uid(), Some(uid));
+        assert_eq!(spec.root.owner.gid(), Some(gid));
+
+        let text = format!("file type=file uid={}\n", uid + 1);
+        let problems = manifest(&text).verify(root);
+        assert_eq!(
+            problems[0].to_string(),
+            format!(
+                "{} is owned by {}:{}, expected {}",
+                root.join("file").display(),
+                uid,
+                gid,
+                uid + 1
+            )
+        );
+
+        // An unknown name falls back to the id, if there is one
+        let text = format!("file type=file uname=makedir-nobody uid={}\n", uid);
+        assert_eq!(manifest(&text).verify(root), vec![]);
+        let unknown = manifest("file type=file uname=makedir-nobody\n");
+        assert!(matches!(
+            unknown.verify(root)[0],
+            Problem::UnknownOwner { .. }
+        ));
+        assert!(unknown
+            .to_spec()
+            .unwrap_err()
+            .contains("unknown user `makedir-nobody`"));
+    }
+
+    #[test]
+    fn test_verify_reports_drift() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::set_permissions(root, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(root.join("bin"), fs::Permissions::from_mode(0o700)).unwrap();
+        fs::write(root.join("bin/tool"), "").unwrap();
+        fs::set_permissions(root.join("bin/tool"), fs::Permissions::from_mode(0o700)).unwrap();
+        symlink("tool", root.join("bin/run me")).unwrap();
+        fs::create_dir(root.join("etc")).unwrap();
+        fs::set_permissions(root.join("etc"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let problems = manifest(CLASSIC).verify(root);
+        assert_eq!(
+            problems,
+            vec![
+                Problem::Mode {
+                    path: root.join("bin"),
+                    expected: 0o750,
+                    found: 0o700
+                },
+                Problem::WrongKind {
+                    path: root.join("bin/run me"),
+                    expected: Kind::File,
+                    found: Kind::Link
+                },
+                Problem::Missing {
+                    path: root.join("share"),
+                    kind: Kind::Dir
+                },
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            format!(
+                "{} has permissions 700, expected 750",
+                root.join("bin").display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_cli_builds_in_current_directory_by_default() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("layout.mtree");
+        fs::write(&path, with_ids(FULL_PATH)).unwrap();
+        let path = path.to_str().unwrap();
+
+        let parsed = parse_args(&args(&["--mtree", path]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from(".")]);
+
+        let parsed = parse_args(&args(&["srv", "--mtree", path]));
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from("srv")]);
+
+        let parsed = parse_args(&args(&["--mtree", "missing.mtree"]));
+        assert!(parsed.errors[0].contains("Invalid mtree spec"));
+    }
+
+    #[test]
+    fn test_verify_command() {
+        let temp_dir = tempdir().unwrap();
+        let spec = temp_dir.path().join("layout.mtree");
+        fs::write(&spec, ". type=dir\nsub type=dir mode=0711\n").unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::set_permissions(root.join("sub"), fs::Permissions::from_mode(0o711)).unwrap();
+
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let verify = |args: &[&dyn AsRef<std::ffi::OsStr>]| {
+            Command::new("cargo")
+                .arg("run")
+                .arg("--manifest-path")
+                .arg(&manifest)
+                .arg("--")
+                .args(["verify", "--mtree"])
+                .args(args.iter().map(|arg| arg.as_ref()))
+                .output()
+                .unwrap()
+        };
+
+        let output = verify(&[&spec, &root]);
+        assert_eq!(output.status.code(), Some(exit_code::SUCCESS));
+
+        fs::set_permissions(root.join("sub"), fs::Permissions::from_mode(0o755)).unwrap();
+        let output = verify(&[&spec, &root]);
+        assert_eq!(output.status.code(), Some(exit_code::MISMATCH));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("has permissions 755, expected 711"));
+
+        let output = verify(&[&spec]);
+        assert_eq!(output.status.code(), Some(exit_code::MISMATCH));
+        let output = verify(&[]);
+        assert_eq!(output.status.code(), Some(exit_code::USAGE));
+
+        // Global flags work like they do for a normal run
+        let output = verify(&[&spec, &root, &"--color=never", &"--output", &"json"]);
+        assert_eq!(output.status.code(), Some(exit_code::MISMATCH));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!(
+                "{{\"success\":false,\"exit_code\":4,\"problems\":[\"{}/sub has permissions 755, expected 711\"]}}\n",
+                root.display()
+            )
+        );
+        let output = verify(&[&spec, &root, &"--git"]);
+        assert_eq!(output.status.code(), Some(exit_code::USAGE));
+    }
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use makedir::cli::parse_args;
+use makedir::outline::parse;
+use makedir::spec::{Node, Spec};
+use tempfile::tempdir;
+
+use common::args;
+
+const TREE_OUTPUT: &str = "\
+billing
+├── README.md
+├── docs/
+├── src
+│   ├── api
+│   │   └── mod.rs
+│   └── main.rs
+└── link -> src/main.rs
+
+4 directories, 4 files
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(node: &Node) -> (Vec<&str>, Vec<&str>) {
+        (
+            node.dirs.iter().map(|(name, _)| name.as_str()).collect(),
+            node.files.iter().map(|file| file.path.as_str()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_tree_output() {
+        let spec = parse(TREE_OUTPUT).unwrap();
+        assert_eq!(spec.name.as_deref(), Some("billing"));
+        assert_eq!(
+            names(&spec.root),
+            (vec!["docs", "src"], vec!["README.md", "link"])
+        );
+        let src = &spec.root.dirs[1].1;
+        assert_eq!(names(src), (vec!["api"], vec!["main.rs"]));
+        assert_eq!(names(&src.dirs[0].1), (vec![], vec!["mod.rs"]));
+        assert!(spec.root.files.iter().all(|file| file.contents.is_none()));
+    }
+
+    #[test]
+    fn test_ascii_tree_output() {
+        let ascii = "\
+.
+|-- Cargo.toml
+`-- src
+    `-- lib.rs
+";
+        let spec = parse(ascii).unwrap();
+        assert_eq!(spec.name, None);
+        assert_eq!(names(&spec.root), (vec!["src"], vec!["Cargo.toml"]));
+        assert_eq!(names(&spec.root.dirs[0].1), (vec![], vec!["lib.rs"]));
+    }
+
+    #[test]
+    fn test_indented_outline() {
+        let outline = "\
+```
+api/
+  handlers/
+    users.rs
+  main.rs
+web/
+\tpublic/
+notes.txt
+```
+";
+        let spec = parse(outline).unwrap();
+        assert_eq!(spec.name, None);
+        assert_eq!(names(&spec.root), (vec!["api", "web"], vec!["notes.txt"]));
+        let api = &spec.root.dirs[0].1;
+        assert_eq!(names(api), (vec!["handlers"], vec!["main.rs"]));
+        assert_eq!(names(&spec.root.dirs[1].1), (vec!["public"], vec![]));
+    }
+
+    #[test]
+    fn test_invalid_outlines() {
+        assert!(parse("\n\n").unwrap_err().message.contains("empty"));
+        let err = parse("src/\n  ../escape\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(parse("/etc/\nfile\n").is_err());
+    }
+
+    #[test]
+    fn test_cli_reads_outline_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("layout.txt");
+        fs::write(&path, TREE_OUTPUT).unwrap();
+        let path = path.to_str().unwrap();
+
+        let parsed = parse_args(&args(&["--from-tree", path]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from("billing")]);
+        assert_eq!(parsed.request.spec, Some(parse(TREE_OUTPUT).unwrap()));
+
+        let parsed = parse_args(&args(&["--from-tree", path, "--spec", "x.yaml"]));
+        assert!(!parsed.errors.is_empty());
+        let parsed = parse_args(&args(&["--from-tree", "missing.txt"]));
+        assert!(parsed.errors[0].contains("Invalid tree"));
+    }
+
+    #[test]
+    fn test_cli_reads_outline_from_stdin() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let mut child = Command::new("cargo")
+            .arg("run")
+            .arg("--manifest-path")
+            .arg(manifest)
+            .arg("--")
+            .args(["app", "--from-tree", "-"])
+            .current_dir(temp_dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(TREE_OUTPUT.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+
+        let app = temp_dir.path().join("app");
+        assert!(app.join("docs").is_dir());
+        assert!(app.join("src/api/mod.rs").is_file());
+        assert_eq!(fs::read_to_string(app.join("README.md")).unwrap(), "");
+        assert!(!temp_dir.path().join("billing").exists());
+    }
+
+    #[test]
+    fn test_cli_keeps_existing_files() {
+        let temp_dir = tempdir().unwrap();
+        let app = temp_dir.path().join("app");
+        fs::create_dir(&app).unwrap();
+        fs::write(app.join("README.md"), "important\n").unwrap();
+        let path = temp_dir.path().join("layout.txt");
+        fs::write(&path, TREE_OUTPUT).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_makedir"))
+            .arg("app")
+            .arg("--from-tree")
+            .arg(&path)
+            .current_dir(temp_dir.path())
+            .env("XDG_CONFIG_HOME", temp_dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            fs::read_to_string(app.join("README.md")).unwrap(),
+            "important\n"
+        );
+        assert_eq!(fs::read_to_string(app.join("src/main.rs")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_spec_equivalence() {
+        let outline = parse("src/\n  main.rs\n").unwrap();
+        let spec = Spec::parse(
+            "name: src\nfiles:\n  main.rs: {}\n",
+            makedir::spec::Format::Yaml,
+            Path::new("."),
+        )
+        .unwrap();
+        assert_eq!(outline, spec);
+    }
+}
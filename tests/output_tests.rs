@@ -0,0 +1,182 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use makedir::cli::parse_args;
+use makedir::mode::Mode;
+use makedir::output::{self, Event, Format};
+use makedir::toml::{Table, Value};
+use makedir::{config, execute, json, CommandSpec, Plan, Step, Target};
+use tempfile::tempdir;
+
+use common::{args, sh};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(table: &'a Table, path: &[&str]) -> &'a Value {
+        let (last, parents) = path.split_last().unwrap();
+        let table = parents.iter().fold(table, |table, key| {
+            table.get(key).unwrap().as_table().unwrap()
+        });
+        table.get(last).unwrap()
+    }
+
+    #[test]
+    fn test_json_writer_round_trips() {
+        let mut table = Table::new();
+        table.insert("name", Value::String("a \"b\"\\\n\tc\u{1}".to_string()));
+        table.insert("count", Value::Integer(-3));
+        table.insert(
+            "list",
+            Value::Array(vec![Value::Boolean(true), Value::Table(Table::new())]),
+        );
+        let text = json::to_string(&Value::Table(table.clone()));
+        assert_eq!(
+            text,
+            r#"{"name":"a \"b\"\\\n\tc\u0001","count":-3,"list":[true,{}]}"#
+        );
+        assert_eq!(json::parse(&text).unwrap(), table);
+    }
+
+    #[test]
+    fn test_report_covers_modes_files_and_commands() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("project");
+        let plan = Plan {
+            targets: vec![Target {
+                dir: dir.clone(),
+                mode: Some(Mode::Octal(0o750)),
+                steps: vec![
+                    Step::WriteFile {
+                        name: "README.md".to_string(),
+                        contents: "# project\n".to_string(),
+                    },
+                    sh("echo out; echo err >&2; exit 3"),
+                ],
+            }],
+            ..Default::default()
+        };
+        let report = execute(&plan);
+
+        let target = &report.targets[0];
+        assert_eq!(target.status(), "failed");
+        assert_eq!(target.mode, Some(0o750));
+        assert_eq!(target.files, vec![dir.join("README.md")]);
+        assert_eq!(target.commands[0].exit_code, Some(3));
+        assert_eq!(target.commands[0].stdout, "out\n");
+        assert_eq!(target.commands[0].stderr, "err\n");
+
+        let json = json::parse(&output::report_json(&report)).unwrap();
+        assert_eq!(get(&json, &["success"]), &Value::Boolean(false));
+        assert_eq!(get(&json, &["exit_code"]), &Value::Integer(3));
+        let directory = get(&json, &["directories"]).as_array().unwrap()[0]
+            .as_table()
+            .unwrap();
+        assert_eq!(get(directory, &["status"]).as_str(), Some("failed"));
+        assert_eq!(get(directory, &["mode"]).as_str(), Some("0750"));
+        let command = get(directory, &["commands"]).as_array().unwrap()[0]
+            .as_table()
+            .unwrap();
+        assert_eq!(
+            get(command, &["command"]).as_str(),
+            Some("sh -c 'echo out; echo err >&2; exit 3'")
+        );
+        assert_eq!(get(command, &["exit_code"]).as_integer(), Some(3));
+        assert_eq!(get(command, &["stderr"]).as_str(), Some("err\n"));
+        let error = get(directory, &["errors"]).as_array().unwrap()[0]
+            .as_table()
+            .unwrap();
+        assert_eq!(get(error, &["kind"]).as_str(), Some("initializer"));
+    }
+
+    #[test]
+    fn test_commands_that_cannot_start() {
+        let temp_dir = tempdir().unwrap();
+        let plan = Plan {
+            targets: vec![Target {
+                dir: temp_dir.path().join("project"),
+                mode: None,
+                steps: vec![Step::Run(CommandSpec::new("makedir-no-such-tool", &[]))],
+            }],
+            ..Default::default()
+        };
+        let report = execute(&plan);
+        let command = &report.targets[0].commands[0];
+        assert_eq!(command.exit_code, None);
+        assert!(command.error.is_some());
+
+        let json = output::report_json(&report);
+        assert!(json.contains(r#""error":"#));
+        assert!(!json.contains("exit_code\":null"));
+    }
+
+    #[test]
+    fn test_events_are_single_lines() {
+        let event = Event::Mode {
+            path: Path::new("a\nb"),
+            mode: 0o2775,
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"mode","path":"a\nb","mode":"2775"}"#
+        );
+    }
+
+    #[test]
+    fn test_cli_options() {
+        let parsed = parse_args(&args(&["a", "--output", "json", "--events=jsonl"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.output, Format::Json);
+        assert!(parsed.request.events);
+
+        let parsed = parse_args(&args(&["a", "--output=text"]));
+        assert_eq!(parsed.request.output, Format::Text);
+        assert!(!parsed.request.events);
+
+        let parsed = parse_args(&args(&["a", "--output", "yaml"]));
+        assert!(parsed.errors[0].contains("expected text or json"));
+        let parsed = parse_args(&args(&["a", "--events", "json"]));
+        assert!(parsed.errors[0].contains("expected jsonl"));
+    }
+
+    #[test]
+    fn test_stdout_holds_only_json() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("a/b");
+        let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let mut command = Command::new("cargo");
+        command
+            .arg("run")
+            .arg("--manifest-path")
+            .arg(&manifest)
+            .arg("--")
+            .arg(&dir)
+            .args(["-700", "--verbose", "--events", "jsonl", "--output", "json"])
+            .env("XDG_CONFIG_HOME", temp_dir.path());
+        for name in config::ENV_VARS {
+            command.env_remove(name);
+        }
+        let output = command.output().unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let lines: Vec<Table> = stdout.lines().map(|l| json::parse(l).unwrap()).collect();
+        let events: Vec<&str> = lines[..lines.len() - 1]
+            .iter()
+            .map(|line| get(line, &["event"]).as_str().unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            vec!["start", "created", "created", "mode", "finish"]
+        );
+        let report = lines.last().unwrap();
+        assert_eq!(get(report, &["success"]), &Value::Boolean(true));
+
+        // Verbose messages move to stderr
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Set permissions 700"));
+    }
+}
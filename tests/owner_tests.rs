@@ -0,0 +1,185 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use makedir::cli::parse_args;
+use makedir::dry_run::describe;
+use makedir::mode::{Mode, ParentsMode};
+use makedir::owner::{Id, Owner};
+use makedir::{execute, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str, id: u32) -> Option<Id> {
+        Some(Id {
+            name: name.to_string(),
+            id,
+        })
+    }
+
+    fn is_root() -> bool {
+        fs::metadata("/proc/self")
+            .map(|m| m.uid() == 0)
+            .unwrap_or(false)
+    }
+
+    /// A group the current user may give directories to without privileges.
+    fn own_group(path: &Path) -> Owner {
+        let gid = fs::metadata(path).unwrap().gid();
+        Owner {
+            user: None,
+            group: id(&gid.to_string(), gid),
+        }
+    }
+
+    #[test]
+    fn test_parse_names_and_ids() {
+        let root = Owner::parse("root").unwrap();
+        assert_eq!(root.uid(), Some(0));
+        assert_eq!(root.gid(), None);
+
+        assert_eq!(Owner::parse("0").unwrap().uid(), Some(0));
+
+        let owner = Owner::parse("root:0").unwrap();
+        assert_eq!((owner.uid(), owner.gid()), (Some(0), Some(0)));
+        assert_eq!(owner.to_string(), "root:0");
+
+        // `user:` means the user's login group
+        let owner = Owner::parse("root:").unwrap();
+        assert_eq!(owner.gid(), Some(0));
+
+        let owner = Owner::parse(":1234").unwrap();
+        assert_eq!((owner.uid(), owner.gid()), (None, Some(1234)));
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let err = Owner::parse("no-such-user-here").unwrap_err();
+        assert!(err.contains("unknown user `no-such-user-here`"));
+        let err = Owner::parse("root:no-such-group-here").unwrap_err();
+        assert!(err.contains("unknown group `no-such-group-here`"));
+        assert!(Owner::parse("").is_err());
+        assert!(Owner::parse(":").is_err());
+    }
+
+    #[test]
+    fn test_cli_options() {
+        let parsed = parse_args(&args(&["srv/team", "--owner", "root:0", "--shared"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.owner.user, id("root", 0));
+        assert_eq!(parsed.request.owner.group, id("0", 0));
+        assert!(parsed.request.shared);
+
+        // --group and a user-only --owner combine in either order
+        let parsed = parse_args(&args(&["srv/team", "--group", "1234", "--owner", "0"]));
+        assert_eq!(parsed.request.owner.to_string(), "0:1234");
+
+        let parsed = parse_args(&args(&["srv/team", "--owner", "no-such-user-here"]));
+        assert!(parsed.errors[0].starts_with("--owner: unknown user"));
+        let parsed = parse_args(&args(&["srv/team", "--group", "no-such-group-here"]));
+        assert!(parsed.errors[0].starts_with("--group: unknown group"));
+        let parsed = parse_args(&args(&["srv/team", "--group"]));
+        assert!(!parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_shared_directories() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let request = CreateRequest {
+            dirs: vec![root.join("team/project")],
+            owner: own_group(root),
+            shared: true,
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(report.is_success());
+
+        for dir in ["team", "team/project"] {
+            let metadata = fs::metadata(root.join(dir)).unwrap();
+            assert_eq!(metadata.gid(), fs::metadata(root).unwrap().gid());
+            assert_eq!(metadata.permissions().mode() & 0o2070, 0o2070);
+        }
+    }
+
+    #[test]
+    fn test_shared_keeps_setgid_after_mode() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("team");
+        let request = CreateRequest {
+            dirs: vec![dir.clone()],
+            mode: Some(Mode::Octal(0o750)),
+            parents_mode: ParentsMode::All,
+            shared: true,
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o2770);
+    }
+
+    #[test]
+    fn test_owner_change_as_root() {
+        if !is_root() {
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("a/b");
+        let request = CreateRequest {
+            dirs: vec![dir.clone()],
+            owner: Owner::parse("65534:65534").unwrap(),
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+        for path in [&dir, &temp_dir.path().join("a")] {
+            let metadata = fs::metadata(path).unwrap();
+            assert_eq!((metadata.uid(), metadata.gid()), (65534, 65534));
+        }
+    }
+
+    #[test]
+    fn test_failures_are_reported() {
+        if is_root() {
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let request = CreateRequest {
+            dirs: vec![temp_dir.path().join("team")],
+            owner: Owner::parse("0").unwrap(),
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let request = CreateRequest {
+            dirs: vec!["srv/team".into()],
+            owner: Owner::parse(":1234").unwrap(),
+            mode: Some(Mode::Octal(0o770)),
+            shared: true,
+            ..Default::default()
+        };
+        let lines = describe(&Plan::new(&request).unwrap());
+        assert_eq!(
+            lines,
+            vec![
+                "create directory srv",
+                "create directory srv/team",
+                "set owner :1234 on srv",
+                "set permissions g+ws on srv",
+                "set owner :1234 on srv/team",
+                "set permissions 770 on srv/team",
+                "set permissions g+ws on srv/team",
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use makedir::cli::parse_args;
+use makedir::{config, execute_parallel_with, Action, CommandSpec, CreateRequest, Plan, Runner};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes a while for every command and tracks how many run at once.
+    /// Commands in a directory named `bad` fail right away.
+    #[derive(Default)]
+    struct SlowRunner {
+        running: AtomicUsize,
+        most: AtomicUsize,
+    }
+
+    impl Runner for SlowRunner {
+        fn run(&self, _command: &CommandSpec, dir: &Path) -> io::Result<Output> {
+            let status = if dir.ends_with("bad") {
+                ExitStatus::from_raw(1 << 8)
+            } else {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.most.fetch_max(running, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(100));
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                ExitStatus::from_raw(0)
+            };
+            Ok(Output {
+                status,
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    fn request(dirs: Vec<PathBuf>, jobs: usize) -> CreateRequest {
+        CreateRequest {
+            dirs,
+            actions: vec![Action::Git],
+            jobs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_directories_run_concurrently() {
+        let temp_dir = tempdir().unwrap();
+        let dirs: Vec<PathBuf> = (0..6)
+            .map(|i| temp_dir.path().join(i.to_string()))
+            .collect();
+        let runner = SlowRunner::default();
+        let report = execute_parallel_with(&Plan::new(&request(dirs.clone(), 3)).unwrap(), &runner);
+
+        assert!(report.is_success());
+        let most = runner.most.load(Ordering::SeqCst);
+        assert!(most > 1 && most <= 3, "{} commands ran at once", most);
+        // The report keeps plan order whatever finished first
+        let reported: Vec<PathBuf> = report.targets.iter().map(|t| t.dir.clone()).collect();
+        assert_eq!(reported, dirs);
+    }
+
+    #[test]
+    fn test_one_job_is_sequential() {
+        let temp_dir = tempdir().unwrap();
+        let dirs: Vec<PathBuf> = (0..3)
+            .map(|i| temp_dir.path().join(i.to_string()))
+            .collect();
+        let runner = SlowRunner::default();
+        assert!(
+            execute_parallel_with(&Plan::new(&request(dirs, 1)).unwrap(), &runner).is_success()
+        );
+        assert_eq!(runner.most.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_nested_directories_are_not_run_together() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("shared");
+        // All of them would create `shared`, and b is inside a
+        let dirs = vec![root.join("a"), root.join("a/b"), root.join("c")];
+        let runner = SlowRunner::default();
+        let report = execute_parallel_with(&Plan::new(&request(dirs, 4)).unwrap(), &runner);
+
+        assert!(report.is_success());
+        assert_eq!(runner.most.load(Ordering::SeqCst), 1);
+        assert!(report.targets.iter().all(|target| target.created));
+    }
+
+    #[test]
+    fn test_fail_fast_starts_nothing_after_a_failure() {
+        let temp_dir = tempdir().unwrap();
+        let mut dirs = vec![temp_dir.path().join("bad")];
+        dirs.extend((0..6).map(|i| temp_dir.path().join(i.to_string())));
+        let request = CreateRequest {
+            fail_fast: true,
+            ..request(dirs.clone(), 2)
+        };
+        let report = execute_parallel_with(&Plan::new(&request).unwrap(), &SlowRunner::default());
+
+        assert!(!report.is_success());
+        assert_eq!(report.targets[0].dir, dirs[0]);
+        assert!(!report.skipped.is_empty());
+        // Every directory is either reported or skipped, in plan order
+        let mut seen: Vec<PathBuf> = report.targets.iter().map(|t| t.dir.clone()).collect();
+        seen.extend(report.skipped.iter().cloned());
+        assert_eq!(seen, dirs);
+    }
+
+    #[test]
+    fn test_cli_options() {
+        assert_eq!(parse_args(&args(&["a", "-j", "4"])).request.jobs, 4);
+        assert_eq!(parse_args(&args(&["a", "-j8"])).request.jobs, 8);
+        assert_eq!(parse_args(&args(&["a", "--jobs=2"])).request.jobs, 2);
+        assert_eq!(parse_args(&args(&["a", "--jobs", "3"])).request.jobs, 3);
+
+        let parsed = parse_args(&args(&["a", "-j", "0"]));
+        assert!(parsed.errors[0].contains("greater than 0"));
+        let parsed = parse_args(&args(&["a", "-jx"]));
+        assert!(parsed.errors[0].starts_with("--jobs:"));
+        let parsed = parse_args(&args(&["a", "-j"]));
+        assert_eq!(parsed.errors, vec!["-j requires a number of jobs"]);
+    }
+
+    #[test]
+    fn test_output_is_grouped_and_summary_ordered() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let mut names = Vec::new();
+        for i in 0..8 {
+            let dir = if i % 3 == 0 {
+                file.join(i.to_string())
+            } else {
+                temp_dir.path().join(i.to_string())
+            };
+            names.push(dir.to_string_lossy().into_owned());
+        }
+
+        let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let mut command = Command::new("cargo");
+        command
+            .arg("run")
+            .arg("--quiet")
+            .arg("--manifest-path")
+            .arg(&manifest)
+            .arg("--")
+            .args(&names)
+            .args(["--readme", "--docker", "-v", "-j", "4", "--color=never"])
+            .env("XDG_CONFIG_HOME", temp_dir.path());
+        for name in config::ENV_VARS {
+            command.env_remove(name);
+        }
+        let output = command.output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+
+        // Each directory's three lines come together
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 5 * 3);
+        for group in lines.chunks(3) {
+            let dir = group[0].strip_prefix("Creating directory: ").unwrap();
+            let name = Path::new(dir).file_name().unwrap().to_str().unwrap();
+            for line in &group[1..] {
+                assert!(line.ends_with(&format!("/{}.", name)), "{:?}", group);
+            }
+        }
+
+        // The summary lists failures in command line order
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let summary: Vec<&str> = stderr
+            .lines()
+            .skip_while(|line| !line.contains("directories failed"))
+            .collect();
+        assert_eq!(summary[0], "3 of 8 directories failed:");
+        for (line, i) in summary[1..].iter().zip([0, 3, 6]) {
+            assert!(line.starts_with(&format!("  {}:", names[i])), "{}", line);
+        }
+    }
+}
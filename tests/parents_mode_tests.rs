@@ -0,0 +1,122 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use makedir::cli::parse_args;
+use makedir::dry_run::describe;
+use makedir::mode::{Mode, ParentsMode};
+use makedir::{execute, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::{args, mode};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(root: &Path, parents_mode: ParentsMode) {
+        let request = CreateRequest {
+            dirs: vec![root.join("a/b/c")],
+            mode: Some(Mode::Octal(0o700)),
+            parents_mode,
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+    }
+
+    #[test]
+    fn test_leaf_only_by_default() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        create(root, ParentsMode::default());
+        assert_eq!(mode(&root.join("a/b/c")), 0o700);
+        assert_ne!(mode(&root.join("a/b")), 0o700);
+        assert_ne!(mode(&root.join("a")), 0o700);
+    }
+
+    #[test]
+    fn test_all_new_directories() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        create(root, ParentsMode::All);
+        assert_eq!(mode(&root.join("a")), 0o700);
+        assert_eq!(mode(&root.join("a/b")), 0o700);
+        assert_eq!(mode(&root.join("a/b/c")), 0o700);
+    }
+
+    #[test]
+    fn test_separate_mode_for_parents() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        create(root, ParentsMode::Mode(Mode::Octal(0o711)));
+        assert_eq!(mode(&root.join("a")), 0o711);
+        assert_eq!(mode(&root.join("a/b")), 0o711);
+        assert_eq!(mode(&root.join("a/b/c")), 0o700);
+    }
+
+    #[test]
+    fn test_existing_directories_are_left_alone() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("a")).unwrap();
+        fs::set_permissions(root.join("a"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        create(root, ParentsMode::All);
+        assert_eq!(mode(&root.join("a")), 0o755);
+        assert_eq!(mode(&root.join("a/b")), 0o700);
+    }
+
+    #[test]
+    fn test_restrictive_parent_mode_still_applies_to_all() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        // Applied innermost first, so the inner directories stay reachable
+        create(root, ParentsMode::Mode(Mode::Octal(0o300)));
+        assert_eq!(mode(&root.join("a")), 0o300);
+        fs::set_permissions(root.join("a"), fs::Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(mode(&root.join("a/b")), 0o300);
+    }
+
+    #[test]
+    fn test_dry_run_lists_parent_permissions() {
+        let request = CreateRequest {
+            dirs: vec!["x/y".into()],
+            mode: Some(Mode::Octal(0o700)),
+            parents_mode: ParentsMode::All,
+            ..Default::default()
+        };
+        let lines = describe(&Plan::new(&request).unwrap());
+        assert_eq!(
+            lines,
+            vec![
+                "create directory x",
+                "create directory x/y",
+                "set permissions 700 on x",
+                "set permissions 700 on x/y",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cli_policies() {
+        let parsed = parse_args(&args(&["a/b", "--parents-mode", "all"]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.parents_mode, ParentsMode::All);
+
+        let parsed = parse_args(&args(&["a/b", "--parents-mode", "leaf"]));
+        assert_eq!(parsed.request.parents_mode, ParentsMode::Leaf);
+
+        let parsed = parse_args(&args(&["a/b", "--parents-mode", "u=rwx,go=x"]));
+        assert_eq!(
+            parsed.request.parents_mode,
+            ParentsMode::Mode(Mode::parse("u=rwx,go=x").unwrap())
+        );
+
+        let parsed = parse_args(&args(&["a/b", "--parents-mode", "some"]));
+        assert!(parsed.errors[0].contains("expected leaf, all or a mode"));
+        let parsed = parse_args(&args(&["a/b", "--parents-mode"]));
+        assert!(!parsed.errors.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use makedir::cli::parse_args_with_config;
+use makedir::mode::Mode;
+use makedir::{Action, Config};
+use tempfile::tempdir;
+
+use common::{args, makedir};
+
+const PRESETS: &str = r#"
+[preset.base]
+flags = ["git", "readme"]
+
+[preset.rust]
+extends = "base"
+flags = ["cargo", "license"]
+mode = "755"
+
+[preset.private]
+extends = ["rust"]
+mode = "700"
+
+[preset.loop-a]
+extends = "loop-b"
+
+[preset.loop-b]
+extends = "loop-a"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_makedir(config_home: &Path, args: &[&str]) -> Output {
+        makedir(config_home).args(args).output().unwrap()
+    }
+
+    #[test]
+    fn test_preset_extends_parent() {
+        let config = Config::from_toml(PRESETS).unwrap();
+        let rust = config.resolve_preset("rust").unwrap();
+
+        assert_eq!(
+            rust.flags,
+            vec![Action::Git, Action::Readme, Action::Cargo, Action::License]
+        );
+        assert_eq!(rust.mode, Some(Mode::Octal(0o755)));
+        assert_eq!(
+            rust.to_args(),
+            vec!["--git", "--readme", "--cargo", "--license", "-755"]
+        );
+    }
+
+    #[test]
+    fn test_child_mode_overrides_parent() {
+        let config = Config::from_toml(PRESETS).unwrap();
+        let private = config.resolve_preset("private").unwrap();
+
+        assert_eq!(private.flags.len(), 4);
+        assert_eq!(private.mode, Some(Mode::Octal(0o700)));
+    }
+
+    #[test]
+    fn test_preset_errors() {
+        let config = Config::from_toml(PRESETS).unwrap();
+
+        let err = config.resolve_preset("loop-a").unwrap_err();
+        assert!(err.contains("loop-a -> loop-b -> loop-a"), "{}", err);
+        let err = config.resolve_preset("missing").unwrap_err();
+        assert!(err.contains("unknown preset `missing`"));
+        let err = Config::from_toml("[preset.x]\nflag = []\n").unwrap_err();
+        assert!(err.contains("unknown key `flag`"));
+    }
+
+    #[test]
+    fn test_preset_on_command_line() {
+        let config = Config::from_toml(PRESETS).unwrap();
+
+        let long = parse_args_with_config(&args(&["api", "--preset", "rust"]), &config);
+        let short = parse_args_with_config(&args(&["api", "@rust"]), &config);
+        assert_eq!(long.request, short.request);
+        assert_eq!(long.request.mode, Some(Mode::Octal(0o755)));
+        assert_eq!(long.request.actions.len(), 4);
+
+        // Later arguments still win over the preset
+        let parsed = parse_args_with_config(&args(&["api", "@rust", "-750", "--npm"]), &config);
+        assert_eq!(parsed.request.mode, Some(Mode::Octal(0o750)));
+        assert_eq!(parsed.request.actions.last(), Some(&Action::Npm));
+
+        let parsed = parse_args_with_config(&args(&["api", "@nope"]), &config);
+        assert!(parsed.errors[0].contains("unknown preset"));
+        let parsed = parse_args_with_config(&args(&["api", "--preset"]), &config);
+        assert!(parsed.errors[0].contains("requires a preset name"));
+    }
+
+    #[test]
+    fn test_presets_from_cli() {
+        let temp_dir = tempdir().unwrap();
+        let config_home = temp_dir.path().join("config");
+        fs::create_dir_all(config_home.join("makedir")).unwrap();
+        fs::write(
+            config_home.join("makedir/config.toml"),
+            "[preset.docs]\nflags = [\"readme\", \"license\"]\nmode = \"750\"\n",
+        )
+        .unwrap();
+        let test_dir = temp_dir.path().join("preset_cli");
+
+        let output = run_makedir(&config_home, &[test_dir.to_str().unwrap(), "@docs"]);
+        assert!(output.status.success());
+        assert!(test_dir.join("README.md").exists());
+        assert!(test_dir.join("LICENSE").exists());
+
+        let output = run_makedir(&config_home, &["presets", "list"]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("@docs"));
+        assert!(stdout.contains("--readme --license -750"));
+    }
+}
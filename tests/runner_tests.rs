@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+
+use makedir::{execute, execute_with, Action, CommandSpec, CreateRequest, Plan, Runner, Step};
+use tempfile::tempdir;
+
+const HOSTILE_NAMES: [&str; 4] = [
+    "x; touch pwned",
+    "$(touch pwned)",
+    "`touch pwned`",
+    "a' && touch pwned && echo '",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every command instead of running it.
+    #[derive(Default)]
+    struct RecordingRunner {
+        calls: RefCell<Vec<(Vec<String>, PathBuf)>>,
+    }
+
+    impl Runner for RecordingRunner {
+        fn run(&self, command: &CommandSpec, dir: &Path) -> io::Result<Output> {
+            let argv = command.argv().iter().map(|s| s.to_string()).collect();
+            self.calls.borrow_mut().push((argv, dir.to_path_buf()));
+            Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_display_quotes_arguments() {
+        let command = CommandSpec::new("go", &["mod", "init", "x; rm -rf ~"]);
+        assert_eq!(command.to_string(), "go mod init 'x; rm -rf ~'");
+
+        let command = CommandSpec::new("echo", &["it's"]);
+        assert_eq!(command.to_string(), r"echo 'it'\''s'");
+
+        let command = CommandSpec::new("npm", &["init", "-y"]);
+        assert_eq!(command.to_string(), "npm init -y");
+    }
+
+    #[test]
+    fn test_hostile_names_are_passed_as_single_argument() {
+        let temp_dir = tempdir().unwrap();
+
+        for name in HOSTILE_NAMES {
+            let dir = temp_dir.path().join(name);
+            let request = CreateRequest {
+                dirs: vec![dir.clone()],
+                actions: vec![Action::Go, Action::Git],
+                ..Default::default()
+            };
+            let runner = RecordingRunner::default();
+            let report = execute_with(&Plan::new(&request).unwrap(), &runner);
+
+            assert!(report.is_success());
+            let calls = runner.calls.borrow();
+            assert_eq!(
+                calls[0].0,
+                vec!["go", "mod", "init", dir.to_str().unwrap()],
+                "Directory name must be one argv entry"
+            );
+            assert_eq!(calls[1].0, vec!["git", "init"]);
+            assert_eq!(calls[1].1, dir);
+        }
+        assert!(!temp_dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn test_commands_never_go_through_a_shell() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("no_shell");
+
+        let plan = Plan {
+            targets: vec![makedir::Target {
+                dir: test_dir.clone(),
+                mode: None,
+                steps: HOSTILE_NAMES
+                    .iter()
+                    .map(|name| Step::Run(CommandSpec::new("touch", &[name])))
+                    .collect(),
+            }],
+            ..Default::default()
+        };
+        let report = execute(&plan);
+
+        assert!(report.is_success());
+        for name in HOSTILE_NAMES {
+            assert!(
+                test_dir.join(name).exists(),
+                "{} should be a literal file",
+                name
+            );
+        }
+        assert!(!test_dir.join("pwned").exists());
+    }
+
+    #[test]
+    fn test_hostile_directory_names_from_cli() {
+        let temp_dir = tempdir().unwrap();
+
+        for name in HOSTILE_NAMES {
+            let dir = temp_dir.path().join(name);
+            let output = Command::new("cargo")
+                .current_dir(temp_dir.path())
+                .arg("run")
+                .arg("--manifest-path")
+                .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+                .arg("--")
+                .arg(name)
+                .arg("--readme")
+                .arg("--go")
+                .arg("--dry-run")
+                .output()
+                .unwrap();
+
+            assert!(output.status.success());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let expected = CommandSpec::new("go", &["mod", "init", name]).to_string();
+            assert!(stdout.contains(&expected), "{} not in {}", expected, stdout);
+            assert!(!dir.exists());
+        }
+        assert!(!temp_dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn test_verbose_output_shows_argv() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("argv; echo hi");
+
+        let git_check = Command::new("which")
+            .arg("git")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+
+        if !git_check {
+            println!("Skipping argv test as git command is not available");
+            return;
+        }
+
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("--")
+            .arg(test_dir.to_str().unwrap())
+            .arg("--git")
+            .arg("-v")
+            .arg("--color=always")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(test_dir.join(".git").exists());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Successfully executed:\x1b[0m git init in"));
+    }
+}
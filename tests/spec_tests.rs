@@ -0,0 +1,358 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use makedir::cli::parse_args;
+use makedir::mode::Mode;
+use makedir::spec::{Format, Spec};
+use makedir::toml::Value;
+use makedir::{dry_run, execute, json, yaml, Action, CreateRequest, Plan, Step};
+use tempfile::tempdir;
+
+use common::args;
+
+const LAYOUT_YAML: &str = r##"
+# Standard service layout
+name: service
+flags: [readme]
+files:
+  .env: ""
+  docs/notes.md:
+    contents: |
+      # Notes for {{ project }}
+      Owned by {{ author_name | default("nobody") }}
+    mode: "600"
+dirs:
+  src:
+    mode: "750"
+    dirs:
+      api:
+      web: {}
+  scripts:
+    files:
+      deploy.sh: { contents: "#!/bin/sh\necho deploy\n", mode: "755" }
+"##;
+
+const LAYOUT_TOML: &str = r##"
+name = "service"
+flags = ["readme"]
+
+[files]
+".env" = ""
+"docs/notes.md" = { contents = "# Notes for {{ project }}\nOwned by {{ author_name | default(\"nobody\") }}\n", mode = "600" }
+
+[dirs.src]
+mode = "750"
+dirs = { api = {}, web = {} }
+
+[dirs.scripts.files]
+"deploy.sh" = { contents = "#!/bin/sh\necho deploy\n", mode = "755" }
+"##;
+
+const LAYOUT_JSON: &str = r##"{
+  "name": "service",
+  "flags": ["readme"],
+  "files": {
+    ".env": "",
+    "docs/notes.md": {
+      "contents": "# Notes for {{ project }}\nOwned by {{ author_name | default(\"nobody\") }}\n",
+      "mode": "600"
+    }
+  },
+  "dirs": {
+    "src": { "mode": "750", "dirs": { "api": null, "web": {} } },
+    "scripts": {
+      "files": { "deploy.sh": { "contents": "#!/bin/sh\necho deploy\n", "mode": "755" } }
+    }
+  }
+}"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str, format: Format) -> Spec {
+        Spec::parse(text, format, Path::new(".")).unwrap()
+    }
+
+    #[test]
+    fn test_formats_describe_the_same_tree() {
+        let yaml = parse(LAYOUT_YAML, Format::Yaml);
+        assert_eq!(yaml, parse(LAYOUT_TOML, Format::Toml));
+        assert_eq!(yaml, parse(LAYOUT_JSON, Format::Json));
+
+        assert_eq!(yaml.name.as_deref(), Some("service"));
+        assert_eq!(yaml.root.flags, vec![Action::Readme]);
+        assert_eq!(yaml.root.files[1].path, "docs/notes.md");
+        assert_eq!(yaml.root.files[1].mode, Some(Mode::Octal(0o600)));
+        let dirs: Vec<&str> = yaml.root.dirs.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(dirs, vec!["src", "scripts"]);
+        assert_eq!(yaml.root.dirs[0].1.mode, Some(Mode::Octal(0o750)));
+        assert_eq!(yaml.root.dirs[0].1.dirs.len(), 2);
+    }
+
+    #[test]
+    fn test_spec_builds_tree() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("billing");
+        let request = CreateRequest {
+            dirs: vec![root.clone()],
+            author_name: Some("Ada".to_string()),
+            spec: Some(parse(LAYOUT_YAML, Format::Yaml)),
+            ..Default::default()
+        };
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(report.is_success());
+
+        assert!(root.join("README.md").exists());
+        assert_eq!(fs::read_to_string(root.join(".env")).unwrap(), "");
+        assert_eq!(
+            fs::read_to_string(root.join("docs/notes.md")).unwrap(),
+            "# Notes for billing\nOwned by Ada\n"
+        );
+        let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(root.join("docs/notes.md")), 0o600);
+        assert_eq!(mode(root.join("src")), 0o750);
+        assert_eq!(mode(root.join("scripts/deploy.sh")), 0o755);
+        assert!(root.join("src/api").is_dir());
+        assert!(root.join("src/web").is_dir());
+        // Flags belong to the node that lists them
+        assert!(!root.join("src/README.md").exists());
+    }
+
+    #[test]
+    fn test_node_flags_and_dry_run() {
+        let spec = parse(
+            "dirs:\n  api:\n    flags: [git]\n  web:\n    flags: [npm]\n",
+            Format::Yaml,
+        );
+        let request = CreateRequest {
+            dirs: vec!["svc".into()],
+            actions: vec![Action::Readme],
+            mode: Some(Mode::Octal(0o700)),
+            spec: Some(spec),
+            ..Default::default()
+        };
+        let plan = Plan::new(&request).unwrap();
+        let dirs: Vec<&Path> = plan.targets.iter().map(|t| t.dir.as_path()).collect();
+        assert_eq!(
+            dirs,
+            vec![Path::new("svc"), Path::new("svc/api"), Path::new("svc/web")]
+        );
+        assert!(plan
+            .targets
+            .iter()
+            .all(|t| t.mode == Some(Mode::Octal(0o700))));
+        assert!(matches!(
+            plan.targets[0].steps[..],
+            [Step::WriteFile { .. }]
+        ));
+
+        let lines = dry_run::describe(&plan);
+        assert!(lines.contains(&"run `git init` in svc/api".to_string()));
+        assert!(lines.contains(&"run `npm init -y` in svc/web".to_string()));
+    }
+
+    #[test]
+    fn test_files_without_contents_keep_existing_ones() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("billing");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("keep.txt"), "important\n").unwrap();
+        let request = CreateRequest {
+            dirs: vec![root.clone()],
+            spec: Some(parse(
+                "files:\n  keep.txt: { mode: \"640\" }\n  new.txt: {}\n",
+                Format::Yaml,
+            )),
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+
+        assert_eq!(
+            fs::read_to_string(root.join("keep.txt")).unwrap(),
+            "important\n"
+        );
+        let mode = fs::metadata(root.join("keep.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o640);
+        assert_eq!(fs::read_to_string(root.join("new.txt")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_templates_are_read_relative_to_spec() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("templates")).unwrap();
+        fs::write(
+            temp_dir.path().join("templates/notes.md"),
+            "notes for {{ project }}\n",
+        )
+        .unwrap();
+        let spec_path = temp_dir.path().join("layout.yml");
+        fs::write(
+            &spec_path,
+            "files:\n  NOTES.md:\n    template: templates/notes.md\n",
+        )
+        .unwrap();
+
+        let spec = Spec::load(&spec_path).unwrap();
+        let request = CreateRequest {
+            dirs: vec!["api".into()],
+            spec: Some(spec),
+            ..Default::default()
+        };
+        let plan = Plan::new(&request).unwrap();
+        assert_eq!(
+            plan.targets[0].steps,
+            vec![Step::WriteFile {
+                name: "NOTES.md".to_string(),
+                contents: "notes for api\n".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_specs_are_rejected() {
+        let err = Spec::parse(
+            "dirs:\n  src:\n    colour: red\n",
+            Format::Yaml,
+            Path::new("."),
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown key `dirs.src.colour`"));
+
+        let err =
+            Spec::parse("files:\n  ../escape: x\n", Format::Yaml, Path::new(".")).unwrap_err();
+        assert!(err.contains("relative path"));
+
+        assert!(Spec::parse("flags = [\"nope\"]", Format::Toml, Path::new(".")).is_err());
+        assert!(Spec::load(Path::new("layout.txt"))
+            .unwrap_err()
+            .contains("unknown spec format"));
+    }
+
+    #[test]
+    fn test_json_modes_must_be_strings() {
+        let err = Spec::parse(
+            r#"{"dirs": {"keys": {"mode": 700}}}"#,
+            Format::Json,
+            Path::new("."),
+        )
+        .unwrap_err();
+        assert!(err.contains("`dirs.keys.mode` must be a string like \"700\""));
+        assert!(Spec::parse(
+            r#"{"files": {"id": {"contents": "", "mode": 600}}}"#,
+            Format::Json,
+            Path::new(".")
+        )
+        .is_err());
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let spec = r#"{"dirs": {"keys": {"mode": "700", "files": {"id": {"contents": "", "mode": "600"}}}}}"#;
+        let request = CreateRequest {
+            dirs: vec![root.clone()],
+            spec: Some(parse(spec, Format::Json)),
+            ..Default::default()
+        };
+        assert!(execute(&Plan::new(&request).unwrap()).is_success());
+        let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode(root.join("keys")), 0o700);
+        assert_eq!(mode(root.join("keys/id")), 0o600);
+    }
+
+    #[test]
+    fn test_cli_uses_spec_name() {
+        let temp_dir = tempdir().unwrap();
+        let spec_path = temp_dir.path().join("layout.json");
+        fs::write(&spec_path, LAYOUT_JSON).unwrap();
+        let spec = spec_path.to_str().unwrap();
+
+        let parsed = parse_args(&args(&["--spec", spec]));
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.request.dirs, vec![PathBuf::from("service")]);
+
+        let parsed = parse_args(&args(&["a", "b", "--spec", spec]));
+        assert_eq!(parsed.request.dirs.len(), 2);
+
+        let parsed = parse_args(&args(&["a", "--spec", "missing.yaml"]));
+        assert!(parsed.errors[0].contains("Invalid spec"));
+    }
+
+    #[test]
+    fn test_yaml_parser() {
+        let doc = yaml::parse(
+            "---\n\
+             plain: hello world # comment\n\
+             quoted: 'it''s'\n\
+             escaped: \"a\\tb\\u00e9\"\n\
+             url: http://example.com/#x\n\
+             list:\n\
+             - one\n\
+             - [two, three]\n\
+             - key: value\n\
+               \x20 other: 2\n\
+             flow: {a: 1, b: [x, y]}\n\
+             literal: |\n\
+             \x20 line one\n\
+             \x20   indented\n\
+             \n\
+             folded: >-\n\
+             \x20 joined\n\
+             \x20 together\n\
+             empty:\n\
+             yes: true\n",
+        )
+        .unwrap();
+
+        let text = |key: &str| doc.get(key).and_then(Value::as_str).unwrap().to_string();
+        assert_eq!(text("plain"), "hello world");
+        assert_eq!(text("quoted"), "it's");
+        assert_eq!(text("escaped"), "a\tbé");
+        assert_eq!(text("url"), "http://example.com/#x");
+        assert_eq!(text("literal"), "line one\n  indented\n");
+        assert_eq!(text("folded"), "joined together");
+        assert_eq!(doc.get("yes"), Some(&Value::Boolean(true)));
+        assert!(doc.get("empty").unwrap().as_table().unwrap().is_empty());
+
+        let list = doc.get("list").unwrap().as_array().unwrap();
+        assert_eq!(list[0].as_str(), Some("one"));
+        assert_eq!(list[1].as_array().unwrap().len(), 2);
+        let entry = list[2].as_table().unwrap();
+        assert_eq!(entry.get("other").and_then(Value::as_str), Some("2"));
+
+        let flow = doc.get("flow").unwrap().as_table().unwrap();
+        assert_eq!(flow.get("b").unwrap().as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_yaml_errors() {
+        assert!(yaml::parse("a: 1\n  b: 2\n").is_err());
+        assert!(yaml::parse("a: 1\na: 2\n").is_err());
+        assert!(yaml::parse("- a\n- b\n").is_err());
+        let err = yaml::parse("a:\n  b: [1, 2\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn test_json_parser() {
+        let doc = json::parse(
+            r#"{"s": "a\"b\u00e9\ud83d\ude00", "n": -12, "b": false, "z": null, "l": [1, {"k": "v"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.get("s").and_then(Value::as_str), Some("a\"bé😀"));
+        assert_eq!(doc.get("n").and_then(Value::as_integer), Some(-12));
+        assert_eq!(doc.get("b"), Some(&Value::Boolean(false)));
+        assert!(doc.get("z").unwrap().as_table().unwrap().is_empty());
+        assert_eq!(doc.get("l").unwrap().as_array().unwrap().len(), 2);
+
+        assert!(json::parse("{\"a\": 1.5}").is_err());
+        assert!(json::parse("{\"a\": 1,}").is_err());
+        assert!(json::parse("[1]").is_err());
+        assert_eq!(json::parse("{\n\"a\": tru\n}").unwrap_err().line, 2);
+    }
+}
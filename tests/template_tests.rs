@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use makedir::plan::template_context;
+use makedir::template::{self, Context, Value};
+use makedir::{Action, CreateRequest, Plan, Step};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        let mut flags = Context::new();
+        flags.insert("cargo", true);
+        flags.insert("npm", false);
+
+        let mut context = Context::new();
+        context.insert("project", "api");
+        context.insert("empty", "");
+        context.insert("flags", flags);
+        context.insert("tools", Value::List(vec!["git".into(), "cargo".into()]));
+        context
+    }
+
+    fn contents(request: &CreateRequest, name: &str) -> String {
+        let plan = Plan::new(request).unwrap();
+        plan.targets[0]
+            .steps
+            .iter()
+            .find_map(|step| match step {
+                Step::WriteFile { name: n, contents } if n == name => Some(contents.clone()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_variables_and_filters() {
+        let out = template::render(
+            "{{ project }} {{project|upper}} {{ missing | default(\"x\") }} {{ project | json }}",
+            &context(),
+        )
+        .unwrap();
+        assert_eq!(out, "api API x \"api\"");
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let source = "{% if flags.npm %}npm{% elif flags.cargo %}cargo{% else %}none{% endif %}";
+        assert_eq!(template::render(source, &context()).unwrap(), "cargo");
+
+        let source = "{% if not empty %}empty{% endif %}{% if project == \"api\" %}!{% endif %}";
+        assert_eq!(template::render(source, &context()).unwrap(), "empty!");
+    }
+
+    #[test]
+    fn test_loops_and_tag_lines() {
+        let source = "Tools:\n{% for tool in tools %}\n* {{ tool }}\n{% endfor %}\nend\n";
+        assert_eq!(
+            template::render(source, &context()).unwrap(),
+            "Tools:\n* git\n* cargo\nend\n"
+        );
+    }
+
+    #[test]
+    fn test_comments_are_dropped() {
+        let out = template::render("a{# note #}b", &context()).unwrap();
+        assert_eq!(out, "ab");
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let err = template::render("ok\n{{ nope }}", &context()).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("nope"));
+
+        let err = template::render("{% if project %}open", &context()).unwrap_err();
+        assert!(err.message.contains("endif"));
+
+        assert!(template::render("{{ project | shout }}", &context()).is_err());
+    }
+
+    #[test]
+    fn test_context_from_request() {
+        let request = CreateRequest {
+            dirs: vec!["work/api".into()],
+            actions: vec![Action::Git, Action::Cargo, Action::Readme],
+            author_name: Some("Ada".to_string()),
+            ..Default::default()
+        };
+        let context = template_context(Path::new("work/api"), &request);
+        let out = template::render(
+            "{{ project }}|{{ author }}|{{ license }}|{% for t in tools %}{{ t }},{% endfor %}",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(out, "api|Ada|MIT|git,cargo,");
+    }
+
+    #[test]
+    fn test_readme_adapts_to_flags() {
+        let mut request = CreateRequest {
+            dirs: vec!["shop".into()],
+            actions: vec![Action::Cargo, Action::Readme],
+            ..Default::default()
+        };
+        let readme = contents(&request, "README.md");
+        assert!(readme.starts_with("# shop\n"));
+        assert!(readme.contains("cargo run"));
+        assert!(readme.contains("* cargo\n"));
+        assert!(!readme.contains("{{"));
+        assert!(!readme.contains("{%"));
+
+        request.actions = vec![Action::Npm, Action::Readme];
+        let readme = contents(&request, "README.md");
+        assert!(readme.contains("npm start"));
+        assert!(!readme.contains("cargo run"));
+    }
+
+    #[test]
+    fn test_license_fills_year_and_author() {
+        let request = CreateRequest {
+            dirs: vec!["shop".into()],
+            actions: vec![Action::License],
+            author_name: Some("Ada Lovelace".to_string()),
+            ..Default::default()
+        };
+        let license = contents(&request, "LICENSE");
+        let year = template::current_year();
+        assert!(license.contains(&format!("Copyright (c) {} Ada Lovelace", year)));
+
+        let request = CreateRequest {
+            author_name: None,
+            ..request
+        };
+        assert!(contents(&request, "LICENSE").contains("[YOUR NAME]"));
+    }
+
+    #[test]
+    fn test_dockerfile_stack_follows_flags() {
+        let request = CreateRequest {
+            dirs: vec!["svc".into()],
+            actions: vec![Action::Go, Action::Docker],
+            ..Default::default()
+        };
+        assert!(contents(&request, "Dockerfile").contains("ARG STACK=go\n"));
+    }
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use std::fs;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use makedir::cli::parse_args;
+use makedir::runner::{format_duration, parse_duration};
+use makedir::{
+    execute, Action, CommandSpec, Config, CreateRequest, Plan, RunOptions, Runner, Step,
+    SystemRunner, Target,
+};
+use tempfile::tempdir;
+
+use common::args;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> CommandSpec {
+        CommandSpec::new("sh", &["-c", script])
+    }
+
+    fn with_timeout(millis: u64) -> RunOptions<'static> {
+        RunOptions {
+            timeout: Some(Duration::from_millis(millis)),
+            on_line: None,
+        }
+    }
+
+    /// Whether `pid` is gone, or only a zombie waiting to be reaped.
+    fn exited(pid: &str) -> bool {
+        match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat.contains(") Z "),
+            Err(_) => true,
+        }
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_duration("90"), Ok(Some(Duration::from_secs(90))));
+        assert_eq!(parse_duration("90s"), Ok(Some(Duration::from_secs(90))));
+        assert_eq!(
+            parse_duration("250ms"),
+            Ok(Some(Duration::from_millis(250)))
+        );
+        assert_eq!(parse_duration("2m"), Ok(Some(Duration::from_secs(120))));
+        assert_eq!(parse_duration("1h"), Ok(Some(Duration::from_secs(3600))));
+        assert_eq!(parse_duration("0"), Ok(None));
+        assert!(parse_duration("2 m").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("-1").is_err());
+
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn test_commands_cannot_read_stdin() {
+        let temp_dir = tempdir().unwrap();
+        let command = sh("read answer; echo \"[$answer]\"");
+        let output = SystemRunner
+            .run_with(&command, temp_dir.path(), &with_timeout(5000))
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "[]\n");
+    }
+
+    #[test]
+    fn test_commands_run_non_interactively() {
+        let temp_dir = tempdir().unwrap();
+        let command = sh("echo $CI $GIT_TERMINAL_PROMPT $npm_config_yes");
+        let output = SystemRunner.run(&command, temp_dir.path()).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1 0 true\n");
+
+        let request = CreateRequest {
+            dirs: vec!["app".into()],
+            actions: vec![Action::Bun],
+            ..Default::default()
+        };
+        assert!(Plan::new(&request).unwrap().targets[0]
+            .steps
+            .contains(&Step::Run(CommandSpec::new("bun", &["init", "-y"]))));
+    }
+
+    #[test]
+    fn test_timeout_kills_the_process_group() {
+        let temp_dir = tempdir().unwrap();
+        let command = sh("sleep 30 & echo $! > pid; wait");
+        let started = Instant::now();
+        let e = SystemRunner
+            .run_with(&command, temp_dir.path(), &with_timeout(300))
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(e.to_string(), "timed out after 300ms");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The background sleep went down with the shell
+        let pid = fs::read_to_string(temp_dir.path().join("pid")).unwrap();
+        let pid = pid.trim();
+        for _ in 0..100 {
+            if exited(pid) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("process {} outlived the timeout", pid);
+    }
+
+    #[test]
+    fn test_timeout_is_reported() {
+        let temp_dir = tempdir().unwrap();
+        let plan = Plan {
+            targets: vec![Target {
+                dir: temp_dir.path().join("slow"),
+                mode: None,
+                steps: vec![Step::Run(sh("sleep 30")), Step::Run(sh("true"))],
+            }],
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let report = execute(&plan);
+
+        let target = &report.targets[0];
+        assert_eq!(report.exit_code(), 3);
+        assert_eq!(
+            target.errors[0].message,
+            "`sh -c 'sleep 30'` timed out after 200ms and was killed"
+        );
+        assert_eq!(target.commands[0].exit_code, None);
+        assert_eq!(
+            target.commands[0].error.as_deref(),
+            Some("timed out after 200ms")
+        );
+        // Later commands still run, as after any other failure
+        assert_eq!(target.commands[1].exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_settings() {
+        let parsed = parse_args(&args(&["a", "--timeout", "2m"]));
+        assert_eq!(parsed.request.timeout, Some(Duration::from_secs(120)));
+        let parsed = parse_args(&args(&["a", "--timeout=0"]));
+        assert_eq!(parsed.request.timeout, None);
+        let parsed = parse_args(&args(&["a", "--timeout", "soon"]));
+        assert!(parsed.errors[0].starts_with("--timeout: invalid duration `soon`"));
+
+        let config = Config::from_toml("[defaults]\ntimeout = 90\n").unwrap();
+        assert_eq!(config.timeout, Some(Duration::from_secs(90)));
+        assert!(config.to_toml().contains("timeout = \"90s\""));
+        let config = Config::from_toml("[defaults]\ntimeout = \"5m\"\n").unwrap();
+        assert_eq!(config.timeout, Some(Duration::from_secs(300)));
+        assert!(Config::from_toml("[defaults]\ntimeout = true\n").is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_env(|name| (name == "MAKEDIR_TIMEOUT").then(|| "45s".to_string()))
+            .unwrap();
+        assert_eq!(config.timeout, Some(Duration::from_secs(45)));
+        assert!(Config::default()
+            .apply_env(|name| (name == "MAKEDIR_TIMEOUT").then(|| "x".to_string()))
+            .unwrap_err()
+            .starts_with("MAKEDIR_TIMEOUT:"));
+    }
+}
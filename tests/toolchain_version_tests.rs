@@ -0,0 +1,229 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Output;
+
+use makedir::cli::parse_args_with_config;
+use makedir::toolchain::{self, Comparison, Outdated, Requirement, Toolchain, Version};
+use makedir::{Action, Config, CreateRequest, Plan};
+use tempfile::tempdir;
+
+use common::{args, makedir};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn met(constraint: &str, found: &str) -> bool {
+        Requirement::parse("go", constraint)
+            .unwrap()
+            .is_met_by(&version(found))
+    }
+
+    /// Runs the makedir binary in `cwd` with only `cwd/bin` on PATH and
+    /// `config` as the config file.
+    fn run(args: &[&str], cwd: &Path, config: &str, env: &[(&str, &str)]) -> Output {
+        fs::create_dir_all(cwd.join("makedir")).unwrap();
+        fs::write(cwd.join("makedir/config.toml"), config).unwrap();
+        makedir(cwd)
+            .args(args)
+            .current_dir(cwd)
+            .env("PATH", cwd.join("bin"))
+            .envs(env.iter().copied())
+            .output()
+            .unwrap()
+    }
+
+    /// A `go` that reports `version` and succeeds at everything else.
+    fn fake_go(cwd: &Path, version: &str) {
+        let bin = cwd.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let path = bin.join("go");
+        let script = format!(
+            "#!/bin/sh\n[ \"$1\" = version ] && echo 'go version go{} linux/amd64'\nexit 0\n",
+            version
+        );
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_versions_in_tool_output() {
+        let found = |line: &str| Version::find(line).map(|v| v.to_string());
+        assert_eq!(
+            found("go version go1.22.1 linux/amd64").as_deref(),
+            Some("1.22.1")
+        );
+        assert_eq!(
+            found("cargo 1.75.0 (1d8b05cdd 2023-11-20)").as_deref(),
+            Some("1.75.0")
+        );
+        assert_eq!(found("v20.11.1").as_deref(), Some("20.11.1"));
+        assert_eq!(found("git version 2.43.0.").as_deref(), Some("2.43.0"));
+        assert_eq!(found("10.8.2").as_deref(), Some("10.8.2"));
+        assert_eq!(found("no numbers here"), None);
+    }
+
+    #[test]
+    fn test_constraints() {
+        assert!(met("1.22", "1.22.1"));
+        assert!(met(">=1.22", "1.22"));
+        assert!(!met(">=1.22", "1.21.9"));
+        // Components compare as numbers
+        assert!(!met(">=1.22", "1.3"));
+        assert!(met(">= 20", "20.11.1"));
+        assert!(!met(">20", "20.11.1"));
+        assert!(met("<=20", "20.11.1"));
+        assert!(met("<1.22", "1.21.9"));
+        assert!(met("=1.22", "1.22.5"));
+        assert!(!met("==1.22", "1.23"));
+
+        let requirement = Requirement::parse("rust", ">=1.75").unwrap();
+        assert_eq!(requirement.toolchain.program, "rustc");
+        assert_eq!(requirement.comparison, Comparison::AtLeast);
+        assert_eq!(requirement.to_string(), "rust >=1.75");
+        assert!(Requirement::parse("java", "17")
+            .unwrap_err()
+            .starts_with("unknown toolchain `java`, expected one of git, go,"));
+        assert!(Requirement::parse("go", ">=1.x")
+            .unwrap_err()
+            .contains("invalid version `1.x`"));
+    }
+
+    #[test]
+    fn test_only_toolchains_the_plan_uses_are_checked() {
+        let request = CreateRequest {
+            dirs: vec!["web".into()],
+            actions: vec![Action::Pnpm],
+            requires: vec![
+                Requirement::parse("node", "20").unwrap(),
+                Requirement::parse("go", "1.22").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let checks = toolchain::check_versions_with(&Plan::new(&request).unwrap(), |toolchain| {
+            assert_eq!(toolchain.program, "node");
+            Ok(version("18.19.0"))
+        });
+        assert_eq!(checks.len(), 1);
+        assert!(!checks[0].is_met());
+        assert_eq!(
+            checks[0].to_string(),
+            "node 18.19.0 is installed, but >=20 is required"
+        );
+
+        let checks = toolchain::check_versions_with(&Plan::new(&request).unwrap(), |_| {
+            Err("`node` is not on PATH".to_string())
+        });
+        assert_eq!(
+            checks[0].to_string(),
+            "cannot check node >=20: `node` is not on PATH"
+        );
+        assert_eq!(Toolchain::named("node").unwrap().used_by.len(), 3);
+    }
+
+    #[test]
+    fn test_config_and_presets() {
+        let text = "[defaults]\noutdated = \"warn\"\n\n[requires]\nnode = 18\ngo = \">=1.22\"\n\n\
+                    [preset.web]\nflags = [\"npm\"]\n\n[preset.web.requires]\nnode = \">=20\"\n";
+        let config = Config::from_toml(text).unwrap();
+        assert_eq!(config.outdated, Some(Outdated::Warn));
+        assert_eq!(config.requires[0].to_string(), "node >=18");
+        assert_eq!(config.presets[0].requires[0].to_string(), "node >=20");
+        let toml = config.to_toml();
+        assert!(toml.contains("outdated = \"warn\""));
+        assert!(toml.contains("[requires]\nnode = \">=18\"\ngo = \">=1.22\"\n"));
+        assert!(toml.contains("[preset.web.requires]\nnode = \">=20\"\n"));
+        assert_eq!(Config::from_toml(&toml).unwrap(), config);
+
+        // The preset's requirement replaces the config's for the same toolchain
+        let parsed = parse_args_with_config(&args(&["app", "@web"]), &config);
+        let requires: Vec<String> = parsed
+            .request
+            .requires
+            .iter()
+            .map(|requirement| requirement.to_string())
+            .collect();
+        assert_eq!(requires, vec!["go >=1.22", "node >=20"]);
+        assert_eq!(parsed.request.outdated, Outdated::Warn);
+
+        assert!(Config::from_toml("[requires]\ngo = true\n")
+            .unwrap_err()
+            .starts_with("`requires.go` must be a string"));
+        assert!(Config::from_toml("[preset.x.requires]\nperl = \"5\"\n")
+            .unwrap_err()
+            .starts_with("`preset.x.requires.perl`: unknown toolchain"));
+        assert!(Config::from_toml("[defaults]\noutdated = \"ignore\"\n").is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_env(|name| (name == "MAKEDIR_OUTDATED").then(|| "refuse".to_string()))
+            .unwrap();
+        assert_eq!(config.outdated, Some(Outdated::Refuse));
+    }
+
+    #[test]
+    fn test_outdated_toolchain_refuses_or_warns() {
+        let temp_dir = tempdir().unwrap();
+        let cwd = temp_dir.path();
+        fake_go(cwd, "1.21.5");
+        let config = "[requires]\ngo = \"1.22\"\n";
+
+        let output = run(&["api", "--go"], cwd, config, &[]);
+        assert_eq!(output.status.code(), Some(3));
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains("Outdated tool: go 1.21.5 is installed, but >=1.22 is required"));
+        assert!(!cwd.join("api").exists());
+
+        // A refused run still prints its report
+        let output = run(&["api", "--go", "--output", "json"], cwd, config, &[]);
+        assert_eq!(output.status.code(), Some(3));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with("{\"success\":false,\"exit_code\":3,"));
+        assert!(stdout.contains("\"skipped\":[\"api\"]"));
+        assert!(stdout.contains(
+            "\"toolchains\":[{\"toolchain\":\"go\",\"required\":\">=1.22\",\"found\":\"1.21.5\",\"satisfied\":false}]"
+        ));
+        assert!(!cwd.join("api").exists());
+
+        // Requirements only apply to runs that use the toolchain
+        let output = run(&["docs", "--readme"], cwd, config, &[]);
+        assert!(output.status.success());
+
+        let output = run(
+            &["api", "--go", "--output", "json"],
+            cwd,
+            config,
+            &[("MAKEDIR_OUTDATED", "warn")],
+        );
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains("Warning: go 1.21.5 is installed, but >=1.22 is required"));
+        assert!(String::from_utf8_lossy(&output.stdout).contains(
+            "\"toolchains\":[{\"toolchain\":\"go\",\"required\":\">=1.22\",\"found\":\"1.21.5\",\"satisfied\":false}]"
+        ));
+        assert!(cwd.join("api").is_dir());
+    }
+
+    #[test]
+    fn test_satisfied_toolchain_is_reported_when_verbose() {
+        let temp_dir = tempdir().unwrap();
+        let cwd = temp_dir.path();
+        fake_go(cwd, "1.22.1");
+        let config = "[requires]\ngo = \">=1.22\"\n";
+
+        let output = run(&["api", "--go", "-v", "--color=never"], cwd, config, &[]);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .contains("Toolchain: go 1.22.1 satisfies >=1.22\n"));
+
+        let output = run(&["web", "--go"], cwd, config, &[]);
+        assert!(!String::from_utf8_lossy(&output.stdout).contains("Toolchain:"));
+    }
+}
@@ -0,0 +1,258 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Output;
+
+use makedir::mode::Mode;
+use makedir::{execute, Action, CreateRequest, Plan, Step, UserTemplate};
+use tempfile::tempdir;
+
+use common::makedir;
+
+const MANIFEST: &str = r#"
+flags = ["readme"]
+commands = [["touch", "{{ project }}.done"]]
+
+[permissions]
+"bin/dev" = "755"
+"#;
+
+const WORKFLOW: &str = "steps:\n  - run: deploy --token ${{ secrets.TOKEN }}\n";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a template called `name` into `templates`.
+    fn write_template(templates: &Path, name: &str) {
+        let root = templates.join(name);
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("makedir.toml"), MANIFEST).unwrap();
+        fs::write(root.join("bin/dev.tmpl"), "#!/bin/sh\necho {{ project }}\n").unwrap();
+        fs::write(root.join("bin/setup.tmpl"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(
+            root.join("bin/setup.tmpl"),
+            fs::Permissions::from_mode(0o750),
+        )
+        .unwrap();
+        fs::write(root.join("src/{{ project }}.txt.tmpl"), "by {{ author }}\n").unwrap();
+        fs::write(root.join("logo.bin"), [0xff, 0xfe, b'{', b'{']).unwrap();
+        fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        fs::write(root.join(".github/workflows/ci.yml"), WORKFLOW).unwrap();
+        fs::create_dir_all(root.join("logs/{{ project }}")).unwrap();
+    }
+
+    fn run_makedir(cwd: &Path, config_home: &Path, args: &[&str]) -> Output {
+        makedir(config_home)
+            .args(args)
+            .current_dir(cwd)
+            .env("GIT_CONFIG_GLOBAL", config_home.join("gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_reads_files_and_manifest() {
+        let temp_dir = tempdir().unwrap();
+        write_template(temp_dir.path(), "svc");
+        let template = UserTemplate::load("svc", &temp_dir.path().join("svc")).unwrap();
+
+        let paths: Vec<&str> = template.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ".github/workflows/ci.yml",
+                "bin/dev.tmpl",
+                "bin/setup.tmpl",
+                "logo.bin",
+                "src/{{ project }}.txt.tmpl"
+            ]
+        );
+        // Only .tmpl files are rendered, and they lose the suffix
+        assert!(template.files[0].text.is_none());
+        assert_eq!(template.files[1].target(), "bin/dev");
+        assert_eq!(template.files[2].mode, 0o750);
+        assert_eq!(template.empty_dirs, vec!["logs/{{ project }}"]);
+        assert_eq!(template.flags, vec![Action::Readme]);
+        assert_eq!(
+            template.permissions,
+            vec![("bin/dev".to_string(), Mode::Octal(0o755))]
+        );
+        assert_eq!(template.commands[0].program, "touch");
+    }
+
+    #[test]
+    fn test_invalid_manifest_is_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("bad");
+        fs::create_dir(&root).unwrap();
+
+        fs::write(root.join("makedir.toml"), "commands = [[]]\n").unwrap();
+        assert!(UserTemplate::load("bad", &root).is_err());
+
+        fs::write(root.join("makedir.toml"), "hooks = []\n").unwrap();
+        let err = UserTemplate::load("bad", &root).unwrap_err();
+        assert!(err.contains("unknown key `hooks`"));
+    }
+
+    #[test]
+    fn test_template_is_rendered_into_directory() {
+        let temp_dir = tempdir().unwrap();
+        write_template(temp_dir.path(), "svc");
+        let dir = temp_dir.path().join("billing");
+        let request = CreateRequest {
+            dirs: vec![dir.clone()],
+            author_name: Some("Ada".to_string()),
+            templates: vec![UserTemplate::load("svc", &temp_dir.path().join("svc")).unwrap()],
+            ..Default::default()
+        };
+
+        let report = execute(&Plan::new(&request).unwrap());
+        assert!(report.is_success());
+
+        assert_eq!(
+            fs::read_to_string(dir.join("bin/dev")).unwrap(),
+            "#!/bin/sh\necho billing\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("src/billing.txt")).unwrap(),
+            "by Ada\n"
+        );
+        assert_eq!(
+            fs::read(dir.join("logo.bin")).unwrap(),
+            [0xff, 0xfe, b'{', b'{']
+        );
+        let mode = fs::metadata(dir.join("bin/dev"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+        let mode = fs::metadata(dir.join("bin/setup"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(
+            fs::read_to_string(dir.join(".github/workflows/ci.yml")).unwrap(),
+            WORKFLOW
+        );
+        assert!(dir.join("logs/billing").is_dir());
+        assert!(!dir.join("bin/dev.tmpl").exists());
+        assert!(dir.join("billing.done").exists());
+        // The manifest's flags are added to the built-in steps
+        assert!(dir.join("README.md").exists());
+        assert!(!dir.join("makedir.toml").exists());
+    }
+
+    #[test]
+    fn test_render_errors_are_reported() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("broken");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("notes.md.tmpl"), "{{ nope }}").unwrap();
+
+        let request = CreateRequest {
+            dirs: vec![temp_dir.path().join("x")],
+            templates: vec![UserTemplate::load("broken", &root).unwrap()],
+            ..Default::default()
+        };
+        let err = Plan::new(&request).unwrap_err();
+        assert!(err.contains("template `broken`, notes.md.tmpl"));
+
+        fs::write(root.join("notes.md.tmpl"), [0xff, 0xfe]).unwrap();
+        let err = UserTemplate::load("broken", &root).unwrap_err();
+        assert!(err.contains("is not UTF-8"));
+    }
+
+    #[test]
+    fn test_rendered_paths_stay_inside_directory() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("escape");
+        fs::create_dir(&root).unwrap();
+        fs::write(
+            root.join("makedir.toml"),
+            "[permissions]\n\"../x\" = \"700\"\n",
+        )
+        .unwrap();
+
+        let request = CreateRequest {
+            dirs: vec![temp_dir.path().join("x")],
+            templates: vec![UserTemplate::load("escape", &root).unwrap()],
+            ..Default::default()
+        };
+        assert!(Plan::new(&request).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_lists_template_steps() {
+        let temp_dir = tempdir().unwrap();
+        write_template(temp_dir.path(), "svc");
+        let request = CreateRequest {
+            dirs: vec!["api".into()],
+            templates: vec![UserTemplate::load("svc", &temp_dir.path().join("svc")).unwrap()],
+            ..Default::default()
+        };
+        let plan = Plan::new(&request).unwrap();
+        assert!(plan.targets[0].steps.contains(&Step::SetMode {
+            name: "bin/dev".to_string(),
+            mode: Mode::Octal(0o755)
+        }));
+
+        let lines = makedir::dry_run::describe(&plan);
+        assert!(lines.contains(&"write api/src/api.txt (4 bytes)".to_string()));
+        assert!(lines.contains(&"set permissions 755 on api/bin/dev".to_string()));
+        assert!(lines.iter().any(|line| line.starts_with("copy ")));
+        assert!(lines.contains(&"run `touch api.done` in api".to_string()));
+        assert!(lines.contains(&"create directory api/logs/api".to_string()));
+    }
+
+    #[test]
+    fn test_cli_finds_config_and_local_templates() {
+        let temp_dir = tempdir().unwrap();
+        let config_home = temp_dir.path().join("config");
+        let work = temp_dir.path().join("work");
+        write_template(&config_home.join("makedir/templates"), "svc");
+        fs::write(
+            config_home.join("gitconfig"),
+            "[user]\n\tname = Ada Lovelace\n\temail = ada@example.com\n",
+        )
+        .unwrap();
+        let local = work.join(".makedir/templates/local");
+        fs::create_dir_all(&local).unwrap();
+        fs::write(local.join("NOTES.tmpl"), "{{ project }} notes\n").unwrap();
+
+        let output = run_makedir(
+            &work,
+            &config_home,
+            &["one", "--template", "svc", "--template", "local"],
+        );
+        assert!(output.status.success(), "{:?}", output);
+        assert!(work.join("one/bin/dev").exists());
+        assert_eq!(
+            fs::read_to_string(work.join("one/NOTES")).unwrap(),
+            "one notes\n"
+        );
+        // The author comes from git even without --readme or --license
+        assert_eq!(
+            fs::read_to_string(work.join("one/src/one.txt")).unwrap(),
+            "by Ada Lovelace <ada@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn test_cli_unknown_template_is_usage_error() {
+        let temp_dir = tempdir().unwrap();
+        let output = run_makedir(
+            temp_dir.path(),
+            temp_dir.path(),
+            &["two", "--template", "missing"],
+        );
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("unknown template `missing`"));
+        assert!(!temp_dir.path().join("two").exists());
+    }
+}
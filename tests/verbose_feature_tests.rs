@@ -1,7 +1,10 @@
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use makedir::cli::parse_args;
+use makedir::reporter::Verbosity;
 use tempfile::tempdir;
 
 #[cfg(test)]
@@ -14,6 +17,25 @@ mod tests {
         }
     }
 
+    fn git_available() -> bool {
+        Command::new("which")
+            .arg("git")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    fn run_makedir(args: &[&str]) -> Output {
+        Command::new("cargo")
+            .arg("run")
+            .arg("--quiet")
+            .arg("--")
+            .args(args)
+            .arg("--color=never")
+            .output()
+            .unwrap()
+    }
+
     #[test]
     fn test_verbose_flag_basic() {
         let temp_dir = tempdir().unwrap();
@@ -280,4 +302,109 @@ mod tests {
         let stdout = String::from_utf8_lossy(&output.stdout);
         assert!(stdout.contains("git") || stdout.contains("Git"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_verbosity_levels_are_parsed() {
+        let levels = |list: &[&str]| {
+            let args: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+            parse_args(&args).request.verbosity
+        };
+        assert_eq!(levels(&["a"]), Verbosity::Normal);
+        assert_eq!(levels(&["a", "-q"]), Verbosity::Quiet);
+        assert_eq!(levels(&["a", "--quiet"]), Verbosity::Quiet);
+        assert_eq!(levels(&["a", "-v"]), Verbosity::Steps);
+        assert_eq!(levels(&["a", "-vv"]), Verbosity::Output);
+        assert_eq!(levels(&["a", "-v", "--verbose"]), Verbosity::Output);
+        assert_eq!(levels(&["a", "-vvv"]), Verbosity::Debug);
+        assert_eq!(levels(&["a", "-vv", "-vv"]), Verbosity::Debug);
+
+        let args: Vec<String> = ["a", "-q", "-v"].iter().map(|s| s.to_string()).collect();
+        let parsed = parse_args(&args);
+        assert_eq!(parsed.errors, vec!["--quiet and --verbose cannot be combined"]);
+    }
+
+    #[test]
+    fn test_quiet_prints_only_errors() {
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("quiet_existing_dir");
+        fs::create_dir(&test_dir).unwrap();
+
+        let output = run_makedir(&[test_dir.to_str().unwrap(), "-q"]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+
+        // Errors still get through
+        let file = temp_dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let output = run_makedir(&[file.join("sub").to_str().unwrap(), "--quiet"]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Failed to create directory"));
+    }
+
+    #[test]
+    fn test_single_verbose_hides_initializer_output() {
+        if !git_available() {
+            println!("Skipping git test as git command is not available");
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("verbose_steps_git");
+
+        let output = run_makedir(&[test_dir.to_str().unwrap(), "--git", "-v"]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Successfully executed: git init"));
+        assert!(!stdout.contains("Initialized empty Git repository"));
+        assert!(!stdout.contains("debug:"));
+    }
+
+    #[test]
+    fn test_double_verbose_streams_initializer_output() {
+        if !git_available() {
+            println!("Skipping git test as git command is not available");
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let first = temp_dir.path().join("stream_one");
+        let second = temp_dir.path().join("stream_two");
+
+        let output = run_makedir(&[
+            first.to_str().unwrap(),
+            second.to_str().unwrap(),
+            "--git",
+            "-vv",
+        ]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Every line is prefixed with the directory it came from
+        for dir in [&first, &second] {
+            let line = format!("[{}] Initialized empty Git repository", dir.display());
+            assert!(stdout.contains(&line), "{} not in {}", line, stdout);
+        }
+        assert!(stdout.contains("Successfully executed: git init"));
+        assert!(!stdout.contains("debug:"));
+    }
+
+    #[test]
+    fn test_triple_verbose_shows_timings_and_argv() {
+        if !git_available() {
+            println!("Skipping git test as git command is not available");
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let test_dir = temp_dir.path().join("verbose_debug_git");
+
+        let output = run_makedir(&[test_dir.to_str().unwrap(), "--git", "-vvv"]);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // The program is resolved through PATH
+        assert!(stdout.contains("debug: argv [\"/"));
+        assert!(stdout.contains("git\", \"init\"] in"));
+        assert!(stdout.contains("debug: git init took "));
+        assert!(stdout.contains(&format!("debug: {} took ", test_dir.display())));
+        assert!(stdout.contains("Initialized empty Git repository"));
+    }
+}